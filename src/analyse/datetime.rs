@@ -0,0 +1,117 @@
+//日期与时间的解析和格式化。
+/*
+    date: 自1970-01-01起的天数。
+    time: 自当天零点起的秒数。
+    timestamp: 自1970-01-01 00:00:00起的秒数。
+*/
+use std::convert::TryFrom;
+
+pub const DAY_SECONDS:i64 = 86400;
+pub const MIN_DATE:i32 = -719162;  //0001-01-01
+pub const MAX_DATE:i32 = 2932896;  //9999-12-31
+
+fn days_from_civil(y:i64, m:i64, d:i64) -> i64 {
+    //公历日期转换为天数。
+    let y = if m <= 2 {y - 1}else{y};
+    let era = if y >= 0 {y}else{y - 399} / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 {m - 3}else{m + 9}) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+fn civil_from_days(z:i64) -> (i64, i64, i64) {
+    //天数转换为公历日期。
+    let z = z + 719468;
+    let era = if z >= 0 {z}else{z - 146096} / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 {mp + 3}else{mp - 9};
+    (if m <= 2 {yoe + era * 400 + 1}else{yoe + era * 400}, m, d)
+}
+fn days_in_month(y:i64, m:i64) -> i64 {
+    match m {
+        2 => if (y % 4 == 0 && y % 100 != 0) || y % 400 == 0 {29}else{28},
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+fn parse_parts(s:&str, sep:char, count:usize) -> Option<Vec<i64>> {
+    let mut ret = Vec::new();
+    for p in s.split(sep) {
+        if p.is_empty() || !p.chars().all(|c|('0'..='9').contains(&c)) {return Option::None;}
+        match p.parse() {
+            Result::Ok(v) => ret.push(v),
+            Result::Err(_) => {return Option::None;}
+        }
+    }
+    if ret.len() == count {Option::Some(ret)}else{Option::None}
+}
+
+pub fn parse_date(s:&str) -> Option<i32> {
+    //格式为YYYY-MM-DD。
+    let p = match parse_parts(s.trim(), '-', 3) {
+        Option::Some(p) => p,
+        Option::None => {return Option::None;}
+    };
+    if p[0] < 1 || p[0] > 9999 || p[1] < 1 || p[1] > 12 || p[2] < 1 || p[2] > days_in_month(p[0], p[1]) {
+        return Option::None;
+    }
+    Option::Some(days_from_civil(p[0], p[1], p[2]) as i32)
+}
+pub fn parse_time(s:&str) -> Option<i32> {
+    //格式为HH:MM:SS，秒可以省略。
+    let s = s.trim();
+    let p = match parse_parts(s, ':', 3) {
+        Option::Some(p) => p,
+        Option::None => match parse_parts(s, ':', 2) {
+            Option::Some(mut p) => {p.push(0); p},
+            Option::None => {return Option::None;}
+        }
+    };
+    if p[0] > 23 || p[1] > 59 || p[2] > 59 {return Option::None;}
+    Option::Some((p[0] * 3600 + p[1] * 60 + p[2]) as i32)
+}
+pub fn parse_timestamp(s:&str) -> Option<i64> {
+    //格式为YYYY-MM-DD HH:MM:SS，日期与时间之间也可以用T分隔。只有日期时取当天零点。
+    let s = s.trim();
+    let (date, time) = match s.find([' ', 'T']) {
+        Option::Some(i) => (&s[..i], Option::Some(&s[i+1..])),
+        Option::None => (s, Option::None)
+    };
+    let days = match parse_date(date) {
+        Option::Some(d) => d as i64,
+        Option::None => {return Option::None;}
+    };
+    let secs = match time {
+        Option::Some(t) => match parse_time(t) {
+            Option::Some(t) => t as i64,
+            Option::None => {return Option::None;}
+        },
+        Option::None => 0
+    };
+    Option::Some(days * DAY_SECONDS + secs)
+}
+pub fn to_date(days:i64) -> Option<i32> {
+    //运算得到的天数在可以表示的日期范围内时转换为date。
+    match i32::try_from(days) {
+        Result::Ok(d) if (MIN_DATE..=MAX_DATE).contains(&d) => Option::Some(d),
+        _ => Option::None
+    }
+}
+pub fn to_timestamp(secs:i64) -> Option<i64> {
+    if secs >= MIN_DATE as i64 * DAY_SECONDS && secs < (MAX_DATE as i64 + 1) * DAY_SECONDS {Option::Some(secs)}else{Option::None}
+}
+pub fn format_date(days:i32) -> String {
+    let (y, m, d) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+pub fn format_time(secs:i32) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+pub fn format_timestamp(secs:i64) -> String {
+    let days = if secs >= 0 {secs / DAY_SECONDS}else{(secs - DAY_SECONDS + 1) / DAY_SECONDS};
+    format!("{} {}", format_date(days as i32), format_time((secs - days * DAY_SECONDS) as i32))
+}
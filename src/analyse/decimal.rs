@@ -0,0 +1,103 @@
+//定点小数的解析、格式化与运算。
+/*
+    定点小数保存为(不带小数点的整数值, 小数位数)，例如12.50保存为(1250, 2)。
+    整数值为i64，因此总位数最多为MAX_PRECISION；运算的中间结果用i128计算，超出范围时返回None。
+*/
+use std::cmp::Ordering;
+
+pub const MAX_PRECISION:usize = 18;
+pub const DIVIDE_SCALE:usize = 6; //除法的结果在两侧较大的小数位数上再多保留的位数
+
+fn pow10(n:usize) -> i128 {
+    let mut ret:i128 = 1;
+    for _ in 0..n {ret *= 10;}
+    ret
+}
+fn narrow(v:i128) -> Option<i64> {
+    if v > i64::max_value() as i128 || v < i64::min_value() as i128 {Option::None}else{Option::Some(v as i64)}
+}
+fn round_div(a:i128, b:i128) -> i128 {
+    //四舍五入的整数除法，0.5向远离0的方向进位。
+    let q = a / b;
+    let r = a % b;
+    if r.abs() * 2 >= b.abs() {
+        if (a < 0) != (b < 0) {q - 1}else{q + 1}
+    }else{q}
+}
+
+pub fn parse(s:&str) -> Option<(i64, usize)> {
+    //格式为[+-]整数部分[.小数部分]，返回值的小数位数就是给出的小数位数。
+    let s = s.trim();
+    let (neg, s) = if s.starts_with('-') {(true, &s[1..])}else if s.starts_with('+') {(false, &s[1..])}else{(false, s)};
+    let (int, frac) = match s.find('.') {
+        Option::Some(i) => (&s[..i], &s[i+1..]),
+        Option::None => (s, "")
+    };
+    if int.len() + frac.len() == 0 || !int.chars().chain(frac.chars()).all(|c|('0'..='9').contains(&c)) {
+        return Option::None;
+    }
+    if frac.len() > MAX_PRECISION {return Option::None;}
+    let mut v:i128 = 0;
+    for c in int.chars().chain(frac.chars()) {
+        v = v * 10 + (c as i128 - '0' as i128);
+        if v > i64::max_value() as i128 {return Option::None;}
+    }
+    Option::Some((if neg {-v as i64}else{v as i64}, frac.len()))
+}
+pub fn format(v:i64, scale:usize) -> String {
+    let neg = v < 0;
+    let digits = (v as i128).abs().to_string();
+    let digits = if digits.len() <= scale {format!("{}{}", "0".repeat(scale + 1 - digits.len()), digits)}else{digits};
+    let (int, frac) = digits.split_at(digits.len() - scale);
+    format!("{}{}{}{}", if neg {"-"}else{""}, int, if scale > 0 {"."}else{""}, frac)
+}
+pub fn rescale(v:i64, from:usize, to:usize) -> Option<i64> {
+    //改变小数位数。减少位数时四舍五入。
+    if to >= from {
+        narrow(v as i128 * pow10(to - from))
+    }else{
+        narrow(round_div(v as i128, pow10(from - to)))
+    }
+}
+pub fn fits(v:i64, precision:usize) -> bool {
+    //整数值的位数不超过precision。
+    (v as i128).abs() < pow10(precision)
+}
+pub fn cmp(a:(i64, usize), b:(i64, usize)) -> Ordering {
+    let s = if a.1 > b.1 {a.1}else{b.1};
+    (a.0 as i128 * pow10(s - a.1)).cmp(&(b.0 as i128 * pow10(s - b.1)))
+}
+pub fn operate(a:(i64, usize), b:(i64, usize), oper:&str) -> Option<(i64, usize)> {
+    //加减与取余按两侧较大的小数位数进行；乘法的小数位数相加，超过MAX_PRECISION时四舍五入；
+    //除法多保留DIVIDE_SCALE位小数。除数为0或结果超出范围时返回None。
+    let s = if a.1 > b.1 {a.1}else{b.1};
+    let x = a.0 as i128 * pow10(s - a.1);
+    let y = b.0 as i128 * pow10(s - b.1);
+    let (v, scale) = match oper {
+        "+" => (x + y, s),
+        "-" => (x - y, s),
+        "%" => {
+            if y == 0 {return Option::None;}
+            (x % y, s)
+        },
+        "*" => {
+            let scale = a.1 + b.1;
+            let v = match (a.0 as i128).checked_mul(b.0 as i128) {
+                Option::Some(v) => v,
+                Option::None => {return Option::None;}
+            };
+            if scale > MAX_PRECISION {(round_div(v, pow10(scale - MAX_PRECISION)), MAX_PRECISION)}else{(v, scale)}
+        },
+        "/" => {
+            if y == 0 {return Option::None;}
+            let scale = if s + DIVIDE_SCALE > MAX_PRECISION {MAX_PRECISION}else{s + DIVIDE_SCALE};
+            let v = match x.checked_mul(pow10(scale)) {
+                Option::Some(v) => v,
+                Option::None => {return Option::None;}
+            };
+            (round_div(v, y), scale)
+        },
+        _ => {return Option::None;}
+    };
+    narrow(v).map(|v|(v, scale))
+}
@@ -417,7 +417,9 @@ impl FiniteAutomaton{
                             "integer", "float", "bool", "auto_increment",
                             "foreign", "key", "reference", "primary", "unique", "not_null", "default",
                             "add", "drop", "user", "adminuser", "grant", "revoke", "privileges", "on", "to",
-                            "password", "with", "view"
+                            "password", "with", "view", "index", "begin", "commit", "rollback",
+                            "vacuum", "in", "join", "inner", "left", "right", "outer", "limit", "offset",
+                            "check", "constraint"
                         ];
                         let bool_list = [
                             "true", "false"
@@ -1,2 +1,5 @@
 pub mod utils;
-pub mod dfa;
\ No newline at end of file
+pub mod dfa;
+pub mod datetime;
+pub mod decimal;
+pub mod password;
@@ -0,0 +1,115 @@
+//用户密码的散列与校验。
+/*
+    保存的格式为"算法$迭代次数$盐$散列值"，盐与散列值是十六进制字符串，例如
+        pbkdf2-sha256$100000$<32位十六进制>$<64位十六进制>
+    开头的算法标记用于以后更换算法：校验时按保存的算法与参数计算，不认识的格式视作旧的明文密码。
+    散列使用pbkdf2与sha2库计算，校验时用subtle库做常数时间的比较。
+*/
+extern crate pbkdf2;
+extern crate sha2;
+extern crate subtle;
+use self::pbkdf2::{pbkdf2_hmac};
+use self::sha2::{Sha256};
+use self::subtle::{ConstantTimeEq};
+use std::fs::{File};
+use std::io::{Read};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const ALGORITHM:&str = "pbkdf2-sha256";
+pub const ITERATIONS:u32 = 100000;
+const SALT_LEN:usize = 16;
+
+pub fn pbkdf2_sha256(password:&[u8], salt:&[u8], iterations:u32) -> [u8; 32] {
+    //输出长度就是SHA-256的长度。
+    let mut ret = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut ret);
+    ret
+}
+
+fn to_hex(b:&[u8]) -> String {
+    b.iter().map(|i|format!("{:02x}", i)).collect()
+}
+fn from_hex(s:&str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {return Option::None;}
+    let mut ret = Vec::with_capacity(s.len() / 2);
+    for i in 0..s.len() / 2 {
+        match u8::from_str_radix(&s[i * 2..i * 2 + 2], 16) {
+            Result::Ok(b) => ret.push(b),
+            Result::Err(_) => {return Option::None;}
+        }
+    }
+    Option::Some(ret)
+}
+fn new_salt() -> Vec<u8> {
+    //优先使用系统的随机数；没有/dev/urandom的平台用随机种子的散列与当前时间生成。
+    let mut salt = vec![0u8; SALT_LEN];
+    if let Result::Ok(mut f) = File::open("/dev/urandom") {
+        if f.read_exact(&mut salt).is_ok() {return salt;}
+    }
+    let nanos = match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Result::Ok(d) => d.as_secs() ^ (d.subsec_nanos() as u64) << 32,
+        Result::Err(_) => 0
+    };
+    for (i, chunk) in salt.chunks_mut(8).enumerate() {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(nanos);
+        hasher.write_usize(i);
+        let v = hasher.finish();
+        for (j, b) in chunk.iter_mut().enumerate() {*b = (v >> (j * 8)) as u8;}
+    }
+    salt
+}
+
+pub fn hash(password:&str) -> String {
+    let salt = new_salt();
+    let key = pbkdf2_sha256(password.as_bytes(), &salt, ITERATIONS);
+    format!("{}${}${}${}", ALGORITHM, ITERATIONS, to_hex(&salt), to_hex(&key))
+}
+pub fn is_hashed(stored:&str) -> bool {
+    stored.starts_with(&format!("{}$", ALGORITHM))
+}
+pub fn verify(password:&str, stored:&str) -> bool {
+    //按保存的算法校验密码。不是散列格式的值按旧的明文密码比较。
+    if !is_hashed(stored) {
+        return password.as_bytes().ct_eq(stored.as_bytes()).into();
+    }
+    let v:Vec<&str> = stored.split('$').collect();
+    if v.len() != 4 {return false;}
+    let (iterations, salt, key) = match (v[1].parse::<u32>(), from_hex(v[2]), from_hex(v[3])) {
+        (Result::Ok(i), Option::Some(s), Option::Some(k)) if i > 0 && k.len() == 32 => (i, s, k),
+        _ => {return false;}
+    };
+    let now = pbkdf2_sha256(password.as_bytes(), &salt, iterations);
+    //常数时间的比较，耗时与不相同的位置无关。
+    now.ct_eq(&key[..]).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pbkdf2_known_answers() {
+        assert_eq!(to_hex(&pbkdf2_sha256(b"password", b"salt", 1)), "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b");
+        assert_eq!(to_hex(&pbkdf2_sha256(b"password", b"salt", 2)), "ae4d0c95af6b46d32d0adff928f06dd02a303f8ef3c251dfd6e2d85a95474c43");
+        assert_eq!(to_hex(&pbkdf2_sha256(b"password", b"salt", 4096)), "c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a");
+        //超过HMAC块长度的密码
+        assert_eq!(to_hex(&pbkdf2_sha256(&[b'k'; 100], b"NaCl", 3)), "c59d6ebf62a532411bd5d636667cdf91ccdf6ccb1049fc1e6df8601b0c8c8595");
+    }
+    #[test]
+    fn hash_and_verify() {
+        let stored = hash("secret");
+        assert!(is_hashed(&stored));
+        assert!(verify("secret", &stored));
+        assert!(!verify("Secret", &stored));
+        //同一个密码每次的盐不同
+        assert!(stored != hash("secret"));
+        //旧版本的明文密码
+        assert!(verify("root", "root"));
+        assert!(!verify("root", "toor"));
+        //损坏的散列值不能通过校验
+        assert!(!verify("secret", "pbkdf2-sha256$0$00$00"));
+    }
+}
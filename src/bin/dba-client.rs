@@ -0,0 +1,82 @@
+extern crate dba;
+use std::io::{self, Write};
+use std::env;
+use dba::engine::ini::{Config};
+use dba::net::client::{Client};
+
+fn get_env(conf:&Config) -> (String, String, String, String) {
+    //依次为：(--host:--port)address, (-u)user, (-p)passwd, (-d)database.
+    let argument:Vec<String> = env::args().collect();
+    let mut host = conf.host.to_string();
+    let mut port = conf.port.to_string();
+    let mut user = "";
+    let mut pw = "";
+    let mut db = "";
+    let mut i = 1;
+    while i + 1 < argument.len() {
+        let s = &argument[i].to_lowercase();
+        if s == "--host" {
+            host = argument[i+1].to_string();
+        }else if s == "--port" {
+            port = argument[i+1].to_string();
+        }else if s == "-u" && user == "" {
+            user = &argument[i+1];
+        }else if s == "-p" && pw == "" {
+            pw = &argument[i+1];
+        }else if s == "-d" && db == "" {
+            db = &argument[i+1];
+        }
+        i += 1;
+    }
+    (format!("{}:{}", host, port), user.to_string(), pw.to_string(), db.to_string())
+}
+fn read_line(prompt:&str) -> String {
+    let stdout = io::stdout();
+    let mut handle = stdout.lock();
+    handle.write(prompt.as_bytes()).unwrap();
+    handle.flush().unwrap();
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).ok();
+    input.trim().to_string()
+}
+fn main() {
+    let conf = Config::load("dba.ini");
+    let (addr, mut user, mut password, db_name) = get_env(&conf);
+    if user == "" || password == "" {
+        user = read_line("Login as:");
+        password = read_line("Password:");
+    }
+    let stdout = io::stdout();
+    let stdin = io::stdin();
+    let mut client = match Client::connect(&addr, &user, &password, if db_name != "" {Option::Some(&db_name)}else{Option::None}) {
+        Result::Ok(ok) => ok,
+        Result::Err(e) => {
+            stdout.lock().write(format!("{}", e).as_bytes()).unwrap();
+            return;
+        }
+    };
+    loop {
+        {
+            let mut handle = stdout.lock();
+            handle.write(format!("\n{}[{}]>",
+                client.get_username(),
+                if let Option::Some(ref s) = client.get_using_database(){s}else{"None"}
+            ).as_bytes()).unwrap();
+            handle.flush().unwrap();
+        }
+        let mut input = String::new();
+        match stdin.read_line(&mut input) {
+            Result::Ok(n) if n > 0 && input.to_lowercase().trim() != "exit" => {
+                match client.execute(&input) {
+                    Result::Ok(response) => response.print(&mut stdout.lock()),
+                    Result::Err(e) => {stdout.lock().write(e.as_bytes()).unwrap();}
+                }
+            },
+            _ => {
+                client.close();
+                stdout.lock().write(format!("Bye.").as_bytes()).unwrap();
+                break;
+            }
+        }
+    }
+}
@@ -0,0 +1,51 @@
+extern crate dba;
+use std::env;
+use std::net::{TcpListener};
+use std::sync::{Arc};
+use dba::engine::engine::{Engine};
+use dba::engine::ini::{Config};
+use dba::net::server::{serve};
+
+fn get_env(conf:&Config) -> (String, u16) {
+    //监听的地址与端口默认来自dba.ini，可以用--host/--port覆盖。
+    let argument:Vec<String> = env::args().collect();
+    let mut host = conf.host.to_string();
+    let mut port = conf.port;
+    let mut i = 1;
+    while i + 1 < argument.len() {
+        let s = argument[i].to_lowercase();
+        if s == "--host" {
+            host = argument[i+1].to_string();
+        }else if s == "--port" {
+            port = argument[i+1].parse().unwrap_or(port);
+        }
+        i += 1;
+    }
+    (host, port)
+}
+fn main() {
+    let conf = Config::load("dba.ini");
+    let (host, port) = get_env(&conf);
+    let engine = match Engine::new() {
+        Result::Ok(ok) => Arc::new(ok),
+        Result::Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let listener = match TcpListener::bind((host.as_str(), port)) {
+        Result::Ok(l) => l,
+        Result::Err(e) => {
+            println!("Cannot listen on {}:{}: {}.", host, port, e);
+            return;
+        }
+    };
+    println!("Listening on {}:{}.", host, port);
+    //协议没有加密，密码与数据都以明文传输。监听的不是本机地址时提醒一下。
+    if let Result::Ok(addr) = listener.local_addr() {
+        if !addr.ip().is_loopback() {
+            println!("WARNING: the protocol is not encrypted. Passwords and data are sent in cleartext to anyone on the network.");
+        }
+    }
+    serve(engine, &listener);
+}
@@ -1,16 +1,23 @@
 use super::save::{
     ConfigFile, SaveFile, DataItem, TableConfig, Data, PageType,
-    FieldConfig, ForeignConfig, FieldType, ForeignType
+    FieldConfig, ForeignConfig, FieldType, ForeignType,
+    IndexConfig, IndexKey, IndexTree, DATA_VERSION, PAGE_SIZE, INLINE_SIZE, SHARED_MAX
 };
 use std::io::{Write};
+use std::ops::{Deref, DerefMut};
 use std::cmp::Ordering;
-use std::collections::{HashMap, HashSet};
+use std::cell::{Cell};
+use std::collections::{HashMap, HashSet, BTreeMap, BTreeSet};
+use std::collections::Bound::{Included, Excluded, Unbounded};
 use super::super::syntax::structures::{
-    Switch, Expression, ExpressionType, SelectSyntax, InsertSyntax, DeleteSyntax, UpdateSyntax,
+    Switch, Expression, ExpressionType, SelectSyntax, JoinSyntax, InsertSyntax, DeleteSyntax, UpdateSyntax,
     CreateTableSyntax, AlterTableSyntax, DropTableSyntax, HelpSyntax,
-    CreateViewSyntax, DropViewSyntax
+    CreateViewSyntax, DropViewSyntax, CreateIndexSyntax, DropIndexSyntax, VacuumSyntax
 };
 use super::super::analyse::dfa::{DfaWord};
+use super::super::analyse::decimal;
+
+const SCAN_BATCH:usize = 256; //select带有limit时，每批读取的记录数。
 //= 工具 ==============================================
 fn has<T, F>(v:&Vec<T>, t:F) -> bool where F: Fn(&T) -> bool {
     for i in v.iter() {
@@ -18,11 +25,316 @@ fn has<T, F>(v:&Vec<T>, t:F) -> bool where F: Fn(&T) -> bool {
     }
     return false;
 }
+fn bind_outer<F>(sub:&SelectSyntax, locals:&[String], resolve:&F) -> SelectSyntax
+    where F: Fn(&[String]) -> Result<ExpressionType, String> {
+    //把子查询中引用外层表的变量替换为外层当前记录的值。
+    //locals是子查询以及它外面各层子查询自己的来源名；不属于这些来源、外层又能取到值的"表名.列名"才会被替换。
+    let mut locals:Vec<String> = locals.to_vec();
+    for name in sub.froms.keys() {locals.push(name.to_string());}
+    for j in sub.joins.iter() {locals.push(j.name.to_string());}
+    let mut ret = sub.copy();
+    for goal in ret.goals.iter_mut() {goal.1 = bind_expression(&goal.1, &locals, resolve);}
+    for join in ret.joins.iter_mut() {join.on = bind_expression(&join.on, &locals, resolve);}
+    ret.wheres = bind_expression(&ret.wheres, &locals, resolve);
+    for group in ret.groups.iter_mut() {*group = bind_expression(group, &locals, resolve);}
+    ret.havings = bind_expression(&ret.havings, &locals, resolve);
+    ret
+}
+fn bind_expression<F>(expression:&Expression, locals:&[String], resolve:&F) -> Expression
+    where F: Fn(&[String]) -> Result<ExpressionType, String> {
+    let mut li = Vec::new();
+    for e in expression.li.iter() {
+        li.push(match e {
+            ExpressionType::Var(prop) if prop.len() == 2 && !locals.contains(&prop[0]) => {
+                match resolve(&prop[..]) {
+                    Result::Ok(value) => value,
+                    Result::Err(_) => e.copy()
+                }
+            },
+            ExpressionType::Select(sub) => ExpressionType::Select(Box::new(bind_outer(sub, locals, resolve))),
+            _ => e.copy()
+        });
+    }
+    Expression{li, setence: expression.setence.to_string()}
+}
+fn join_key(e:&ExpressionType) -> Option<String> {
+    //哈希连接的键。整数与整值的浮点数视作相同；null不与任何值匹配。
+    match e {
+        &ExpressionType::Integer(i) => Option::Some(format!("n:{}", i)),
+        &ExpressionType::Float(f) if f.fract() == 0.0 && f.abs() < 9e15 => Option::Some(format!("n:{}", f as i64)),
+        &ExpressionType::Float(f) => Option::Some(format!("f:{}", f)),
+        ExpressionType::Str(s) => Option::Some(format!("s:{}", s)),
+        &ExpressionType::Bool(b) => Option::Some(format!("b:{}", b)),
+        &ExpressionType::Date(d) => Option::Some(format!("d:{}", d)),
+        &ExpressionType::Time(t) => Option::Some(format!("t:{}", t)),
+        &ExpressionType::Timestamp(t) => Option::Some(format!("ts:{}", t)),
+        &ExpressionType::Decimal(v, s) => {
+            //去掉小数末尾的0之后与整数、浮点数的键一致。
+            let text = decimal::format(v, s);
+            let text = if s > 0 {text.trim_end_matches('0').trim_end_matches('.').to_string()}else{text};
+            if text.contains('.') {Option::Some(format!("f:{}", text))}else{Option::Some(format!("n:{}", text))}
+        },
+        _ => Option::None
+    }
+}
+fn overflow_of(row:&Data) -> Vec<usize> {
+    //记录中text/blob字段已经写入溢出页的内容的地址。
+    row.li.iter().filter_map(|i|match i {
+        &DataItem::Text(p, _) | &DataItem::Blob(p, _) => p,
+        _ => Option::None
+    }).collect()
+}
+fn null_data(len:usize) -> Data {
+    //外连接中没有匹配时用来填充的一行null。
+    let mut li = Vec::new();
+    for _ in 0..len {li.push(DataItem::Null(FieldType::Str(0)));}
+    Data::new(li)
+}
+fn get_resource_value(origin:&[(String, DTable)], resource:&HashMap<String, Data>, prop:&[String]) -> Result<ExpressionType, String> {
+    //在select的一行组合记录中取得变量的值。只有一个来源时可以省略表名。
+    let get_table_field_index = |table:&str, field:&str| {
+        for (s, d) in origin.iter() {
+            if s == table {
+                for (i, d) in d.head.iter().enumerate() {
+                    if d == field {return i as i64;}
+                }
+            }
+        }
+        -1
+    };
+    if prop.len() == 1 {
+        if resource.len() > 1 {
+            Result::Err("Syntax error: please give a name for table when there are more tables.".to_string())
+        }else if resource.is_empty() {
+            Result::Err("Syntax error: no any table.".to_string())
+        }else {
+            let mut name = "";
+            let mut data = &Data{li:vec![]}; //获得该tabledata的name/data list。
+            for (k, v) in resource.iter() {name=k;data=v;}
+            let propname = &prop[0]; // 需要取得的属性名
+            //需要根据属性名，从origin中匹配数据列。
+            let index = get_table_field_index(name, propname);
+            if index >= 0 {
+                Result::Ok(data.li[index as usize].to_expt())
+            }else{
+                Result::Err(format!("Field {} not found.", propname))
+            }
+        }
+    }else if prop.is_empty() {
+        Result::Err("Syntax error: no prop name.".to_string())
+    }else{ //有表引用
+        let tablename = &prop[0];
+        let propname = &prop[1];
+        let index = get_table_field_index(tablename, propname);
+        if let Option::Some(data) = resource.get(tablename) {
+            if index >= 0 {
+                Result::Ok(data.li[index as usize].to_expt())
+            }else{
+                Result::Err(format!("Field {} not found.", propname))
+            }
+        }else{
+            Result::Err(format!("Table {} not found.", tablename))
+        }
+    }
+}
+fn get_group_value(origin:&[(String, DTable)], rows:&[&HashMap<String, Data>], prop:&[String]) -> Result<ExpressionType, String> {
+    //分组的字段在组内的值都相同，取第一条记录的值。空的组中取值为null。
+    match rows.first() {
+        Option::Some(resource) => get_resource_value(origin, resource, prop),
+        Option::None => Result::Ok(ExpressionType::Null)
+    }
+}
+fn is_grouped(prop:&[String], groups:&[Expression]) -> bool {
+    //变量是否是某个分组字段。只有一方带表名时按字段名比较。
+    for g in groups.iter() {
+        if g.li.len() != 1 {continue;}
+        if let ExpressionType::Var(ref v) = g.li[0] {
+            if v[..] == prop[..] {return true;}
+            if (v.len() == 1 || prop.len() == 1) && v.last() == prop.last() {return true;}
+        }
+    }
+    false
+}
+fn get_field_value(table:&str, head:&[String], data:&Data, prop:&[String]) -> Result<ExpressionType, String> {
+    //在单表的一条记录中取得变量的值，用于update/delete。可以用表名限定，以便子查询引用被修改的表。
+    if prop.len() == 1 || (prop.len() == 2 && prop[0] == table) {
+        let propname = &prop[prop.len() - 1];
+        for (i, s) in head.iter().enumerate() {
+            if s == propname {
+                return Result::Ok(data.li[i].to_expt());
+            }
+        }
+        Result::Err(format!("Field {} not found.", propname))
+    }else{
+        Result::Err("Syntax error: update syntax donot allow mutli tables.".to_string())
+    }
+}
+fn table_keys(conf:&TableConfig) -> Vec<(String, Vec<usize>)> {
+    //表上需要查重的字段组合：主键、字段上的unique与组合unique。返回(违反时的错误信息, 字段下标列表)。
+    let pos = |names:&[String]| -> Vec<usize> {
+        names.iter().filter_map(|n|conf.fields.iter().position(|f|f.name == *n)).collect()
+    };
+    let mut ret = Vec::new();
+    if !conf.primary.is_empty() {
+        ret.push(("Primary constriant is not satisfied.".to_string(), pos(&conf.primary)));
+    }
+    for (i, f) in conf.fields.iter().enumerate() {
+        if f.unique && conf.primary[..] != [f.name.to_string()] {
+            ret.push((format!("Unique constriant ({}) is not satisfied.", f.name), vec![i]));
+        }
+    }
+    for u in conf.uniques.iter() {
+        ret.push((format!("Unique constriant ({}) is not satisfied.", u.join(", ")), pos(u)));
+    }
+    ret
+}
+fn key_of(row:&Data, key:&[usize]) -> Option<Vec<String>> {
+    //记录在一个字段组合上的值，用于查重。含有null时返回None。
+    let mut ret = Vec::new();
+    for &k in key.iter() {
+        match join_key(&row.li[k].to_expt()) {
+            Option::Some(s) => ret.push(s),
+            Option::None => {return Option::None;}
+        }
+    }
+    Option::Some(ret)
+}
+fn key_valid(head:&[String], key:&[String]) -> Result<(), String> {
+    //primary key (...)与unique (...)中的字段必须存在且不能重复。空列表表示没有声明。
+    for (i, f) in key.iter().enumerate() {
+        if ! head.contains(f) {
+            return Result::Err(format!("Field {} not found.", f));
+        }
+        if key[..i].contains(f) {
+            return Result::Err(format!("Field {} is repeated in key.", f));
+        }
+    }
+    Result::Ok(())
+}
+fn check_valid(head:&[String], checks:&[(String, Expression)]) -> Result<(), String> {
+    //check约束只能引用本表的字段，不能包含子查询与聚合函数，约束名不能重复。
+    for (i, (name, e)) in checks.iter().enumerate() {
+        if checks[..i].iter().any(|c|c.0 == *name) {
+            return Result::Err(format!("Check constraint {} is already exists.", name));
+        }
+        if !e.sub_selects().is_empty() || e.has_aggregate() {
+            return Result::Err(format!("Check constraint {} cannot use subqueries or aggregate functions.", name));
+        }
+        for item in e.li.iter() {
+            if let ExpressionType::Var(v) = item {
+                let field = &v[v.len() - 1];
+                if ! head.iter().any(|h|h == field) {
+                    return Result::Err(format!("Field {} not found.", field));
+                }
+            }
+        }
+    }
+    Result::Ok(())
+}
+fn name_checks(table:&str, existing:&[(String, Expression)], adds:&[(String, Expression)]) -> Vec<(String, Expression)> {
+    /*  没有给出名称的check约束按引用的字段命名：只引用一个字段时为"表_字段_check"，否则为"表_check"。
+        与已有的约束、同一语句中给出的约束名或之前生成的名称重复时，在后面加上编号。
+    */
+    let mut taken:Vec<String> = existing.iter().chain(adds.iter()).filter(|c|!c.0.is_empty()).map(|c|c.0.to_string()).collect();
+    let mut ret = Vec::new();
+    for (name, e) in adds.iter() {
+        if !name.is_empty() {
+            ret.push((name.to_string(), e.copy()));
+            continue;
+        }
+        let mut fields:Vec<String> = Vec::new();
+        for item in e.li.iter() {
+            if let ExpressionType::Var(v) = item {
+                if ! fields.contains(&v[v.len() - 1]) {fields.push(v[v.len() - 1].to_string());}
+            }
+        }
+        let base = if fields.len() == 1 {format!("{}_{}_check", table, fields[0])}else{format!("{}_check", table)};
+        let mut name = base.to_string();
+        let mut n = 1;
+        while taken.contains(&name) {
+            name = format!("{}{}", base, n);
+            n += 1;
+        }
+        taken.push(name.to_string());
+        ret.push((name, e.copy()));
+    }
+    ret
+}
+
+//= 外键动作的执行计划 =================================
+struct ForeignPlan {
+    tables: HashMap<String, DTable>,  //已经读取的表数据
+    deletes: HashMap<String, BTreeSet<usize>>,  //将被删除的记录
+    changes: HashMap<String, BTreeMap<usize, Data>>,  //将被改写的记录及其新内容
+    restricts: Vec<(String, usize, usize, DataItem)>,  //restrict需要最后确认的记录：(表, 行号, 外键字段下标, 被引用的值)
+    affected: HashSet<(String, usize)>  //被外键动作波及的记录
+}
+impl ForeignPlan {
+    fn new() -> Self {
+        Self {
+            tables: HashMap::new(),
+            deletes: HashMap::new(),
+            changes: HashMap::new(),
+            restricts: Vec::new(),
+            affected: HashSet::new()
+        }
+    }
+    fn is_deleted(&self, table:&str, seek:usize) -> bool {
+        match self.deletes.get(table) {
+            Option::Some(set) => set.contains(&seek),
+            Option::None => false
+        }
+    }
+    fn current(&self, table:&str, seek:usize) -> &Data {
+        //计划中记录的当前内容：改写过的取新内容。
+        if let Option::Some(rows) = self.changes.get(table) {
+            if let Option::Some(row) = rows.get(&seek) {return row;}
+        }
+        &self.tables[table].content[seek]
+    }
+    fn dependents(&self, table:&str, seeks:&[usize]) -> usize {
+        //语句直接作用的记录之外，被波及的记录数量。
+        let mut count = 0;
+        for &(ref t, s) in self.affected.iter() {
+            if t != table || !seeks.contains(&s) {count += 1;}
+        }
+        count
+    }
+}
 
 //= 数据库会话实体 =====================================
+pub enum ConfRef<'t> {
+    //会话使用的配置。只读的会话只能执行查询，试图修改配置时是程序的错误。
+    Read(&'t ConfigFile),
+    Write(&'t mut ConfigFile)
+}
+impl<'t> ConfRef<'t> {
+    pub fn is_writable(&self) -> bool {
+        if let &ConfRef::Write(_) = self {true}else{false}
+    }
+}
+impl<'t> Deref for ConfRef<'t> {
+    type Target = ConfigFile;
+    fn deref(&self) -> &ConfigFile {
+        match self {
+            &ConfRef::Read(c) => c,
+            ConfRef::Write(c) => c
+        }
+    }
+}
+impl<'t> DerefMut for ConfRef<'t> {
+    fn deref_mut(&mut self) -> &mut ConfigFile {
+        match self {
+            &mut ConfRef::Write(ref mut c) => c,
+            &mut ConfRef::Read(_) => {panic!("ALERT:WRITE IN READ SESSION.");}
+        }
+    }
+}
 pub struct Database<'t> {
-    pub conf: &'t mut ConfigFile,
-    pub file: SaveFile
+    pub conf: ConfRef<'t>,
+    pub file: SaveFile,
+    pub loaded: HashMap<String, IndexTree>,  //只读的会话加载的索引。语句结束后交给SharedConfig放入共享的缓存
+    pub subqueries: HashMap<String, ExpressionType>  //本条语句中不相关子查询的结果
 }
 impl<'t> Database<'t> {
     fn get_table_sub(&mut self, syntax:&SelectSyntax) -> DResult {
@@ -30,19 +342,46 @@ impl<'t> Database<'t> {
         //     println!("GOAL[{}]:{}", i, j.to_string());
         // }
         /*select的语序：
-            1. 提取froms的名单。
-            2. 构造笛卡儿积
+            1. 提取froms和joins的名单。
+            2. 构造笛卡儿积，再依次执行join
             3. 按照where的条件过滤记录
-            4. 按照order的条件执行排序
-            5. 按照goal的表达式返回列
-            6. 如果有必要，就去重
+            4. 按照order的条件执行排序(有分组时在第5步之后排序)
+            5. 如果有group by或聚合函数，就分组，并按having过滤分组
+            6. 按照goal的表达式返回列
+            7. 如果有必要，就去重
+            8. 按照limit/offset截取结果
         */
         // 1.
         let mut origin:Vec<(String, DTable)> = Vec::new(); //所有的源数据都会被提取
-        
+        let conditions = syntax.wheres.split_and(); //where中以&&连接的各个条件，用于匹配索引。
+        //没有join、排序、分组和去重时，结果的前offset+limit行在扫描中就能确定，得到足够的行后即可结束扫描。
+        //有join时where在join之后才过滤，扫描中无法知道哪些行会留下。
+        let want = if syntax.joins.is_empty() && syntax.orders.is_empty() && !syntax.distinct && syntax.groups.is_empty() && syntax.havings.li.is_empty() &&
+            !syntax.goals.iter().any(|(_, g)|g.has_aggregate()) {
+            syntax.limit.map(|l|l + syntax.offset)
+        }else{Option::None};
+        let single = syntax.froms.len() == 1 && syntax.joins.is_empty();
+        let mut batch:Option<(String, usize, usize)> = Option::None; //分批读取的表名，已读取的行数，总行数。
         for (name, switch) in syntax.froms.iter() {
             let res = match switch {
-                &Switch::One(ref s) => self.get_table_name(s),
+                Switch::One(s) => {
+                    //如果where中存在可以使用索引的条件，就只读取索引命中的记录。其余条件仍在后面逐条判断。
+                    match self.get_index_candidates(name, s, &conditions, single) {
+                        Option::Some(seeks) => self.get_table_seeks(s, &seeks),
+                        Option::None => if want.is_some() && single && self.conf.tables.contains_key(s) {
+                            //只有一张表且可以提前结束时，分批读取记录，第一批至少包含所需的行数。
+                            let count = self.conf.tables[s].count;
+                            let mut read = want.unwrap();
+                            if read < SCAN_BATCH {read = SCAN_BATCH;}
+                            if read > count {read = count;}
+                            batch = Option::Some((s.to_string(), read, count));
+                            let seeks:Vec<usize> = (0..read).collect();
+                            self.get_table_seeks(s, &seeks)
+                        }else{
+                            self.get_table_name(s)
+                        }
+                    }
+                },
                 &Switch::Two(ref s) => self.get_table_sub(s)
             };
             if let DResult::Table(t) = res {
@@ -51,13 +390,20 @@ impl<'t> Database<'t> {
                 return res;
             } 
         }
+        let base = origin.len(); //froms的数量。其后是各个join的表。
+        for join in syntax.joins.iter() {
+            match self.get_table_name(&join.table) {
+                DResult::Table(t) => {origin.push((join.name.to_string(), t));},
+                res => {return res;}
+            }
+        }
         //println!("origin={}", origin.len());
         let mut stack:Vec<i64> = Vec::new();  //暂存元组与索引。
-        for _ in 0..origin.len() {stack.push(-1);}
+        for _ in 0..base {stack.push(-1);}
         let mut result:Vec<HashMap<String, Data>> = Vec::new();
         let mut num: i64 = 0;
         while num >= 0 {
-            if num < origin.len() as i64 {
+            if num < base as i64 {
                 //处理其中一个单元。这里是递推过程。
                 //println!("num={}, len={}", num, stack.len());
                 let mut index = stack.get_mut(num as usize).unwrap();
@@ -69,150 +415,73 @@ impl<'t> Database<'t> {
                     *index = -1;
                     num -= 1;
                 }
+                if num < 0 {
+                    //一批记录扫描完而结果还不够时，读取下一批重新扫描。
+                    if let Option::Some((ref name, ref mut read, count)) = batch {
+                        if *read < count {
+                            let mut end = *read + SCAN_BATCH;
+                            if end > count {end = count;}
+                            let seeks:Vec<usize> = (*read..end).collect();
+                            *read = end;
+                            if let DResult::Table(t) = self.get_table_seeks(name, &seeks) {
+                                origin[0].1 = t;
+                                num = 0;
+                            }
+                        }
+                    }
+                }
             }else{
                 let mut flag = true;
                 //= where处理区 =========================
                 let mut map = HashMap::new();//构造待用的resource。
-                for (index, i) in origin.iter().enumerate() {
+                for (index, i) in origin[..base].iter().enumerate() {
                     map.insert(i.0.to_string(), i.1.content()[stack[index] as usize].copy());
                 }
-                if syntax.wheres.li.len() > 0 {
+                if syntax.joins.is_empty() {
                     let resource = &map;
                     //println!("WHERE: {}", syntax.wheres.to_string());
-                    let mut que: Vec<ExpressionType> = Vec::new();
-                    for exp in syntax.wheres.li.iter() {
-                        match exp {
-                            &ExpressionType::Var(ref prop) => {
-                                let get_table_field_index = |table:&str, field:&str | {
-                                    for &(ref s, ref d) in origin.iter() {
-                                        if s == table {
-                                            for (i, d) in d.head.iter().enumerate() {
-                                                if d == field {return i as i64;}
-                                            }
-                                        }
-                                    }
-                                    return -1;
-                                };
-                                if prop.len() == 1 {
-                                    if resource.len() > 1 {
-                                        return DResult::String(format!("Syntax error: please give a name for table when there are more tables."));
-                                    }else if resource.len() == 0{
-                                        return DResult::String(format!("Syntax error: no any table."));
-                                    }else {
-                                        let mut name = "";
-                                        let mut data = &Data{li:vec![]}; //获得该tabledata的name/data list。
-                                        for (k, v) in resource.iter() {name=k;data=v;}
-                                        let propname = &prop[0]; // 需要取得的属性名
-                                        //需要根据属性名，从origin中匹配数据列。
-                                        let index = get_table_field_index(name, propname);
-                                        if index >= 0 {
-                                            let value = data.li[index as usize].to_expt();
-                                            que.push(value);
-                                        }else{
-                                            return DResult::String(format!("Field {} not found.", propname));
-                                        }
-                                    }
-                                }else if prop.len() == 0 {
-                                    return DResult::String(format!("Syntax error: no prop name."));
-                                }else{ //有表引用
-                                    let tablename = &prop[0];
-                                    let propname = &prop[1];
-                                    let index = get_table_field_index(tablename, propname);
-                                    if let Option::Some(data) = resource.get(tablename) {
-                                        if index >= 0 {
-                                            let value = data.li[index as usize].to_expt();
-                                            que.push(value);
-                                        }else{
-                                            return DResult::String(format!("Field {} not found.", propname));
-                                        }
-                                    }else{
-                                        return DResult::String(format!("Table {} not found.", tablename));
-                                    }
-                                }
-                            },
-                            &ExpressionType::Integer(..) |
-                            &ExpressionType::Float(..) |
-                            &ExpressionType::Str(..) |
-                            &ExpressionType::Bool(..) => {
-                                que.push(exp.copy());
-                            },
-                            &ExpressionType::Signal(ref sign) => {
-                                let pp = {
-                                    let mut get_param = || { //直接从que中弹出顶端参数，并自动处理变量类型。
-                                        if let Option::Some(some) = que.pop(){
-                                            return match some { //expt
-                                                ExpressionType::Integer(..) |
-                                                ExpressionType::Float(..) |
-                                                ExpressionType::Str(..) |
-                                                ExpressionType::Bool(..) => Result::Ok(some.copy()),
-                                                ExpressionType::Var(_) => {Result::Err(format!("invalid var."))},
-                                                _ => {Result::Err(format!("invalid type."))}
-                                            };
-                                        }else{
-                                            Result::Err(format!("Syntax expression error."))
-                                        }
-                                    };
-                                    match sign.as_str() {
-                                        "^" | "*" | "/" | "+" | "-" | "%" |
-                                        ">" | "<" | ">="  |"<=" | "=" | "!=" |
-                                        "&&" | "||" => {
-                                            let p2 = match get_param() {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            };
-                                            let p1 = match get_param() {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            };
-                                            match ExpressionType::make_two(&p1, &p2, sign.as_str()) {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            }
-                                            
-                                        },
-                                        "!" => {
-                                            let p1 = match get_param() {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            };
-                                            match ExpressionType::make_one(&p1, sign.as_str()) {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            }
-                                            
-                                        },
-                                        _ => {
-                                            return DResult::String(format!("Unknown operator."));
-                                        }
-                                    }
-                                };
-                                que.push(pp);
-                            },
-                            _ => {}
-                        }
-                    }
-                    if que.len() == 1 {
-                        if let &ExpressionType::Bool(b) = &que[0] {
-                            flag = b;
-                        }else {
-                            return DResult::String(format!("Wrong where expression."));
-                        }
-                    }else {
-                        return DResult::String(format!("Wrong where expression."));
+                    match self.eval_condition(&syntax.wheres, &|prop:&[String]| get_resource_value(&origin, resource, prop)) {
+                        Result::Ok(b) => {flag = b;},
+                        Result::Err(e) => {return DResult::String(e);}
                     }
                 }
 
                 //= where处理结束 =======================
                 if flag { //flag
                     result.push(map);
+                    if let Option::Some(w) = want {
+                        if result.len() >= w {break;}
+                    }
                 }
                 num -= 1;
             }
         }
+        if !syntax.joins.is_empty() {
+            //有join时，where要在所有join完成之后再过滤。
+            for (k, join) in syntax.joins.iter().enumerate() {
+                result = match self.join_rows(result, &origin, base + k, join) {
+                    Result::Ok(r) => r,
+                    Result::Err(e) => {return DResult::String(e);}
+                };
+            }
+            let mut filtered = Vec::new();
+            for map in result.into_iter() {
+                match self.eval_condition(&syntax.wheres, &|prop:&[String]| get_resource_value(&origin, &map, prop)) {
+                    Result::Ok(true) => {filtered.push(map);},
+                    Result::Ok(false) => {},
+                    Result::Err(e) => {return DResult::String(e);}
+                }
+            }
+            result = filtered;
+        }
         //ORDER
+        //有分组时在分组和having之后再排序，见下方。
+        let grouping = !syntax.groups.is_empty() || !syntax.havings.li.is_empty() ||
+            syntax.goals.iter().any(|(_, g)|g.has_aggregate());
+        let orders = if grouping {&[][..]}else{&syntax.orders[..]};
         //预处理orders列表。
         let mut ord:Vec<(usize, String, usize, String, bool)> = Vec::new();
-        for i in syntax.orders.iter() {
+        for i in orders.iter() {
             //在origin中的索引，表名，列索引，列名，desc
             if let Option::Some(u) = i.0.find(".") {
                 let tablename = i.0[..u].to_string();
@@ -334,133 +603,103 @@ impl<'t> Database<'t> {
             }
         }
         let mut content:Vec<Data> = vec![];
-        for resource in result.iter() { //这个是遍历数据行，然后输出content。
-            let mut content_sub = vec![];
-            for &(_, ref expression) in ref_goals.iter() { //这个是遍历列目标。
-                // 执行expression
-                let mut que:Vec<ExpressionType> = vec![]; //中转存储区。
-                
-                for exp in expression.li.iter() {
-                    match exp {
-                        &ExpressionType::Var(ref prop) => {
-                            let get_table_field_index = |table:&str, field:&str| {
-                                for &(ref s, ref d) in origin.iter() {
-                                    if s == table {
-                                        for (i, d) in d.head.iter().enumerate() {
-                                            if d == field {return i as i64;}
-                                        }
-                                    }
-                                }
-                                return -1;
-                            };
-                            if prop.len() == 1 {
-                                if resource.len() > 1 {
-                                    return DResult::String(format!("Syntax error: please give a name for table when there are more tables."));
-                                }else if resource.len() == 0{
-                                    return DResult::String(format!("Syntax error: no any table."));
-                                }else {
-                                    let mut name = "";
-                                    let mut data = &Data{li:vec![]}; //获得该tabledata的name/data list。
-                                    for (k, v) in resource.iter() {name=k;data=v;}
-                                    let propname = &prop[0]; // 需要取得的属性名
-                                    //需要根据属性名，从origin中匹配数据列。
-                                    let index = get_table_field_index(name, propname);
-                                    if index >= 0 {
-                                        let value = data.li[index as usize].to_expt();
-                                        que.push(value);
-                                    }else{
-                                        return DResult::String(format!("Field {} not found.", propname));
-                                    }
-                                }
-                            }else if prop.len() == 0 {
-                                return DResult::String(format!("Syntax error: no prop name."));
-                            }else{ //有表引用
-                                let tablename = &prop[0];
-                                let propname = &prop[1];
-                                let index = get_table_field_index(tablename, propname);
-                                if let Option::Some(data) = resource.get(tablename) {
-                                    if index >= 0 {
-                                        let value = data.li[index as usize].to_expt();
-                                        que.push(value);
-                                    }else{
-                                        return DResult::String(format!("Field {} not found.", propname));
-                                    }
-                                }else{
-                                    return DResult::String(format!("Table {} not found.", tablename));
-                                }
-                            }
-                        },
-                        &ExpressionType::Integer(..) |
-                        &ExpressionType::Float(..) |
-                        &ExpressionType::Str(..) |
-                        &ExpressionType::Bool(..) => {
-                            que.push(exp.copy());
-                        },
-                        &ExpressionType::Signal(ref sign) => {
-                            let pp = {
-                                let mut get_param = || { //直接从que中弹出顶端参数，并自动处理变量类型。
-                                    if let Option::Some(some) = que.pop(){
-                                        return match some { //expt
-                                            ExpressionType::Integer(..) |
-                                            ExpressionType::Float(..) |
-                                            ExpressionType::Str(..) |
-                                            ExpressionType::Bool(..) => Result::Ok(some.copy()),
-                                            ExpressionType::Var(_) => {Result::Err(format!("invalid var."))},
-                                            _ => {Result::Err(format!("invalid type."))}
-                                        };
-                                    }else{
-                                        Result::Err(format!("Syntax expression error."))
-                                    }
-                                };
-                                match sign.as_str() {
-                                    "^" | "*" | "/" | "+" | "-" | "%" |
-                                    ">" | "<" | ">="  |"<=" | "=" | "!=" |
-                                    "&&" | "||" => {
-                                        let p2 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        let p1 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        match ExpressionType::make_two(&p1, &p2, sign.as_str()) {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            }
-                                        
-                                    },
-                                    "!" => {
-                                        let p1 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        match ExpressionType::make_one(&p1, sign.as_str()) {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            }
-                                        
-                                    },
-                                    _ => {
-                                        return DResult::String(format!("Unknown operator."));
-                                    }
-                                }
-                            };
-                            que.push(pp);
-                        },
-                        _ => {}
+        if grouping {
+            //分组：按分组表达式的值把记录分成若干组，组的顺序是组内第一条记录的顺序。
+            //没有group by时全部记录是一组，即使没有记录也会输出一行聚合结果。
+            let mut groups:Vec<(Data, Vec<usize>)> = Vec::new();
+            for (index, resource) in result.iter().enumerate() {
+                let mut key = vec![];
+                for g in syntax.groups.iter() {
+                    match self.eval_expression(g, &|prop:&[String]| get_resource_value(&origin, resource, prop)) {
+                        Result::Ok(value) => key.push(DataItem::from_expt(&value)),
+                        Result::Err(e) => {return DResult::String(e);}
                     }
                 }
-                //最后剩下的一个是运算结果。
-                if que.len() == 1 {
-                    //println!("final result is {}", que[0].to_string());
-                    content_sub.push(DataItem::from_expt(&que[0]));
-                }else {
-                    return DResult::String(format!("Wrong expression."));
+                let key = Data{li: key};
+                match groups.iter().position(|(k, _)|k.eq(&key)) {
+                    Option::Some(p) => {groups[p].1.push(index);},
+                    Option::None => {groups.push((key, vec![index]));}
+                }
+            }
+            if syntax.groups.is_empty() && groups.is_empty() {
+                groups.push((Data{li: vec![]}, vec![]));
+            }
+            //排序的名字是某个目标列的名字时按该列排序，可以借此按聚合的结果排序；否则按分组字段排序。
+            let mut group_ord:Vec<(Option<usize>, Expression, bool)> = Vec::new();
+            for &(ref name, asc) in syntax.orders.iter() {
+                let prop:Vec<String> = name.split('.').map(|s|s.to_string()).collect();
+                group_ord.push((head.iter().position(|h|h == name), Expression{li: vec![ExpressionType::Var(prop)], setence: name.to_string()}, asc));
+            }
+            let mut keys:Vec<Vec<DataItem>> = Vec::new();
+            for (_, indexes) in groups.iter() {
+                let rows:Vec<&HashMap<String, Data>> = indexes.iter().map(|i|&result[*i]).collect();
+                //having
+                let havings = match self.fold_aggregates(&syntax.havings, &syntax.groups, &origin, &rows) {
+                    Result::Ok(ok) => ok,
+                    Result::Err(e) => {return DResult::String(e);}
+                };
+                match self.eval_condition(&havings, &|prop:&[String]| get_group_value(&origin, &rows, prop)) {
+                    Result::Ok(true) => {},
+                    Result::Ok(false) => {continue;},
+                    Result::Err(e) => {return DResult::String(e);}
+                }
+                let mut content_sub = vec![];
+                for (_, expression) in ref_goals.iter() {
+                    let expression = match self.fold_aggregates(expression, &syntax.groups, &origin, &rows) {
+                        Result::Ok(ok) => ok,
+                        Result::Err(e) => {return DResult::String(e);}
+                    };
+                    match self.eval_expression(&expression, &|prop:&[String]| get_group_value(&origin, &rows, prop)) {
+                        Result::Ok(value) => content_sub.push(DataItem::from_expt(&value)),
+                        Result::Err(e) => {return DResult::String(e);}
+                    }
+                }
+                let mut key = vec![];
+                for &(goal, ref expression, _) in group_ord.iter() {
+                    if let Option::Some(p) = goal {
+                        key.push(content_sub[p].copy());
+                        continue;
+                    }
+                    let expression = match self.fold_aggregates(expression, &syntax.groups, &origin, &rows) {
+                        Result::Ok(ok) => ok,
+                        Result::Err(e) => {return DResult::String(e);}
+                    };
+                    match self.eval_expression(&expression, &|prop:&[String]| get_group_value(&origin, &rows, prop)) {
+                        Result::Ok(value) => key.push(DataItem::from_expt(&value)),
+                        Result::Err(e) => {return DResult::String(e);}
+                    }
+                }
+                keys.push(key);
+                content.push(Data{li: content_sub});
+            }
+            if !group_ord.is_empty() {
+                let mut sorted:Vec<(Vec<DataItem>, Data)> = keys.into_iter().zip(content).collect();
+                sorted.sort_by(|a, b|{
+                    //与上方记录的排序相同：不能比较的两项按相等处理。
+                    for (i, &(_, _, asc)) in group_ord.iter().enumerate() {
+                        let mut result = if let Result::Ok(ok) = a.0[i].cmp(&b.0[i]){ok}else{Ordering::Equal};
+                        if !asc {result = result.reverse();}
+                        if result != Ordering::Equal {
+                            return result;
+                        }
+                    }
+                    Ordering::Equal
+                });
+                content = sorted.into_iter().map(|(_, d)|d).collect();
+            }
+        }else{
+            for resource in result.iter() { //这个是遍历数据行，然后输出content。
+                let mut content_sub = vec![];
+                for (_, expression) in ref_goals.iter() { //这个是遍历列目标。
+                    // 执行expression
+                    match self.eval_expression(expression, &|prop:&[String]| get_resource_value(&origin, resource, prop)) {
+                        Result::Ok(value) => content_sub.push(DataItem::from_expt(&value)),
+                        Result::Err(e) => {return DResult::String(e);}
+                    }
+                    //end expression
                 }
-                //end expression
+                content.push(Data{li: content_sub});
             }
-            content.push(Data{li: content_sub});
         }
         if syntax.distinct {
             //去重
@@ -480,6 +719,10 @@ impl<'t> Database<'t> {
                 i += 1;
             }
         }
+        if syntax.offset > 0 || syntax.limit.is_some() {
+            let limit = syntax.limit.unwrap_or(content.len());
+            content = content.into_iter().skip(syntax.offset).take(limit).collect();
+        }
         DResult::Table(DTable{
             head: head,
             content: content
@@ -502,10 +745,10 @@ impl<'t> Database<'t> {
             head.push(i.name.to_string());
         }
         let mut temp = table_conf.get_template(); // 获得数据模板
-        if ! self.conf.table_pages.contains_key(table_name) {
-            self.conf.table_pages.insert(table_name.to_string(), vec![]);
-        }
-        let pages = &self.conf.table_pages[table_name][..];
+        let pages = match self.conf.table_pages.get(table_name) {
+            Option::Some(p) => &p[..],
+            Option::None => &[]
+        };
         for i in 0..table_conf.count {
             self.file.read(pages, self.conf.pages.len(), i, &mut temp);
             records.push(temp.copy());
@@ -515,6 +758,29 @@ impl<'t> Database<'t> {
             content: records
         })
     }
+    fn get_table_seeks(&mut self, table_name:&str, seeks:&[usize]) -> DResult {
+        //这个函数返回表中指定行号的记录组成的表格。表的存在性已经在调用前确认。
+        let mut records = vec![];
+        let table_conf = &self.conf.tables[table_name];
+        let mut head = vec![];
+        for i in table_conf.fields.iter() {
+            head.push(i.name.to_string());
+        }
+        let mut temp = table_conf.get_template();
+        let pages = match self.conf.table_pages.get(table_name) {
+            Option::Some(p) => &p[..],
+            Option::None => &[]
+        };
+        for &i in seeks.iter() {
+            if i >= table_conf.count {continue;}
+            self.file.read(pages, self.conf.pages.len(), i, &mut temp);
+            records.push(temp.copy());
+        }
+        DResult::Table(DTable{
+            head,
+            content: records
+        })
+    }
     fn get_view_name(&mut self, view_name:&str) -> DResult {
         //这个函数返回一个视图的结果。
         if !self.conf.views.contains_key(view_name) {
@@ -524,20 +790,41 @@ impl<'t> Database<'t> {
         self.get_table_sub(&view)
     }
     pub fn select(&mut self, syntax:&SelectSyntax) -> DResult {
+        //子查询的结果只在一条语句中有效。
+        self.subqueries.clear();
         self.get_table_sub(syntax)
     }
-    pub fn insert_into(&mut self, syntax:&InsertSyntax) -> DResult {
+    pub fn insert_into(&mut self, syntax:&InsertSyntax) -> Result<DResult, String> {
         //插入一组数据到表格中。它们会被追加到末尾。
-        //插入操作可能扩展新页，因此需要根据返回结果更改页记录。
+        self.subqueries.clear();
         if ! self.conf.tables.contains_key(syntax.table_name.as_str()) {
-            return DResult::String(format!("Table is not exists."));
+            return Result::Err("Table is not exists.".to_string());
         }
+        //任何一行插入失败时，撤销整条语句已经插入的记录。
+        let point = self.savepoint();
+        let ret = if let Option::Some(ref select) = syntax.select {
+            self.insert_select(syntax, select)
+        }else{
+            self.insert_values(syntax)
+        };
+        if ret.is_err() {self.restore(point);}
+        match ret {
+            Result::Ok(count) => Result::Ok(DResult::String(format!("{} record(s) has inserted.", count))),
+            Result::Err(e) => Result::Err(e)
+        }
+    }
+    fn insert_values(&mut self, syntax:&InsertSyntax) -> Result<usize, String> {
+        //插入操作可能扩展新页，因此需要根据返回结果更改页记录。
+        //表上的索引：(索引名, 字段下标, 字段类型)
+        let indexes = self.get_table_index_fields(syntax.table_name.as_str());
         let mut table_conf = self.conf.tables.remove(syntax.table_name.as_str()).unwrap();
         //获取页号列表
         if ! self.conf.table_pages.contains_key(syntax.table_name.as_str()) {
             self.conf.table_pages.insert(syntax.table_name.to_string(), vec![]);
         }
         let mut pages = self.conf.table_pages.remove(syntax.table_name.as_str()).unwrap();
+        let head:Vec<String> = table_conf.fields.iter().map(|f|f.name.to_string()).collect();
+        let keys = table_keys(&table_conf);
         let mut count = 0;
         let mut result = DResult::None;
         'outer: for i in syntax.values.iter() {  // 遍历数据行
@@ -557,7 +844,10 @@ impl<'t> Database<'t> {
                         }
                     }else if i.contains_key(enable_index.to_string().as_str()){
                         let mut dataitem = j.t.get_dataitem();
-                        DataItem::from_dfa(&i[enable_index.to_string().as_str()], &mut dataitem);
+                        if !DataItem::from_dfa(&i[enable_index.to_string().as_str()], &mut dataitem) {
+                            result = DResult::String(format!("Wrong value type for field {}.", j.name));
+                            break 'outer;
+                        }
                         li.push(dataitem);
                         enable_index += 1;
                     }else {
@@ -578,11 +868,17 @@ impl<'t> Database<'t> {
                         //println!("auto_inc: value={}", table_conf.auto_config.get(j.name.as_str()).unwrap());
                     }else if i.contains_key(j.name.as_str()) {  // 存在该数据
                         let mut dataitem = j.t.get_dataitem();
-                        DataItem::from_dfa(&i[j.name.as_str()], &mut dataitem);
+                        if !DataItem::from_dfa(&i[j.name.as_str()], &mut dataitem) {
+                            result = DResult::String(format!("Wrong value type for field {}.", j.name));
+                            break 'outer;
+                        }
                         li.push(dataitem);
                     }else if let Option::Some(ref value) = j.default {
                         let dataitem = value.copy();
                         li.push(dataitem);
+                    }else if !j.not_null && !j.primary {
+                        //允许为空的字段没有给出值时取null。
+                        li.push(DataItem::Null(j.t));
                     }else{
                         result = DResult::String(format!("Error: field {} has no default value and cannot find its value.", j.name.as_str()));
                         break 'outer;
@@ -592,31 +888,65 @@ impl<'t> Database<'t> {
             
             //查重与确认操作。主要查：
             /*
+                0. not null约束与check约束
                 1. primary重复
                 2. unique重复
                 3. 外键约束
             */
+            for (index, p) in table_conf.fields.iter().enumerate() {
+                if (p.not_null || p.primary) && li[index].is_null() {
+                    result = DResult::String(format!("Field {} cannot be null.", p.name));
+                    break 'outer;
+                }
+            }
+            if !table_conf.checks.is_empty() {
+                let row = Data::new(li.iter().map(|d|d.copy()).collect());
+                if let Result::Err(e) = self.check_row(syntax.table_name.as_str(), &head, &table_conf.checks, &row) {
+                    result = DResult::String(e);
+                    break 'outer;
+                }
+            }
             let success = true;
+            //主键与unique按整个字段组合查重。只有一个字段且建有索引的组合直接在索引上查重，其余的需要扫描全表。
+            let mut scan_keys = Vec::new();
+            for (message, key) in keys.iter() {
+                if has(key, |&k|li[k].is_null()) {continue;}  //含有null的组合不参与查重
+                if key.len() == 1 {
+                    if let Option::Some(&(ref name, _, t)) = indexes.iter().find(|x|x.1 == key[0]) {
+                        if !self.index_lookup(name, t, "=", &li[key[0]]).is_empty() {
+                            result = DResult::String(message.to_string());
+                            break 'outer;
+                        }
+                        continue;
+                    }
+                }
+                scan_keys.push((message, key));
+            }
             let mut temp = table_conf.get_template();
-            for i in 0..table_conf.count {
+            for i in 0..(if !scan_keys.is_empty() {table_conf.count}else{0}) {
                 self.file.read(&pages[..], self.conf.pages.len(), i, &mut temp);
-                let mut now_success = true;
-                //primary查重
-                let mut primary_flag = true;
-                for (index, p) in table_conf.fields.iter().enumerate() {
-                    if primary_flag && p.primary && !temp.li[index].eq(&li[index]) {primary_flag = false;}
-                    if p.unique && temp.li[index].eq(&li[index]) {now_success = false;break;}
-                }
-                if !now_success{
-                    result = DResult::String(format!("Primary constriant is not satisfied."));
-                    break 'outer;
+                for &(message, key) in scan_keys.iter() {
+                    if key.iter().all(|&k|temp.li[k].eq(&li[k])) {
+                        result = DResult::String(message.to_string());
+                        break 'outer;
+                    }
                 }
             }
             for (index, p) in table_conf.fields.iter().enumerate() {
                 if table_conf.foreign.contains_key(p.name.as_str()) {
                     //存在外键约束。需要检查外表的存在与数据的存在。
                     let item = &li[index];
+                    if item.is_null() {continue;}  //null的外键不需要对应的记录
                     let foreign = table_conf.foreign.get(p.name.as_str()).unwrap();
+                    //外表的对应字段上有索引时，直接查索引。
+                    if let Option::Some(name) = self.get_field_index(foreign.foreign_table.as_str(), foreign.foreign_field.as_str()) {
+                        let t = self.get_field_type(foreign.foreign_table.as_str(), foreign.foreign_field.as_str()).unwrap();
+                        if self.index_lookup(name.as_str(), t, "=", item).is_empty() {
+                            result = DResult::String("Foreign constriant is failed.".to_string());
+                            break 'outer;
+                        }
+                        continue;
+                    }
                     if let DResult::Table(ref dt) = self.get_table_name(foreign.foreign_table.as_str()) {
                         //已经提取到了该表的所有数据。直接提取列并且检查。
                         let mut now_success = false;
@@ -638,14 +968,13 @@ impl<'t> Database<'t> {
             }
             //准备完成，开始写文件
             if success {
-                let data = Data::new(li);
-                if let Option::Some(u) = self.file.write(&pages[..], self.conf.pages.len(), table_conf.count, &data) {
-                    //u表示最新的页号。从page.len()->u的所有页号都是新的页号。
-                    for i in self.conf.pages.len()..u+1 {
-                        self.conf.pages.push(PageType::Data(syntax.table_name.to_string()));
-                        pages.push(i);
-                    }
+                for &(ref name, field_index, t) in indexes.iter() {
+                    self.index_add(name, t, li[field_index].copy(), table_conf.count);
                 }
+                let mut data = Data::new(li);
+                self.store_overflow(syntax.table_name.as_str(), &mut data);
+                self.reserve_pages(&mut pages, table_conf.count, data.len(), PageType::Data(syntax.table_name.to_string()));
+                self.file.write(&pages[..], self.conf.pages.len(), table_conf.count, &data);
                 count += 1;
                 table_conf.count += 1;
             }
@@ -653,350 +982,241 @@ impl<'t> Database<'t> {
         //为了不违反rust的mut借用规则，这个地方只能这么写，先把内容提取出来在最后再插入回去。
         self.conf.tables.insert(syntax.table_name.to_string(), table_conf);
         self.conf.table_pages.insert(syntax.table_name.to_string(), pages);
-        if let DResult::None = result {
-            DResult::String(format!("{} record(s) has inserted.", count))
-        }else {
-            result
+        match result {
+            DResult::String(e) => Result::Err(e),
+            _ => Result::Ok(count)
+        }
+    }
+    fn check_row(&mut self, table:&str, head:&[String], checks:&[(String, Expression)], row:&Data) -> Result<(), String> {
+        //依次计算记录上的check约束。结果为null时视为满足。
+        for (name, e) in checks.iter() {
+            match self.eval_expression(e, &|prop:&[String]| get_field_value(table, head, row, prop)) {
+                Result::Ok(ExpressionType::Bool(true)) | Result::Ok(ExpressionType::Null) => {},
+                Result::Ok(ExpressionType::Bool(false)) => {
+                    return Result::Err(format!("Check constraint {} is not satisfied.", name));
+                },
+                Result::Ok(_) => {
+                    return Result::Err(format!("Check constraint {} is not a condition.", name));
+                },
+                Result::Err(e) => {return Result::Err(e);}
+            }
+        }
+        Result::Ok(())
+    }
+    pub fn savepoint(&self) -> (String, HashMap<usize, Vec<u8>>) {
+        //语句开始前的配置与脏页。语句在中途失败时用restore撤销它已经做出的修改。
+        (self.conf.to_json_string(), self.file.copy_dirty())
+    }
+    pub fn restore(&mut self, point:(String, HashMap<usize, Vec<u8>>)) {
+        self.conf.load(&point.0);
+        self.file.restore_dirty(point.1);
+    }
+    fn insert_select(&mut self, syntax:&InsertSyntax, select:&SelectSyntax) -> Result<usize, String> {
+        //把查询结果的每一行按列的顺序转换为字面值的一行，再按普通的insert插入。
+        //没有给出列名时，查询结果依次对应除自增字段以外的各个字段。
+        let dt = match self.get_table_sub(select) {
+            DResult::Table(dt) => dt,
+            DResult::String(e) => {return Result::Err(e);},
+            _ => {return Result::Err("Wrong subquery.".to_string());}
+        };
+        let mut fields = Vec::new(); //(字段名, 字段类型)
+        {
+            let table_conf = &self.conf.tables[syntax.table_name.as_str()];
+            if syntax.has_head {
+                for name in syntax.columns.iter() {
+                    match table_conf.fields.iter().find(|f|&f.name == name) {
+                        Option::Some(f) => fields.push((f.name.to_string(), f.t)),
+                        Option::None => {return Result::Err(format!("Field {} not found.", name));}
+                    }
+                }
+            }else{
+                for f in table_conf.fields.iter() {
+                    if !(f.auto_inc && f.t == FieldType::Integer) {fields.push((f.name.to_string(), f.t));}
+                }
+            }
+        }
+        if fields.len() != dt.head.len() {
+            return Result::Err("Column count of select does not match the insert.".to_string());
         }
-        
+        let mut values = Vec::new();
+        for row in dt.content.iter() {
+            let mut map = HashMap::new();
+            for (index, &(ref name, t)) in fields.iter().enumerate() {
+                let word = match DataItem::from_expt_typed(&row.li[index].to_expt(), t) {
+                    Option::Some(DataItem::Integer(i)) => DfaWord::Integer(i),
+                    Option::Some(DataItem::Float(f)) => DfaWord::Float(f),
+                    Option::Some(DataItem::Bool(b)) => DfaWord::Bool(b),
+                    Option::Some(DataItem::Str(_, s)) | Option::Some(DataItem::Text(_, s)) => DfaWord::Str(s),
+                    Option::Some(d@DataItem::Date(_)) | Option::Some(d@DataItem::Time(_)) |
+                    Option::Some(d@DataItem::Timestamp(_)) | Option::Some(d@DataItem::Blob(..)) |
+                    Option::Some(d@DataItem::Decimal(..)) => DfaWord::Str(d.to_string()),
+                    Option::Some(DataItem::Null(_)) => DfaWord::Kword("null".to_string()),
+                    Option::None => {return Result::Err(format!("Wrong value type for field {}.", name));}
+                };
+                map.insert(if syntax.has_head {name.to_string()}else{index.to_string()}, word);
+            }
+            values.push(map);
+        }
+        self.insert_values(&InsertSyntax{
+            table_name: syntax.table_name.to_string(),
+            has_head: syntax.has_head,
+            values,
+            columns: vec![],
+            select: Option::None
+        })
     }
-    pub fn update(&mut self, syntax:&UpdateSyntax) -> DResult {
+    pub fn update(&mut self, syntax:&UpdateSyntax) -> Result<DResult, String> {
         //update的语序：
         /*  1. 逐个读取表中的所有记录
             2. 对当前记录执行where语句，判断是否符合条件
-            3. 如果符合条件就覆写当前记录
+            3. 如果符合条件就计算新的记录，加入改写计划
+            4. 按照insert的约束检查改写之后的记录(not null、check、主键与unique、外键)，全部通过后才写入
         */
+        self.subqueries.clear();
         let table_name = syntax.table_name.as_str();
         if ! self.conf.tables.contains_key(table_name) {
-            return DResult::String(format!("Table {} is not exists.", table_name));
+            return Result::Err(format!("Table {} is not exists.", table_name));
         }
-        let table_conf = &self.conf.tables[table_name]; // 获得该表格的配置信息。
-
         let mut head = vec![];  // 获得表格的head。
-        for i in table_conf.fields.iter() {head.push(i.name.to_string());}
-        //for i in head.iter() {print!("[{}]", i);}
-
-        //获得set的覆盖模板。
-        let mut set_temp = Vec::new();
-        for (index, i) in head.iter().enumerate() {
-            if syntax.sets.contains_key(i) {
-                let v = syntax.sets.get(i).unwrap();
-                set_temp.push(Option::Some(match table_conf.fields[index].t{
-                    FieldType::Integer => if let &DfaWord::Integer(i) = v {DataItem::Integer(i)}
-                        else{return DResult::String(format!("Wrong where expression type."));},
-                    FieldType::Float => if let &DfaWord::Float(f) = v {DataItem::Float(f)}
-                        else{return DResult::String(format!("Wrong where expression type."));},
-                    FieldType::Bool => if let &DfaWord::Bool(b) = v {DataItem::Bool(b)}
-                        else{return DResult::String(format!("Wrong where expression type."));},
-                    FieldType::Str(u) => if let &DfaWord::Str(ref s) = v {DataItem::Str(u, s.to_string())}
-                        else{return DResult::String(format!("Wrong where expression type."));}
-                }));
-            }else{
-                set_temp.push(Option::None);
+        let mut set_temp = Vec::new(); //set的覆盖模板：(字段下标, 字段类型, 是否允许null, 新值的表达式)
+        {
+            let table_conf = &self.conf.tables[table_name]; // 获得该表格的配置信息。
+            for i in table_conf.fields.iter() {head.push(i.name.to_string());}
+            //for i in head.iter() {print!("[{}]", i);}
+            for name in syntax.sets.keys() {
+                if !head.contains(name) {
+                    return Result::Err(format!("Field {} not found.", name));
+                }
+            }
+            for (index, field) in table_conf.fields.iter().enumerate() {
+                if let Option::Some(expression) = syntax.sets.get(&field.name) {
+                    set_temp.push((index, field.t, !field.not_null && !field.primary, expression));
+                }
             }
         }
 
-        let mut temp = table_conf.get_template(); // 获得数据模板
-        if ! self.conf.table_pages.contains_key(table_name) {
-            self.conf.table_pages.insert(table_name.to_string(), vec![]);
-        }
-        let pages = &self.conf.table_pages[table_name][..];
-        let mut count = 0;
-        for i in 0..table_conf.count {
-            self.file.read(pages, self.conf.pages.len(), i, &mut temp);
+        let dt = if let DResult::Table(dt) = self.get_table_name(table_name) {dt}else{
+            return Result::Err(format!("Table {} is not exists.", table_name));
+        };
+        let mut plan = ForeignPlan::new();
+        let mut seeks = Vec::new();
+        for (i, row) in dt.content.iter().enumerate() {
             // 执行第2步，开始判断。
-            let mut flag = true;
-            if syntax.wheres.li.len() > 0 {
-                let resource = &temp;
-                let mut que: Vec<ExpressionType> = Vec::new();
-                for exp in syntax.wheres.li.iter() {
-                    match exp {
-                        &ExpressionType::Var(ref prop) => {
-                            let get_field_index = |field:&str| {
-                                for (i, s) in head.iter().enumerate() {
-                                    if s.to_string() == field.to_string() {
-                                        return i as i64;
-                                    }
-                                }
-                                return -1;
-                            };
-                            if prop.len() == 1 {
-                                let propname = &prop[0];
-                                //println!("propname: {}", propname);
-                                let index:i64 = get_field_index(propname);
-                                if index >= 0 {
-                                    let value = resource.li[index as usize].to_expt();
-                                    que.push(value);
-                                }else{
-                                    return DResult::String(format!("Field {} not found.", propname));
-                                }
-                            }else{
-                                return DResult::String(format!("Syntax error: update syntax donot allow mutli tables."));
-                            }
-                        },
-                        &ExpressionType::Integer(..) |
-                        &ExpressionType::Float(..) |
-                        &ExpressionType::Str(..) |
-                        &ExpressionType::Bool(..) => {
-                            que.push(exp.copy());
-                        },
-                        &ExpressionType::Signal(ref sign) => {
-                            let pp = {
-                                let mut get_param = || { //直接从que中弹出顶端参数，并自动处理变量类型。
-                                    if let Option::Some(some) = que.pop(){
-                                        return match some { //expt
-                                            ExpressionType::Integer(..) |
-                                            ExpressionType::Float(..) |
-                                            ExpressionType::Str(..) |
-                                            ExpressionType::Bool(..) => Result::Ok(some.copy()),
-                                            ExpressionType::Var(_) => {Result::Err(format!("invalid var."))},
-                                            _ => {Result::Err(format!("invalid type."))}
-                                        };
-                                    }else{
-                                        Result::Err(format!("Syntax expression error."))
-                                    }
-                                };
-                                match sign.as_str() {
-                                    "^" | "*" | "/" | "+" | "-" | "%" |
-                                    ">" | "<" | ">="  |"<=" | "=" | "!=" |
-                                    "&&" | "||" => {
-                                        let p2 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        let p1 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        match ExpressionType::make_two(&p1, &p2, sign.as_str()) {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            }
-                                        
-                                    },
-                                    "!" => {
-                                        let p1 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        match ExpressionType::make_one(&p1, sign.as_str()) {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            }
-                                        
-                                    },
-                                    _ => {
-                                        return DResult::String(format!("Unknown operator."));
-                                    }
-                                }
-                            };
-                            que.push(pp);
-                        },
-                        _ => {}
-                    }
-                }
-                if que.len() == 1 {
-                    if let &ExpressionType::Bool(b) = &que[0] {
-                        flag = b;
-                    }else {
-                        return DResult::String(format!("Wrong where expression."));
-                    }
-                }else {
-                    return DResult::String(format!("Wrong where expression."));
+            let flag = match self.eval_condition(&syntax.wheres, &|prop:&[String]| get_field_value(table_name, &head, row, prop)) {
+                Result::Ok(b) => b,
+                Result::Err(e) => {return Result::Err(e);}
+            };
+            if flag {seeks.push(i);}
+        }
+        plan.tables.insert(table_name.to_string(), dt);
+        for &i in seeks.iter() { //执行3，确认修改本记录。被引用的字段改变时，按外键的动作处理引用它的记录。
+            //set的表达式都在修改之前的记录上求值。
+            let old = plan.current(table_name, i).copy();
+            let mut row = old.copy();
+            for &(index, t, nullable, expression) in set_temp.iter() {
+                let value = match self.eval_expression(expression, &|prop:&[String]| get_field_value(table_name, &head, &old, prop)) {
+                    Result::Ok(v) => v,
+                    Result::Err(e) => {return Result::Err(e);}
+                };
+                row.li[index] = match DataItem::from_expt_typed(&value, t) {
+                    Option::Some(item) => item,
+                    Option::None => {return Result::Err(format!("Wrong value type for field {}.", head[index]));}
+                };
+                if !nullable && row.li[index].is_null() {
+                    return Result::Err(format!("Field {} cannot be null.", head[index]));
                 }
             }
-            if flag { //执行3，确认修改本记录。
-                count += 1;
-                for (i, m) in set_temp.iter().enumerate() {
-                    if let &Option::Some(ref some) = m {
-                        temp.li[i] = some.copy();
-                    }
-                }
-                self.file.write(pages, self.conf.pages.len(), i, &temp);
+            if let Result::Err(e) = self.plan_change(&mut plan, table_name, i, row) {
+                return Result::Err(e);
             }
         }
-        DResult::String(format!("{} record(s) updated.", count))
+        //执行4，在写入之前检查全部约束。任何一条记录失败时整条语句都不生效。
+        if let Result::Err(e) = self.plan_check(&plan) {
+            return Result::Err(e);
+        }
+        if let Result::Err(e) = self.plan_check_foreign(&mut plan) {
+            return Result::Err(e);
+        }
+        if let Result::Err(e) = self.plan_check_rows(&plan) {
+            return Result::Err(e);
+        }
+        let dependents = plan.dependents(table_name, &seeks);
+        self.plan_apply(&plan);
+        if dependents > 0 {
+            Result::Ok(DResult::String(format!("{} record(s) updated, {} dependent record(s) affected.", seeks.len(), dependents)))
+        }else{
+            Result::Ok(DResult::String(format!("{} record(s) updated.", seeks.len())))
+        }
     }
-    pub fn delete(&mut self, syntax:&DeleteSyntax) -> DResult {
+    pub fn delete(&mut self, syntax:&DeleteSyntax) -> Result<DResult, String> {
         //delete的语序：
         /*  1. 逐条读取record中的所有记录
             2. 判断某一条记录是否应该被删除。如果需要，记下其seek
-            3. 从表格的后方依次往前读，读出n条不需要被删除的数据前移。要前移的数据seek必须大于移往的目标，防止出错。
-            4. 删除每一条记录时，都要检查所有的外键引用，删掉约束对象。
+            3. 按照外键的动作，递归地找出所有受影响的记录：cascade的一并删除，setnull的置空，restrict的拒绝删除
+            4. 从表格的后方依次往前读，读出n条不需要被删除的数据前移。要前移的数据seek必须大于移往的目标，防止出错。
         */
+        self.subqueries.clear();
         let table_name = syntax.table_name.as_str();
         if ! self.conf.tables.contains_key(table_name) {
-            return DResult::String(format!("Table {} is not exists.", table_name));
+            return Result::Err(format!("Table {} is not exists.", table_name));
         }
-        let table_conf = self.conf.tables.get_mut(table_name).unwrap(); // 获得该表格的配置信息。
-
         let mut head = vec![];  // 获得表格的head。
-        for i in table_conf.fields.iter() {head.push(i.name.to_string());}
+        for i in self.conf.tables[table_name].fields.iter() {head.push(i.name.to_string());}
 
-        let mut temp = table_conf.get_template(); // 获得数据模板
-        if ! self.conf.table_pages.contains_key(table_name) {
-            self.conf.table_pages.insert(table_name.to_string(), vec![]);
-        }
-        let pages = &self.conf.table_pages[table_name][..];
+        let dt = if let DResult::Table(dt) = self.get_table_name(table_name) {dt}else{
+            return Result::Err(format!("Table {} is not exists.", table_name));
+        };
         let mut seeks = Vec::new(); //需要删除的标记列表。
-        let mut seeks_set = HashSet::new();
-        for i in 0..table_conf.count {
-            self.file.read(pages, self.conf.pages.len(), i, &mut temp);
+        for (i, row) in dt.content.iter().enumerate() {
             // 执行第2步，开始判断。
-            let mut flag = true;
-            if syntax.wheres.li.len() > 0 {
-                let resource = &temp;
-                let mut que: Vec<ExpressionType> = Vec::new();
-                for exp in syntax.wheres.li.iter() {
-                    match exp {
-                        &ExpressionType::Var(ref prop) => {
-                            let get_field_index = |field:&str| {
-                                for (i, s) in head.iter().enumerate() {
-                                    if s.to_string() == field.to_string() {
-                                        return i as i64;
-                                    }
-                                }
-                                return -1;
-                            };
-                            if prop.len() == 1 {
-                                let propname = &prop[0];
-                                //println!("propname: {}", propname);
-                                let index:i64 = get_field_index(propname);
-                                if index >= 0 {
-                                    let value = resource.li[index as usize].to_expt();
-                                    que.push(value);
-                                }else{
-                                    return DResult::String(format!("Field {} not found.", propname));
-                                }
-                            }else{
-                                return DResult::String(format!("Syntax error: update syntax donot allow mutli tables."));
-                            }
-                        },
-                        &ExpressionType::Integer(..) |
-                        &ExpressionType::Float(..) |
-                        &ExpressionType::Str(..) |
-                        &ExpressionType::Bool(..) => {
-                            que.push(exp.copy());
-                        },
-                        &ExpressionType::Signal(ref sign) => {
-                            let pp = {
-                                let mut get_param = || { //直接从que中弹出顶端参数，并自动处理变量类型。
-                                    if let Option::Some(some) = que.pop(){
-                                        return match some { //expt
-                                            ExpressionType::Integer(..) |
-                                            ExpressionType::Float(..) |
-                                            ExpressionType::Str(..) |
-                                            ExpressionType::Bool(..) => Result::Ok(some.copy()),
-                                            ExpressionType::Var(_) => {Result::Err(format!("invalid var."))},
-                                            _ => {Result::Err(format!("invalid type."))}
-                                        };
-                                    }else{
-                                        Result::Err(format!("Syntax expression error."))
-                                    }
-                                };
-                                match sign.as_str() {
-                                    "^" | "*" | "/" | "+" | "-" | "%" |
-                                    ">" | "<" | ">="  |"<=" | "=" | "!=" |
-                                    "&&" | "||" => {
-                                        let p2 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        let p1 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        match ExpressionType::make_two(&p1, &p2, sign.as_str()) {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            }
-                                        
-                                    },
-                                    "!" => {
-                                        let p1 = match get_param() {
-                                            Result::Ok(ok) => ok,
-                                            Result::Err(e) => {return DResult::String(e);}
-                                        };
-                                        match ExpressionType::make_one(&p1, sign.as_str()) {
-                                                Result::Ok(ok) => ok,
-                                                Result::Err(e) => {return DResult::String(e);}
-                                            }
-                                        
-                                    },
-                                    _ => {
-                                        return DResult::String(format!("Unknown operator."));
-                                    }
-                                }
-                            };
-                            que.push(pp);
-                        },
-                        _ => {}
-                    }
-                }
-                if que.len() == 1 {
-                    if let &ExpressionType::Bool(b) = &que[0] {
-                        flag = b;
-                    }else {
-                        return DResult::String(format!("Wrong where expression."));
-                    }
-                }else {
-                    return DResult::String(format!("Wrong where expression."));
-                }
-            }
-            if flag { //执行3，确认删除改本记录。
-                seeks.push(i);
-                seeks_set.insert(i);
+            let flag = match self.eval_condition(&syntax.wheres, &|prop:&[String]| get_field_value(table_name, &head, row, prop)) {
+                Result::Ok(b) => b,
+                Result::Err(e) => {return Result::Err(e);}
+            };
+            if flag {seeks.push(i);}
+        }
+        //执行第3步。
+        let mut plan = ForeignPlan::new();
+        plan.tables.insert(table_name.to_string(), dt);
+        for &i in seeks.iter() {
+            if let Result::Err(e) = self.plan_delete(&mut plan, table_name, i) {
+                return Result::Err(e);
             }
         }
-        //print!("DELETE:[");
-        //for i in seeks.iter() {print!("{},", i);}
-        //println!("]");
-        //执行批量删除＋前移
-        let mut last = table_conf.count as i64;
-        for i in seeks.iter() {
-            //首先确定下一个需要前移的数据源。
-            let mut flag = false;
-            //print!("[{}]", i);
-            loop {
-                //print!("[last:{}]", last);
-                last -= 1;
-                if last < 0 {flag = true;break;}
-                else if last <= *i as i64 {break;}
-                else if !seeks_set.contains(&(last as usize)) {
-                    //这表示last可用，将last移到i上。
-                    //println!("DELETE: FROM {} TO {}.", last, *i);
-                    self.file.read(pages, self.conf.pages.len(), last as usize, &mut temp);
-                    self.file.write(pages, self.conf.pages.len(), *i, &temp);
-                    break;
-                }
-            }
-            if flag {break;}
-            seeks_set.remove(i);
+        if let Result::Err(e) = self.plan_check(&plan) {
+            return Result::Err(e);
+        }
+        if let Result::Err(e) = self.plan_check_rows(&plan) {
+            return Result::Err(e);
+        }
+        let dependents = plan.dependents(table_name, &seeks);
+        self.plan_apply(&plan);
+        if dependents > 0 {
+            Result::Ok(DResult::String(format!("{} record(s) deleted, {} dependent record(s) affected.", seeks.len(), dependents)))
+        }else{
+            Result::Ok(DResult::String(format!("{} record(s) deleted.", seeks.len())))
         }
-        table_conf.count -= seeks.len();
-        DResult::String(format!("{} record(s) deleted.", seeks.len()))
     }
-    pub fn create_table(&mut self, syntax:&CreateTableSyntax) -> DResult {
+    pub fn create_table(&mut self, syntax:&CreateTableSyntax) -> Result<DResult, String> {
         // 根据syntax直接映射表conf.
         if self.conf.tables.contains_key(&syntax.name) {
-            return DResult::String(format!("Table is already exists."));
+            return Result::Err("Table is already exists.".to_string());
         }
         if syntax.name.trim() == "".to_string() {
-            return DResult::String(format!("Table name cannot be empty."));
+            return Result::Err("Table name cannot be empty.".to_string());
         }
         let mut fields = Vec::with_capacity(syntax.fields.len());
         let mut primary = vec![];
         for i in syntax.fields.iter() {
-            let tp = match i.t.as_str() {
-                "integer" => FieldType::Integer,
-                "float" => FieldType::Float,
-                "bool" => FieldType::Bool,
-                _ => {
-                    if i.t.starts_with("str:") {
-                        FieldType::Str(i.t[4..].parse().unwrap())
-                    }else{
-                        panic!("Wrong type.");
-                    }
+            let tp = match FieldType::parse(i.t.as_str()) {
+                Option::Some(t) => t,
+                Option::None => {return Result::Err(format!("Wrong type for field {}.", i.name));}
+            };
+            let default = match i.default {
+                Option::None => Option::None,
+                Option::Some(ref s) => match tp.parse_value(s) {
+                    Option::Some(d) => Option::Some(d),
+                    Option::None => {return Result::Err(format!("Wrong default value for field {}.", i.name));}
                 }
             };
             fields.push(FieldConfig{
@@ -1005,19 +1225,33 @@ impl<'t> Database<'t> {
                 primary: i.primary,
                 not_null: i.not_null,
                 auto_inc: i.auto_inc,
-                default: match i.default {
-                    Option::None => Option::None,
-                    Option::Some(ref s) => Option::Some(match tp {
-                        FieldType::Integer => DataItem::Integer(s.parse().unwrap()),
-                        FieldType::Float => DataItem::Float(s.parse().unwrap()),
-                        FieldType::Bool => DataItem::Bool(s.parse().unwrap()),
-                        FieldType::Str(u) => DataItem::Str(u, s.to_string())
-                    })
-                },
+                default,
                 t: tp,
             });
             if i.primary {primary.push(i.name.to_string());}
         }
+        let checks = name_checks(syntax.name.as_str(), &[], &syntax.checks);
+        {
+            let head:Vec<String> = fields.iter().map(|f|f.name.to_string()).collect();
+            if let Result::Err(e) = check_valid(&head, &checks) {
+                return Result::Err(e);
+            }
+            for key in syntax.uniques.iter().chain(Option::Some(&syntax.primary)) {
+                if let Result::Err(e) = key_valid(&head, key) {
+                    return Result::Err(e);
+                }
+            }
+        }
+        //表级的primary key (a, b)与字段上的primary key不能同时出现。
+        if !syntax.primary.is_empty() {
+            if !primary.is_empty() {
+                return Result::Err("Table can only have one primary key.".to_string());
+            }
+            for f in fields.iter_mut() {
+                if syntax.primary.contains(&f.name) {f.primary = true;}
+            }
+            primary = syntax.primary.to_vec();
+        }
         let mut foreign = HashMap::new();
         for i in syntax.foreigns.iter() {
             foreign.insert(i.field.to_string(), ForeignConfig{
@@ -1028,7 +1262,7 @@ impl<'t> Database<'t> {
                     "cascade" => ForeignType::Cascade,
                     "restrict" => ForeignType::Restrict,
                     "setnull" => ForeignType::SetNull,
-                    _ => ForeignType::Cascade
+                    _ => ForeignType::Restrict  //没有声明动作时拒绝删除被引用的记录
                 }
             });
         }
@@ -1036,48 +1270,61 @@ impl<'t> Database<'t> {
             name: syntax.name.to_string(),
             fields: fields,
             primary: primary,
+            uniques: syntax.uniques.to_vec(),
             auto_config: HashMap::new(),
             foreign: foreign,
+            checks,
             count: 0
         };
-        let ret = DResult::String(format!("Table {} has created.", conf.name.to_string()));
+        let ret = Result::Ok(DResult::String(format!("Table {} has created.", conf.name)));
         self.conf.tables.insert(conf.name.to_string(), conf);
         ret
     }
-    pub fn alter_table(&mut self, syntax:&AlterTableSyntax) -> DResult {
+    pub fn alter_table(&mut self, syntax:&AlterTableSyntax) -> Result<DResult, String> {
         //alter的语序：
         /*  1. 首先进行全盘检查。
             2. 检查add部分，是否存在重名字段（如果字段在drop列表内则不算，这会算作d/a）
             3. 检查add部分的default值。如果该表数据不为0，那么必须存在default值。
             3. 检查alter部分，添加unique/primary属性需要做约束检查。
+            4. 修改完成之后，所有记录都要满足修改后的check约束，否则撤销整个alter。
         */
         let table_name = syntax.name.as_str();
         if ! self.conf.tables.contains_key(table_name) {
-            return DResult::String(format!("Table {} is not exists.", table_name));
+            return Result::Err(format!("Table {} is not exists.", table_name));
         }
-        let mut table_conf = self.conf.tables.get_mut(table_name).unwrap(); // 获得该表格的配置信息。
+        let point = self.savepoint();
+        let conf = &mut *self.conf;
+        let table_conf = conf.tables.get_mut(table_name).unwrap(); // 获得该表格的配置信息。
 
         let mut head = vec![];  // 获得表格的head。
         for i in table_conf.fields.iter() {head.push(i.name.to_string());}
 
         let mut temp = table_conf.get_template(); // 获得数据模板
-        if ! self.conf.table_pages.contains_key(table_name) {
-            self.conf.table_pages.insert(table_name.to_string(), vec![]);
+        if ! conf.table_pages.contains_key(table_name) {
+            conf.table_pages.insert(table_name.to_string(), vec![]);
         }
-        let pages = self.conf.table_pages.get_mut(table_name).unwrap();
+        let pages = conf.table_pages.get_mut(table_name).unwrap();
         //首先抓取全部的数据
         let mut old_list = Vec::new();
         for index in 0..table_conf.count {
-            self.file.read(&pages[..], self.conf.pages.len(), index, &mut temp);
+            self.file.read(&pages[..], conf.pages.len(), index, &mut temp);
             old_list.push(temp.copy());
         }
+        let old_overflow:Vec<usize> = old_list.iter().flat_map(overflow_of).collect();
         //然后开始检查syntax.
-        //add
+        //add。一次只能加入一个主键字段，并且表上还没有主键。
+        let add_primary = syntax.adds.iter().filter(|f|f.primary).count();
+        if add_primary > 1 || (add_primary == 1 && !table_conf.primary.is_empty()) {
+            return Result::Err("Table can only have one primary key.".to_string());
+        }
         for f in syntax.adds.iter() {
+            if FieldType::parse(f.t.as_str()).is_none() {
+                return Result::Err(format!("Wrong type for field {}.", f.name));
+            }
             //add的字段名不能与现有名重复。除非该字段名在drop列表内。
             if has(&table_conf.fields, |i|i.name == f.name) {
                 if ! has(&syntax.drops, |i|i.to_string() == f.name) {
-                    return DResult::String(format!("Add field {} is repeated.", f.name));
+                    return Result::Err(format!("Add field {} is repeated.", f.name));
                 }
             }
             //检查add字段的default值。只要count>0，default必须不为空。
@@ -1085,13 +1332,13 @@ impl<'t> Database<'t> {
             //检查add字段的primary值。只要count>0，就不允许新的primary。
             if table_conf.count > 0 {
                 if let Option::None = f.default {
-                    return DResult::String(format!("Add field {} must have a default value.", f.name));
+                    return Result::Err(format!("Add field {} must have a default value.", f.name));
                 }
                 if f.unique {
-                    return DResult::String(format!("Add field {} cannot be unique.", f.name));
+                    return Result::Err(format!("Add field {} cannot be unique.", f.name));
                 }
                 if f.primary {
-                    return DResult::String(format!("Add field {} cannot be primary key.", f.name));
+                    return Result::Err(format!("Add field {} cannot be primary key.", f.name));
                 }
             }
         }
@@ -1104,44 +1351,94 @@ impl<'t> Database<'t> {
                 index += 1;
             }
             if index >= head.len() {
-                return DResult::String(format!("Alter field {} is not exists.", f.name));
+                return Result::Err(format!("Alter field {} is not exists.", f.name));
+            }
+            if FieldType::parse(f.t.as_str()).is_none() {
+                return Result::Err(format!("Wrong type for field {}.", f.name));
             }
             //检查unique值。现有数据如果存在非unqiue值，就拒绝unique约束。
             if f.unique && !table_conf.fields[index].unique && table_conf.count > 0 {
                 //直接用粗暴的检查方法。
                 for i in 0..old_list.len() {
+                    if old_list[i].li[index].is_null() {continue;}
                     for j in i+1..old_list.len() {
                         if old_list[i].li[index].eq(&old_list[j].li[index]) {
-                            return DResult::String(format!("Alter field {} cannot be unique: repeat value in records.", f.name));
+                            return Result::Err(format!("Alter field {} cannot be unique: repeat value in records.", f.name));
                         }
                     }
                 }
             }
-            //检查primary值。不允许新的primary约束。
-            if f.primary {
-                return DResult::String(format!("Alter field {} cannot be primary key.", f.name));
+            //检查not_null值。类型不变时保留原有数据，现有数据中存在null就拒绝not_null约束。
+            if f.not_null && f.t == table_conf.fields[index].t.to_string()
+                && has(&old_list, |i|i.li[index].is_null()) {
+                    return Result::Err(format!("Alter field {} cannot be not null: null value in records.", f.name));
+                }
+            //检查primary值。主键只能在建表时声明，alter既不能加上也不能去掉字段的primary。
+            if f.primary && !table_conf.fields[index].primary {
+                return Result::Err(format!("Alter field {} cannot be primary key.", f.name));
+            }
+            if !f.primary && table_conf.fields[index].primary {
+                return Result::Err(format!("Alter field {} is a primary key and must keep primary.", f.name));
             }
             //检查type。如果type不同，就要求新的default。
             if f.t != table_conf.fields[index].t.to_string() {
                 if let Option::None = f.default {
-                    return DResult::String(format!("Alter field {} must has a default because type is changed.", f.name));
+                    return Result::Err(format!("Alter field {} must has a default because type is changed.", f.name));
                 }
             }
         }
         //drop
         for f in syntax.drops.iter() {
             if ! has(&table_conf.fields, |i|i.name == f.to_string()) {
-                return DResult::String(format!("Drop field {} is not exists.", f));
+                return Result::Err(format!("Drop field {} is not exists.", f));
             }
             //拒绝删除主键field.
             for i in table_conf.fields.iter() {
                 if f.to_string() == i.name {
                     if i.primary {
-                        return DResult::String(format!("Drop field {} cannot be primary key.", f));
+                        return Result::Err(format!("Drop field {} cannot be primary key.", f));
                     }
                 }
             }
         }
+        //check。先删除drop的约束，再加入新的约束。
+        let mut checks = Vec::new();
+        for f in syntax.drop_checks.iter() {
+            if ! has(&table_conf.checks, |c|c.0 == *f) {
+                return Result::Err(format!("Check constraint {} is not exists.", f));
+            }
+        }
+        for (name, e) in table_conf.checks.iter() {
+            if ! has(&syntax.drop_checks, |i|i == name) {checks.push((name.to_string(), e.copy()));}
+        }
+        let added = name_checks(table_name, &checks, &syntax.checks);
+        checks.extend(added);
+        {
+            for f in syntax.drops.iter() {
+                for (name, e) in checks.iter() {
+                    if has(&e.li, |i|if let ExpressionType::Var(v) = i {v[v.len() - 1] == *f}else{false}) {
+                        return Result::Err(format!("Drop field {} is used by check constraint {}.", f, name));
+                    }
+                }
+            }
+            for f in syntax.drops.iter() {
+                for u in table_conf.uniques.iter() {
+                    if u.contains(f) {
+                        return Result::Err(format!("Drop field {} is used by unique ({}).", f, u.join(", ")));
+                    }
+                }
+            }
+            let mut new_head:Vec<String> = head.iter().filter(|h|! has(&syntax.drops, |i|i == *h)).map(|h|h.to_string()).collect();
+            for f in syntax.adds.iter() {new_head.push(f.name.to_string());}
+            if let Result::Err(e) = check_valid(&new_head, &checks) {
+                return Result::Err(e);
+            }
+            for key in syntax.uniques.iter() {
+                if let Result::Err(e) = key_valid(&new_head, key) {
+                    return Result::Err(e);
+                }
+            }
+        }
         //全部检查完成之后开始修改。
         //修改方案：先将drop的删掉，然后将alter的head合并。
         //最后将add的新加入。
@@ -1173,16 +1470,11 @@ impl<'t> Database<'t> {
             let default;
             if table_conf.fields[index].t.to_string() != alter.t { //类型不同时要按照default重写所有值。
                 if let Option::Some(ref v) = alter.default {
-                    let def = match alter.t.as_str() {
-                        "integer" => DataItem::Integer(v.parse().unwrap()),
-                        "float" => DataItem::Float(v.parse().unwrap()),
-                        "bool" => DataItem::Bool(v.parse().unwrap()),
-                        other@_ => {
-                            if other.starts_with("str:") {
-                                DataItem::Str(other[4..].parse().unwrap(), v.to_string())
-                            }else{
-                                return DResult::String(format!("Alter fields {} has a wrong type.", alter.name));
-                            }
+                    let def = match FieldType::from_string(alter.t.as_str()).parse_value(v) {
+                        Option::Some(d) => d,
+                        Option::None => {
+                            self.restore(point);
+                            return Result::Err(format!("Alter fields {} has a wrong type.", alter.name));
                         }
                     };
                     default = Option::Some(def.copy());
@@ -1205,20 +1497,16 @@ impl<'t> Database<'t> {
         //执行add。添加表头以及默认数据。
         for add in syntax.adds.iter() {
             let default = if let Option::Some(ref v) = add.default {
-                Option::Some(match add.t.as_str() {
-                    "integer" => DataItem::Integer(v.parse().unwrap()),
-                    "float" => DataItem::Float(v.parse().unwrap()),
-                    "bool" => DataItem::Bool(v.parse().unwrap()),
-                    other@_ => {
-                        if other.starts_with("str:") {
-                            DataItem::Str(other[4..].parse().unwrap(), v.to_string())
-                        }else{
-                            return DResult::String(format!("Alter fields {} has a wrong type.", add.name));
-                        }
+                match FieldType::from_string(add.t.as_str()).parse_value(v) {
+                    Option::Some(d) => Option::Some(d),
+                    Option::None => {
+                        self.restore(point);
+                        return Result::Err(format!("Alter fields {} has a wrong type.", add.name));
                     }
-                })
+                }
             }else{Option::None};
             //插入表头。
+            if add.primary {table_conf.primary.push(add.name.to_string());}
             table_conf.fields.push(FieldConfig {
                 name: add.name.to_string(),
                 unique: add.unique,
@@ -1235,59 +1523,1108 @@ impl<'t> Database<'t> {
                 }else{panic!("Error occured: none value.")});
             }
         }
-        //最后将数据回写。
-        for (i, r) in old_list.iter().enumerate() {
-            if let Option::Some(u) = self.file.write(&pages[..], self.conf.pages.len(), i, r) {
-                for i in self.conf.pages.len()..u+1 {
-                    self.conf.pages.push(PageType::Data(table_name.to_string()));
-                    pages.push(i);
+        table_conf.checks = checks;
+        for key in syntax.uniques.iter() {table_conf.uniques.push(key.to_vec());}
+        //修改之后的记录仍然需要满足主键与unique约束。
+        for (message, key) in table_keys(table_conf) {
+            let mut seen = HashSet::new();
+            for r in old_list.iter() {
+                if let Option::Some(k) = key_of(r, &key) {
+                    if ! seen.insert(k) {
+                        self.restore(point);
+                        return Result::Err(message);
+                    }
                 }
             }
         }
-        DResult::String(format!("Alter table success."))
+        //检查所有记录是否满足check约束。
+        if !table_conf.checks.is_empty() {
+            let checks:Vec<(String, Expression)> = table_conf.checks.iter().map(|c|(c.0.to_string(), c.1.copy())).collect();
+            let head:Vec<String> = table_conf.fields.iter().map(|f|f.name.to_string()).collect();
+            for r in old_list.iter() {
+                if let Result::Err(e) = self.check_row(table_name, &head, &checks, r) {
+                    self.restore(point);
+                    return Result::Err(e);
+                }
+            }
+        }
+        //最后将数据回写。被删除或改变了类型的text/blob字段的溢出页随之释放。
+        let mut pages = self.conf.table_pages.remove(table_name).unwrap_or(vec![]);
+        let mut now_overflow = HashSet::new();
+        for (i, r) in old_list.iter_mut().enumerate() {
+            self.store_overflow(table_name, r);
+            now_overflow.extend(overflow_of(r));
+            self.reserve_pages(&mut pages, i, r.len(), PageType::Data(table_name.to_string()));
+            self.file.write(&pages[..], self.conf.pages.len(), i, r);
+        }
+        self.conf.table_pages.insert(table_name.to_string(), pages);
+        let unused:Vec<usize> = old_overflow.into_iter().filter(|p|!now_overflow.contains(p)).collect();
+        self.free_overflow(&unused);
+        //被删除的字段上的索引随之删除，其余索引按新的数据重建。
+        for name in self.get_table_indexes(table_name) {
+            let field = self.conf.indexes[&name].field.to_string();
+            if self.get_field_type(table_name, field.as_str()).is_none() {
+                self.conf.indexes.remove(&name);
+                self.conf.index_trees.remove(&name);
+            }
+        }
+        self.rebuild_table_indexes(table_name);
+        Result::Ok(DResult::String("Alter table success.".to_string()))
     }
-    pub fn drop_table(&mut self, syntax:&DropTableSyntax) -> DResult {
+    pub fn drop_table(&mut self, syntax:&DropTableSyntax) -> Result<DResult, String> {
         let table_name = syntax.name.as_str();
         if ! self.conf.tables.contains_key(table_name) {
-            return DResult::String(format!("Table {} is not exists.", table_name));
+            return Result::Err(format!("Table {} is not exists.", table_name));
         }
         // 检查约束关系。
         // 如果存在任意外键链接到当前表，那么就拒绝删除
         for (name, table) in self.conf.tables.iter() {
             for (_, f) in table.foreign.iter() {
                 if f.foreign_table == table_name {
-                    return DResult::String(format!("Cannot delete table: foreign key constriant in {}.", name));
+                    return Result::Err(format!("Cannot delete table: foreign key constriant in {}.", name));
                 }
             }
         }
         self.conf.tables.remove(table_name);
-        DResult::String(format!("Table {} is deleted.", table_name))
+        for name in self.get_table_indexes(table_name) {
+            self.remove_index(name.as_str());
+        }
+        //表的数据页归还到空闲页表。
+        if let Option::Some(pages) = self.conf.table_pages.remove(table_name) {
+            self.conf.free_pages(&pages[..]);
+        }
+        let overflow:Vec<usize> = self.conf.pages.iter().enumerate().filter(|&(_, p)|match p {
+            PageType::Overflow(t) | PageType::Shared(t, _, _) => t == table_name,
+            _ => false
+        }).map(|(i, _)|i).collect();
+        self.conf.free_pages(&overflow[..]);
+        Result::Ok(DResult::String(format!("Table {} is deleted.", table_name)))
     }
-    pub fn create_view(&mut self, syntax:&CreateViewSyntax) -> DResult {
+    pub fn create_view(&mut self, syntax:&CreateViewSyntax) -> Result<DResult, String> {
         let name = syntax.name.as_str();
         if self.conf.tables.contains_key(name) {
-            return DResult::String(format!("There is a table has same name of {}.", name));
+            return Result::Err(format!("There is a table has same name of {}.", name));
         }
         if self.conf.views.contains_key(name) {
-            return DResult::String(format!("View {} is already exists.", name));
+            return Result::Err(format!("View {} is already exists.", name));
         }
         if name.trim() == "" {
-            return DResult::String(format!("View name cannot be empty."));
+            return Result::Err("View name cannot be empty.".to_string());
         }
         self.conf.views.insert(name.to_string(), syntax.sub.copy());
-        DResult::String(format!("View {} has created.", name))
+        Result::Ok(DResult::String(format!("View {} has created.", name)))
     }
-    pub fn drop_view(&mut self, syntax:&DropViewSyntax) -> DResult {
+    pub fn drop_view(&mut self, syntax:&DropViewSyntax) -> Result<DResult, String> {
         let name = syntax.name.as_str();
         if self.conf.tables.contains_key(name) {
-            return DResult::String(format!("{} is a table, not a view.", name));
+            return Result::Err(format!("{} is a table, not a view.", name));
         }
         if !self.conf.views.contains_key(name) {
-            return DResult::String(format!("View {} is not exists.", name));
+            return Result::Err(format!("View {} is not exists.", name));
         }
         self.conf.views.remove(name);
-        DResult::String(format!("View {} is deleted.", name))
+        Result::Ok(DResult::String(format!("View {} is deleted.", name)))
+    }
+    pub fn create_index(&mut self, syntax:&CreateIndexSyntax) -> Result<DResult, String> {
+        let name = syntax.name.as_str();
+        if name.trim() == "" {
+            return Result::Err("Index name cannot be empty.".to_string());
+        }
+        if self.conf.indexes.contains_key(name) {
+            return Result::Err(format!("Index {} is already exists.", name));
+        }
+        if ! self.conf.tables.contains_key(syntax.table.as_str()) {
+            return Result::Err(format!("Table {} is not exists.", syntax.table));
+        }
+        match self.get_field_type(syntax.table.as_str(), syntax.field.as_str()) {
+            Option::None => {
+                return Result::Err(format!("Field {} is not found in {}.", syntax.field, syntax.table));
+            },
+            Option::Some(FieldType::Text) | Option::Some(FieldType::Blob) => {
+                //索引页中的记录是定长的，放不下text/blob的内容。
+                return Result::Err(format!("Cannot create index on text or blob field {}.", syntax.field));
+            },
+            _ => {}
+        }
+        if let Option::Some(other) = self.get_field_index(syntax.table.as_str(), syntax.field.as_str()) {
+            return Result::Err(format!("Field {} already has index {}.", syntax.field, other));
+        }
+        self.conf.indexes.insert(name.to_string(), IndexConfig{
+            name: name.to_string(),
+            table: syntax.table.to_string(),
+            field: syntax.field.to_string(),
+            count: 0
+        });
+        self.rebuild_index(name);
+        Result::Ok(DResult::String(format!("Index {} has created.", name)))
+    }
+    pub fn drop_index(&mut self, syntax:&DropIndexSyntax) -> Result<DResult, String> {
+        let name = syntax.name.as_str();
+        if ! self.conf.indexes.contains_key(name) {
+            return Result::Err(format!("Index {} is not exists.", name));
+        }
+        self.remove_index(name);
+        Result::Ok(DResult::String(format!("Index {} is deleted.", name)))
+    }
+    fn remove_index(&mut self, name:&str) {
+        //删除索引，并将它的索引页归还到空闲页表。
+        self.conf.indexes.remove(name);
+        self.conf.index_trees.remove(name);
+        if let Option::Some(pages) = self.conf.index_pages.remove(name) {
+            self.conf.free_pages(&pages[..]);
+        }
+    }
+    pub fn vacuum(&mut self, syntax:&VacuumSyntax) -> Result<DResult, String> {
+        //将表的记录重新紧凑地写入最少的页中，多出来的数据页和索引页归还到空闲页表。
+        //不指定表时整理全部的表，并回收不属于任何表和索引的页。
+        let mut names:Vec<String> = match syntax.table {
+            Option::Some(ref t) => {
+                if ! self.conf.tables.contains_key(t.as_str()) {
+                    return Result::Err(format!("Table {} is not exists.", t));
+                }
+                vec![t.to_string()]
+            },
+            Option::None => self.conf.tables.keys().map(|k|k.to_string()).collect()
+        };
+        names.sort();
+        let mut freed = 0;
+        let mut overflow = HashSet::new();  //仍被记录使用的溢出页
+        for name in names.iter() {
+            freed += self.vacuum_table(name.as_str(), &mut overflow);
+        }
+        if syntax.table.is_none() {
+            let mut orphans = Vec::new();
+            for (i, p) in self.conf.pages.iter().enumerate() {
+                let owned = match p {
+                    PageType::Data(t) => self.conf.table_pages.get(t).map(|v|v.contains(&i)).unwrap_or(false),
+                    PageType::Index(t) => self.conf.index_pages.get(t).map(|v|v.contains(&i)).unwrap_or(false),
+                    &PageType::Overflow(_) | &PageType::Shared(..) => overflow.contains(&i),
+                    &PageType::Free => true
+                };
+                if !owned {orphans.push(i);}
+            }
+            freed += orphans.len();
+            self.conf.free_pages(&orphans[..]);
+        }
+        Result::Ok(DResult::String(format!("Vacuum finished: {} page(s) freed.", freed)))
+    }
+    fn vacuum_table(&mut self, table_name:&str, overflow:&mut HashSet<usize>) -> usize {
+        //返回释放的页数。记录使用的溢出页会加入overflow。
+        let mut temp = self.conf.tables[table_name].get_template();
+        let count = self.conf.tables[table_name].count;
+        let mut pages = self.conf.table_pages.remove(table_name).unwrap_or(vec![]);
+        let mut rows = Vec::with_capacity(count);
+        for i in 0..count {
+            self.file.read(&pages[..], self.conf.pages.len(), i, &mut temp);
+            for addr in overflow_of(&temp) {
+                overflow.extend(self.file.overflow_pages(addr));
+            }
+            rows.push(temp.copy());
+        }
+        //保留页号最小的若干页，按页号顺序重写全部记录，其余的页释放掉。
+        let need = if count == 0 {0}else{(count * temp.len()) / PAGE_SIZE + 1};
+        pages.sort();
+        let mut freed = 0;
+        if pages.len() > need {
+            let rest = pages.split_off(need);
+            freed += rest.len();
+            self.conf.free_pages(&rest[..]);
+        }
+        for (i, r) in rows.iter().enumerate() {
+            self.reserve_pages(&mut pages, i, r.len(), PageType::Data(table_name.to_string()));
+            self.file.write(&pages[..], self.conf.pages.len(), i, r);
+        }
+        self.conf.table_pages.insert(table_name.to_string(), pages);
+        //索引按照整理后的数据重建，同样只保留需要的页。
+        for name in self.get_table_indexes(table_name) {
+            let before = self.conf.index_pages.get(&name).map(|p|p.len()).unwrap_or(0);
+            if let Option::Some(old) = self.conf.index_pages.remove(&name) {
+                self.conf.free_pages(&old[..]);
+            }
+            self.rebuild_index(name.as_str());
+            let after = self.conf.index_pages.get(&name).map(|p|p.len()).unwrap_or(0);
+            if before > after {freed += before - after;}
+        }
+        freed
+    }
+
+    //= 外键动作 =========================================
+    fn get_referencing(&self, table_name:&str) -> Vec<(String, usize, usize, ForeignType)> {
+        //找出引用了该表的所有外键：(引用表, 外键字段下标, 被引用字段下标, 删除动作)
+        let mut ret = Vec::new();
+        let parent = &self.conf.tables[table_name];
+        for (name, table) in self.conf.tables.iter() {
+            for (_, f) in table.foreign.iter() {
+                if f.foreign_table != table_name {continue;}
+                let field = table.fields.iter().position(|i|i.name == f.field);
+                let parent_field = parent.fields.iter().position(|i|i.name == f.foreign_field);
+                if let (Option::Some(field), Option::Some(parent_field)) = (field, parent_field) {
+                    ret.push((name.to_string(), field, parent_field, f.delete_action.copy()));
+                }
+            }
+        }
+        ret
+    }
+    fn plan_delete(&mut self, plan:&mut ForeignPlan, table_name:&str, seek:usize) -> Result<(), String> {
+        //将一条记录加入删除计划，并处理引用它的记录。
+        if plan.is_deleted(table_name, seek) {return Result::Ok(());}
+        plan.deletes.entry(table_name.to_string()).or_default().insert(seek);
+        let row = plan.current(table_name, seek).copy();
+        self.plan_children(plan, table_name, &row, Option::None)
+    }
+    fn plan_change(&mut self, plan:&mut ForeignPlan, table_name:&str, seek:usize, new:Data) -> Result<(), String> {
+        //将一条记录的新内容加入改写计划，被引用的字段发生变化时处理引用它的记录。
+        let old = plan.current(table_name, seek).copy();
+        plan.changes.entry(table_name.to_string()).or_default().insert(seek, new.copy());
+        self.plan_children(plan, table_name, &old, Option::Some(&new))
+    }
+    fn plan_children(&mut self, plan:&mut ForeignPlan, table_name:&str, old:&Data, new:Option<&Data>) -> Result<(), String> {
+        //按外键的动作处理引用了old的记录。new为None表示old被删除，否则表示old被改写为new。
+        /*  cascade: 删除时一并删除；改写时将外键改为新的值。
+            setnull: 将外键置为null，外键字段不允许为null时报错。
+            restrict: 先记下来，全部计划完成后再确认是否仍然引用。
+        */
+        for (child, field, parent_field, action) in self.get_referencing(table_name) {
+            let key = &old.li[parent_field];
+            if key.is_null() {continue;}
+            if let Option::Some(n) = new {
+                if n.li[parent_field].eq(key) {continue;}
+            }
+            if ! plan.tables.contains_key(&child) {
+                if let DResult::Table(dt) = self.get_table_name(&child) {
+                    plan.tables.insert(child.to_string(), dt);
+                }
+            }
+            let count = plan.tables[&child].content.len();
+            for s in 0..count {
+                if plan.is_deleted(&child, s) || !plan.current(&child, s).li[field].eq(key) {continue;}
+                let mut row = plan.current(&child, s).copy();
+                let res = match action {
+                    ForeignType::Restrict => {
+                        plan.restricts.push((child.to_string(), s, field, key.copy()));
+                        Result::Ok(())
+                    },
+                    ForeignType::Cascade => {
+                        plan.affected.insert((child.to_string(), s));
+                        match new {
+                            Option::None => self.plan_delete(plan, &child, s),
+                            Option::Some(n) => {
+                                row.li[field] = n.li[parent_field].copy();
+                                self.plan_change(plan, &child, s, row)
+                            }
+                        }
+                    },
+                    ForeignType::SetNull => {
+                        let (name, t, nullable) = {
+                            let f = &self.conf.tables[&child].fields[field];
+                            (f.name.to_string(), f.t, !f.not_null && !f.primary)
+                        };
+                        if !nullable {
+                            return Result::Err(format!("Cannot set field {} in {} to null.", name, child));
+                        }
+                        plan.affected.insert((child.to_string(), s));
+                        row.li[field] = DataItem::Null(t);
+                        self.plan_change(plan, &child, s, row)
+                    }
+                };
+                if let Result::Err(e) = res {return Result::Err(e);}
+            }
+        }
+        Result::Ok(())
+    }
+    fn plan_check(&self, plan:&ForeignPlan) -> Result<(), String> {
+        //restrict：计划执行之后仍然引用原来的值的记录会使整条语句失败。
+        for &(ref table, seek, field, ref key) in plan.restricts.iter() {
+            if plan.is_deleted(table, seek) {continue;}
+            if plan.current(table, seek).li[field].eq(key) {
+                return Result::Err(format!("Foreign constriant is failed: record(s) in {} reference it.", table));
+            }
+        }
+        //被改写过的表，在计划执行之后的全部记录上按主键与unique查重。
+        for table in plan.changes.keys() {
+            let content = &plan.tables[table].content;
+            for (message, key) in table_keys(&self.conf.tables[table]) {
+                let mut seen = HashSet::new();
+                for seek in 0..content.len() {
+                    if plan.is_deleted(table, seek) {continue;}
+                    if let Option::Some(k) = key_of(plan.current(table, seek), &key) {
+                        if ! seen.insert(k) {return Result::Err(message);}
+                    }
+                }
+            }
+        }
+        Result::Ok(())
+    }
+    fn plan_check_foreign(&mut self, plan:&mut ForeignPlan) -> Result<(), String> {
+        //被改写的外键字段，新的值必须在外表中存在。外表在同一个计划中被改写时按改写之后的内容查找。
+        let mut checks = Vec::new();  //(表, 行号, 外键字段下标, 外表, 外表字段)
+        for (table, rows) in plan.changes.iter() {
+            let conf = &self.conf.tables[table];
+            for (index, f) in conf.fields.iter().enumerate() {
+                if let Option::Some(foreign) = conf.foreign.get(&f.name) {
+                    for (&seek, row) in rows.iter() {
+                        if plan.is_deleted(table, seek) || row.li[index].is_null() {continue;}
+                        if row.li[index].eq(&plan.tables[table].content[seek].li[index]) {continue;}
+                        checks.push((table.to_string(), seek, index, foreign.foreign_table.to_string(), foreign.foreign_field.to_string()));
+                    }
+                }
+            }
+        }
+        let mut values:HashMap<(String, String), HashSet<String>> = HashMap::new();  //外表字段上的全部值
+        for (table, seek, index, foreign_table, foreign_field) in checks {
+            if ! plan.tables.contains_key(&foreign_table) {
+                match self.get_table_name(&foreign_table) {
+                    DResult::Table(dt) => {plan.tables.insert(foreign_table.to_string(), dt);},
+                    _ => {return Result::Err("Foreign constriant is failed.".to_string());}
+                }
+            }
+            let pair = (foreign_table.to_string(), foreign_field.to_string());
+            if ! values.contains_key(&pair) {
+                let field = match self.conf.tables[&foreign_table].fields.iter().position(|f|f.name == foreign_field) {
+                    Option::Some(i) => i,
+                    Option::None => {return Result::Err("Foreign constriant is failed.".to_string());}
+                };
+                let mut set = HashSet::new();
+                for s in 0..plan.tables[&foreign_table].content.len() {
+                    if plan.is_deleted(&foreign_table, s) {continue;}
+                    if let Option::Some(k) = join_key(&plan.current(&foreign_table, s).li[field].to_expt()) {set.insert(k);}
+                }
+                values.insert((foreign_table.to_string(), foreign_field.to_string()), set);
+            }
+            let key = join_key(&plan.current(&table, seek).li[index].to_expt());
+            if ! key.map(|k|values[&pair].contains(&k)).unwrap_or(false) {
+                return Result::Err("Foreign constriant is failed.".to_string());
+            }
+        }
+        Result::Ok(())
+    }
+    fn plan_check_rows(&mut self, plan:&ForeignPlan) -> Result<(), String> {
+        //被改写的记录（包括外键置空的记录）都需要满足所在表的check约束。
+        for (table, rows) in plan.changes.iter() {
+            let checks:Vec<(String, Expression)> = self.conf.tables[table].checks.iter().map(|c|(c.0.to_string(), c.1.copy())).collect();
+            if checks.is_empty() {continue;}
+            let head:Vec<String> = self.conf.tables[table].fields.iter().map(|f|f.name.to_string()).collect();
+            for (seek, row) in rows.iter() {
+                if plan.is_deleted(table, *seek) {continue;}
+                if let Result::Err(e) = self.check_row(table, &head, &checks, row) {
+                    return Result::Err(e);
+                }
+            }
+        }
+        Result::Ok(())
+    }
+    fn plan_apply(&mut self, plan:&ForeignPlan) {
+        //先覆写被改写的记录，再删除记录并前移，同时逐条维护受影响的表的索引。
+        //改写后不再使用的与被删除的记录的溢出页都归还到空闲页表。
+        for (table, rows) in plan.changes.iter() {
+            let pages = self.conf.table_pages.get(table).map(|p|p.to_vec()).unwrap_or(vec![]);
+            let indexes = self.get_table_index_fields(table);
+            for (seek, row) in rows.iter() {
+                if plan.is_deleted(table, *seek) {continue;}
+                //先释放，新的内容可以复用释放的页。
+                let mut row = row.copy();
+                let now = overflow_of(&row);
+                let unused:Vec<usize> = overflow_of(&plan.tables[table].content[*seek]).into_iter().filter(|p|!now.contains(p)).collect();
+                self.free_overflow(&unused);
+                self.store_overflow(table, &mut row);
+                self.file.write(&pages[..], self.conf.pages.len(), *seek, &row);
+                for &(ref name, field_index, t) in indexes.iter() {
+                    let old = &plan.tables[table].content[*seek].li[field_index];
+                    if IndexKey(old.copy()) != IndexKey(row.li[field_index].copy()) {
+                        self.index_set(name, t, *seek, old, row.li[field_index].copy());
+                    }
+                }
+            }
+        }
+        for (table, seeks) in plan.deletes.iter() {
+            let seeks:Vec<usize> = seeks.iter().copied().collect();
+            for &seek in seeks.iter() {
+                let unused = overflow_of(&plan.tables[table].content[seek]);
+                self.free_overflow(&unused);
+            }
+            let moves = self.remove_rows(table, &seeks);
+            for (name, _, t) in self.get_table_index_fields(table) {
+                self.index_remove(name.as_str(), t, &seeks, &moves);
+            }
+        }
+    }
+    fn remove_rows(&mut self, table_name:&str, seeks:&[usize]) -> Vec<(usize, usize)> {
+        //执行批量删除＋前移。seeks需要从小到大排列。
+        //返回从表尾移到空位上的记录：(原来的行号, 新的行号)
+        let mut temp = self.conf.tables[table_name].get_template();
+        let pages = self.conf.table_pages.get(table_name).map(|p|p.to_vec()).unwrap_or(vec![]);
+        let mut seeks_set:HashSet<usize> = seeks.iter().copied().collect();
+        let mut last = self.conf.tables[table_name].count as i64;
+        let mut moves = Vec::new();
+        for i in seeks.iter() {
+            //首先确定下一个需要前移的数据源。
+            let mut flag = false;
+            //print!("[{}]", i);
+            loop {
+                //print!("[last:{}]", last);
+                last -= 1;
+                if last < 0 {flag = true;break;}
+                else if last <= *i as i64 {break;}
+                else if !seeks_set.contains(&(last as usize)) {
+                    //这表示last可用，将last移到i上。
+                    //println!("DELETE: FROM {} TO {}.", last, *i);
+                    self.file.read(&pages[..], self.conf.pages.len(), last as usize, &mut temp);
+                    self.file.write(&pages[..], self.conf.pages.len(), *i, &temp);
+                    moves.push((last as usize, *i));
+                    break;
+                }
+            }
+            if flag {break;}
+            seeks_set.remove(i);
+        }
+        self.conf.tables.get_mut(table_name).unwrap().count -= seeks.len();
+        moves
+    }
+
+    //= 表达式 ===========================================
+    fn eval_expression<F>(&mut self, expression:&Expression, resolve:&F) -> Result<ExpressionType, String>
+        where F: Fn(&[String]) -> Result<ExpressionType, String> {
+        //计算一个后缀表达式。变量的值交给resolve从当前记录中取得。
+        let mut que:Vec<ExpressionType> = vec![]; //中转存储区。
+        for (i, exp) in expression.li.iter().enumerate() {
+            match exp {
+                ExpressionType::Var(prop) => {
+                    match resolve(&prop[..]) {
+                        Result::Ok(value) => que.push(value),
+                        Result::Err(e) => {return Result::Err(e);}
+                    }
+                },
+                &ExpressionType::Integer(..) |
+                &ExpressionType::Float(..) |
+                &ExpressionType::Str(..) |
+                &ExpressionType::Bool(..) |
+                &ExpressionType::Date(..) |
+                &ExpressionType::Time(..) |
+                &ExpressionType::Timestamp(..) |
+                &ExpressionType::Decimal(..) |
+                &ExpressionType::Null => {
+                    que.push(exp.copy());
+                },
+                ExpressionType::Signal(sign) => {
+                    //直接从que中弹出顶端参数。
+                    let argc = Expression::arity_of(sign);
+                    if que.len() < argc {
+                        return Result::Err("Syntax expression error.".to_string());
+                    }
+                    let value = if argc == 1 {
+                        let p1 = que.pop().unwrap();
+                        ExpressionType::make_one(&p1, sign.as_str())
+                    }else if argc == 3 {
+                        let p3 = que.pop().unwrap();
+                        let p2 = que.pop().unwrap();
+                        let p1 = que.pop().unwrap();
+                        ExpressionType::make_three(&p1, &p2, &p3, sign.as_str())
+                    }else{
+                        let p2 = que.pop().unwrap();
+                        let p1 = que.pop().unwrap();
+                        ExpressionType::make_two(&p1, &p2, sign.as_str())
+                    };
+                    match value {
+                        Result::Ok(ok) => que.push(ok),
+                        Result::Err(e) => {return Result::Err(e);}
+                    }
+                },
+                ExpressionType::Select(sub) => {
+                    //子查询中对外层表的引用先替换为当前记录的值，因此相关子查询对每条外层记录都会执行一次。
+                    //in/exists的参数作为一个列表参与计算，其他位置的子查询是一个标量。
+                    let next = match expression.li.get(i + 1) {
+                        Option::Some(&ExpressionType::Func(ref name, 2)) if name == "in" || name == "notin" => "in",
+                        Option::Some(&ExpressionType::Func(ref name, 1)) if name == "exists" => "exists",
+                        _ => ""
+                    };
+                    //没有引用外层记录的子查询对每条记录的结果都相同，一条语句中只执行一次。
+                    let bound = Cell::new(false);
+                    let mut sub = bind_outer(sub, &[], &|prop:&[String]| {
+                        let value = resolve(prop);
+                        if value.is_ok() {bound.set(true);}
+                        value
+                    });
+                    if next != "in" {
+                        //exists只需要知道有没有记录，标量只需要知道是否多于一条。
+                        let most = if next == "exists" {1}else{2};
+                        if sub.limit.is_none_or(|l|l > most) {sub.limit = Option::Some(most);}
+                    }
+                    let key = if bound.get() {Option::None}else{Option::Some(format!("{}:{}", next, sub.to_json()))};
+                    if let Option::Some(value) = key.as_ref().and_then(|k|self.subqueries.get(k)) {
+                        que.push(value.copy());
+                        continue;
+                    }
+                    let dt = match self.get_table_sub(&sub) {
+                        DResult::Table(dt) => dt,
+                        DResult::String(e) => {return Result::Err(e);},
+                        _ => {return Result::Err("Wrong subquery.".to_string());}
+                    };
+                    let value = if next == "exists" {
+                        ExpressionType::List(dt.content.iter().map(|_|ExpressionType::Null).collect())
+                    }else if dt.head.len() != 1 {
+                        return Result::Err("Subquery must return only one column.".to_string());
+                    }else if next == "in" {
+                        ExpressionType::List(dt.content.iter().map(|d|d.li[0].to_expt()).collect())
+                    }else if dt.content.len() > 1 {
+                        return Result::Err("Subquery returned more than one row.".to_string());
+                    }else{
+                        dt.content.first().map_or(ExpressionType::Null, |d|d.li[0].to_expt())
+                    };
+                    if let Option::Some(k) = key {
+                        self.subqueries.insert(k, value.copy());
+                    }
+                    que.push(value);
+                },
+                &ExpressionType::Func(ref name, 1) if name == "exists" => {
+                    match que.pop() {
+                        Option::Some(ExpressionType::List(rows)) => que.push(ExpressionType::Bool(!rows.is_empty())),
+                        _ => {return Result::Err("Function exists needs a subquery.".to_string());}
+                    }
+                },
+                &ExpressionType::Func(ref name, argc) if name == "in" || name == "notin" => {
+                    if argc == 0 || que.len() < argc {
+                        return Result::Err("Syntax expression error.".to_string());
+                    }
+                    let mut list = que.split_off(que.len() - argc + 1);
+                    let p1 = que.pop().unwrap();
+                    //in (子查询)的唯一参数是子查询的结果列。
+                    let is_list = if let Option::Some(&ExpressionType::List(_)) = list.first() {list.len() == 1}else{false};
+                    if is_list {
+                        if let Option::Some(ExpressionType::List(values)) = list.pop() {list = values;}
+                    }
+                    match ExpressionType::make_in(&p1, &list, name.as_str()) {
+                        Result::Ok(ok) => que.push(ok),
+                        Result::Err(e) => {return Result::Err(e);}
+                    }
+                },
+                &ExpressionType::Func(ref name, argc) if Expression::is_scalar_name(name) => {
+                    if que.len() < argc {
+                        return Result::Err("Syntax expression error.".to_string());
+                    }
+                    let params = que.split_off(que.len() - argc);
+                    match ExpressionType::make_func(name.as_str(), &params) {
+                        Result::Ok(ok) => que.push(ok),
+                        Result::Err(e) => {return Result::Err(e);}
+                    }
+                },
+                ExpressionType::Func(name, _) => {
+                    //聚合函数在分组阶段就已经被替换为它的值。
+                    return Result::Err(if exp.is_aggregate() {
+                        format!("Aggregate function {} is not allowed here.", name)
+                    }else{
+                        format!("Unknown function: {}.", name)
+                    });
+                },
+                _ => {}
+            }
+        }
+        //最后剩下的一个是运算结果。
+        if que.len() == 1 {
+            Result::Ok(que.pop().unwrap())
+        }else {
+            Result::Err("Wrong expression.".to_string())
+        }
+    }
+    fn fold_aggregates(&mut self, expression:&Expression, groups:&[Expression], origin:&[(String, DTable)], rows:&[&HashMap<String, Data>]) -> Result<Expression, String> {
+        //将表达式中的聚合函数在一组记录上求值，替换为常量。
+        //剩下的变量都在聚合函数之外，它们必须是分组的字段。
+        let (origin_starts, _) = expression.subtree_starts();
+        for (i, e) in expression.li.iter().enumerate() {
+            if e.is_aggregate() && expression.li[origin_starts[i]..i].iter().any(|a|a.is_aggregate()) {
+                return Result::Err("Aggregate function cannot be nested.".to_string());
+            }
+        }
+        let mut li:Vec<ExpressionType> = Vec::new();
+        let mut starts:Vec<usize> = Vec::new(); //li中每棵子树的起点
+        for e in expression.li.iter() {
+            let mut start = li.len();
+            for _ in 0..Expression::arity(e) {
+                if let Option::Some(s) = starts.pop() {start = s;}
+            }
+            if let &ExpressionType::Func(ref name, argc) = e {
+                if e.is_aggregate() {
+                    let arg = Expression{li: li.split_off(start), setence: String::new()};
+                    match self.aggregate(name.as_str(), argc, &arg, origin, rows) {
+                        Result::Ok(value) => li.push(value),
+                        Result::Err(e) => {return Result::Err(e);}
+                    }
+                    starts.push(start);
+                    continue;
+                }
+            }
+            li.push(e.copy());
+            starts.push(start);
+        }
+        for e in li.iter() {
+            if let ExpressionType::Var(prop) = e {
+                if !is_grouped(prop, groups) {
+                    return Result::Err(format!("Field {} must appear in group by or in an aggregate function.", prop.join(".")));
+                }
+            }
+        }
+        Result::Ok(Expression{li, setence: expression.setence.to_string()})
+    }
+    fn aggregate(&mut self, name:&str, argc:usize, arg:&Expression, origin:&[(String, DTable)], rows:&[&HashMap<String, Data>]) -> Result<ExpressionType, String> {
+        //在一组记录上计算聚合函数。除count(*)外，null值都不参与计算。
+        //带distinct时相同的值只计算一次。
+        let (name, distinct) = match name.strip_suffix(" distinct") {
+            Option::Some(n) => (n, true),
+            Option::None => (name, false)
+        };
+        if argc == 0 && name == "count" {
+            return Result::Ok(ExpressionType::Integer(rows.len() as i64));
+        }else if argc != 1 {
+            return Result::Err(format!("Function {} needs 1 argument.", name));
+        }
+        let mut values = Vec::new();
+        for resource in rows.iter() {
+            match self.eval_expression(arg, &|prop:&[String]| get_resource_value(origin, resource, prop)) {
+                Result::Ok(ExpressionType::Null) => {},
+                Result::Ok(v) => {
+                    if distinct && values.iter().any(|u|DataItem::from_expt(u).cmp(&DataItem::from_expt(&v)) == Result::Ok(Ordering::Equal)) {
+                        continue;
+                    }
+                    values.push(v);
+                },
+                Result::Err(e) => {return Result::Err(e);}
+            }
+        }
+        match name {
+            "count" => Result::Ok(ExpressionType::Integer(values.len() as i64)),
+            "sum" | "avg" => {
+                let mut sum = ExpressionType::Integer(0);
+                for v in values.iter() {
+                    match v {
+                        &ExpressionType::Integer(..) | &ExpressionType::Float(..) | &ExpressionType::Decimal(..) => {},
+                        _ => {return Result::Err(format!("Function {} needs numeric values.", name));}
+                    }
+                    sum = match ExpressionType::make_two(&sum, v, "+") {
+                        Result::Ok(ok) => ok,
+                        Result::Err(e) => {return Result::Err(e);}
+                    };
+                }
+                if values.is_empty() {Result::Ok(ExpressionType::Null)}
+                else if name == "sum" {Result::Ok(sum)}
+                else {ExpressionType::make_two(&sum, &ExpressionType::Float(values.len() as f64), "/")}
+            },
+            _ => {
+                //min/max
+                let mut ret:Option<ExpressionType> = Option::None;
+                for v in values.into_iter() {
+                    let replace = match ret {
+                        Option::None => true,
+                        Option::Some(ref r) => match DataItem::from_expt(&v).cmp(&DataItem::from_expt(r)) {
+                            Result::Ok(o) => if name == "min" {o == Ordering::Less}else{o == Ordering::Greater},
+                            Result::Err(_) => {return Result::Err("Wrong operator param type.".to_string());}
+                        }
+                    };
+                    if replace {ret = Option::Some(v);}
+                }
+                Result::Ok(ret.unwrap_or(ExpressionType::Null))
+            }
+        }
+    }
+    fn eval_condition<F>(&mut self, expression:&Expression, resolve:&F) -> Result<bool, String>
+        where F: Fn(&[String]) -> Result<ExpressionType, String> {
+        //计算where条件。没有条件时全部通过；结果为null时按false处理。
+        if expression.li.is_empty() {return Result::Ok(true);}
+        match self.eval_expression(expression, resolve) {
+            Result::Ok(ExpressionType::Bool(b)) => Result::Ok(b),
+            Result::Ok(ExpressionType::Null) => Result::Ok(false),
+            Result::Ok(_) => Result::Err("Wrong where expression.".to_string()),
+            Result::Err(e) => Result::Err(e)
+        }
+    }
+
+    //= 索引 =============================================
+    fn get_table_indexes(&self, table_name:&str) -> Vec<String> {
+        //取得建立在某个表上的所有索引名。
+        let mut ret = Vec::new();
+        for (name, index) in self.conf.indexes.iter() {
+            if index.table == table_name {ret.push(name.to_string());}
+        }
+        ret
+    }
+    fn get_table_index_fields(&self, table_name:&str) -> Vec<(String, usize, FieldType)> {
+        //取得表上的所有索引以及它们的字段：(索引名, 字段下标, 字段类型)
+        let mut ret = Vec::new();
+        for name in self.get_table_indexes(table_name) {
+            let field = self.conf.indexes[&name].field.to_string();
+            for (index, f) in self.conf.tables[table_name].fields.iter().enumerate() {
+                if f.name == field {ret.push((name.to_string(), index, f.t));}
+            }
+        }
+        ret
+    }
+    fn get_field_index(&self, table_name:&str, field:&str) -> Option<String> {
+        //取得建立在某个字段上的索引名。
+        for (name, index) in self.conf.indexes.iter() {
+            if index.table == table_name && index.field == field {return Option::Some(name.to_string());}
+        }
+        Option::None
+    }
+    fn get_field_type(&self, table_name:&str, field:&str) -> Option<FieldType> {
+        if let Option::Some(table_conf) = self.conf.tables.get(table_name) {
+            for f in table_conf.fields.iter() {
+                if f.name == field {return Option::Some(f.t);}
+            }
+        }
+        Option::None
+    }
+    fn write_index_entry(&mut self, name:&str, seek:usize, d:&Data) {
+        //向索引页写入一条(键, 行号)记录，必要时扩展新页。
+        let mut pages = self.conf.index_pages.remove(name).unwrap_or(vec![]);
+        self.reserve_pages(&mut pages, seek, d.len(), PageType::Index(name.to_string()));
+        self.file.write(&pages[..], self.conf.pages.len(), seek, d);
+        self.conf.index_pages.insert(name.to_string(), pages);
+    }
+    fn store_overflow(&mut self, table_name:&str, row:&mut Data) {
+        //把记录中尚未写入溢出页的text/blob内容写入溢出页，并在记录中填上地址。
+        //不超过INLINE_SIZE的内容直接保存在记录中，不超过SHARED_MAX的内容写入共享溢出页，更长的内容写入新分配的溢出页链。
+        for item in row.li.iter_mut() {
+            let content = match *item {
+                DataItem::Text(Option::None, ref s) => s.as_bytes().to_vec(),
+                DataItem::Blob(Option::None, ref b) => b.to_vec(),
+                _ => {continue;}
+            };
+            if content.len() <= INLINE_SIZE {continue;}
+            let addr = if content.len() <= SHARED_MAX {
+                let (page, offset) = self.conf.alloc_shared(table_name, content.len());
+                self.file.write_shared(page, offset, &content[..]);
+                page * PAGE_SIZE + offset
+            }else{
+                let mut pages = Vec::new();
+                for _ in 0..SaveFile::overflow_count(content.len()) {
+                    pages.push(self.conf.alloc_page(PageType::Overflow(table_name.to_string())));
+                }
+                self.file.write_overflow(&pages[..], &content[..]);
+                pages[0] * PAGE_SIZE
+            };
+            match *item {
+                DataItem::Text(ref mut p, _) | DataItem::Blob(ref mut p, _) => {*p = Option::Some(addr);},
+                _ => {}
+            }
+        }
+    }
+    fn free_overflow(&mut self, addrs:&[usize]) {
+        //将溢出页链整条归还到空闲页表；共享溢出页中的值逐个释放，整页不再使用时归还。
+        for &addr in addrs.iter() {
+            if addr.is_multiple_of(PAGE_SIZE) {
+                let chain = self.file.overflow_pages(addr);
+                self.conf.free_pages(&chain[..]);
+            }else{
+                self.conf.release_shared(addr / PAGE_SIZE);
+            }
+        }
+    }
+    fn reserve_pages(&mut self, pages:&mut Vec<usize>, seek:usize, len:usize, owner:PageType) {
+        //保证页链足够容纳第seek条长度为len的记录。不够的页从空闲页表中分配。
+        let need = (seek + 1) * len / PAGE_SIZE + 1;
+        while pages.len() < need {
+            pages.push(self.conf.alloc_page(owner.copy()));
+        }
+    }
+    fn load_index(&mut self, name:&str, t:FieldType) {
+        //将索引从索引页加载到内存中的B树。已经加载过的不再重复加载。
+        if self.conf.index_trees.contains_key(name) {return;}
+        let tree = self.read_index(name, t);
+        self.conf.index_trees.insert(name.to_string(), tree);
+    }
+    fn read_index(&mut self, name:&str, t:FieldType) -> IndexTree {
+        let count = self.conf.indexes[name].count;
+        let mut temp = self.conf.indexes[name].get_template(&t);
+        let mut tree:IndexTree = BTreeMap::new();
+        {
+            let pages = match self.conf.index_pages.get(name) {
+                Option::Some(p) => &p[..],
+                Option::None => &[]
+            };
+            for i in 0..count {
+                self.file.read(pages, self.conf.pages.len(), i, &mut temp);
+                let seek = if let DataItem::Integer(s) = temp.li[1] {s as usize}else{continue;};
+                tree.entry(IndexKey(temp.li[0].copy())).or_insert(vec![]).push(seek);
+            }
+        }
+        tree
+    }
+    fn index_lookup(&mut self, name:&str, t:FieldType, oper:&str, key:&DataItem) -> Vec<usize> {
+        //在索引上查找满足 字段 oper key 的所有行号，按行号排序。
+        //只读的会话不能修改配置，还没有加载过的索引读到会话自己的loaded中。
+        let tree = if self.conf.is_writable() || self.conf.index_trees.contains_key(name) {
+            self.load_index(name, t);
+            &self.conf.index_trees[name]
+        }else{
+            if !self.loaded.contains_key(name) {
+                let tree = self.read_index(name, t);
+                self.loaded.insert(name.to_string(), tree);
+            }
+            &self.loaded[name]
+        };
+        let k = IndexKey(key.copy());
+        let mut ret = Vec::new();
+        {
+            let mut push = |v:&Vec<usize>| {for i in v.iter() {ret.push(*i);}};
+            match oper {
+                "=" => {if let Option::Some(v) = tree.get(&k) {push(v);}},
+                "<" => {for (_, v) in tree.range((Unbounded, Excluded(&k))) {push(v);}},
+                "<=" => {for (_, v) in tree.range((Unbounded, Included(&k))) {push(v);}},
+                ">" => {for (_, v) in tree.range((Excluded(&k), Unbounded)) {push(v);}},
+                ">=" => {for (_, v) in tree.range((Included(&k), Unbounded)) {push(v);}},
+                _ => {}
+            }
+        }
+        ret.sort();
+        ret
+    }
+    fn index_add(&mut self, name:&str, t:FieldType, key:DataItem, seek:usize) {
+        //新插入一条记录时，向索引追加一条(键, 行号)。
+        self.load_index(name, t);
+        let count = self.conf.indexes[name].count;
+        let d = Data::new(vec![key.copy(), DataItem::Integer(seek as i64)]);
+        self.write_index_entry(name, count, &d);
+        self.conf.indexes.get_mut(name).unwrap().count += 1;
+        self.conf.index_trees.get_mut(name).unwrap().entry(IndexKey(key)).or_insert(vec![]).push(seek);
+    }
+    fn index_set(&mut self, name:&str, t:FieldType, seek:usize, old:&DataItem, key:DataItem) {
+        //第seek条记录的键由old改为key。索引页中第seek条就是这条记录的(键, 行号)，原地覆写。
+        self.load_index(name, t);
+        let d = Data::new(vec![key.copy(), DataItem::Integer(seek as i64)]);
+        self.write_index_entry(name, seek, &d);
+        let tree = self.conf.index_trees.get_mut(name).unwrap();
+        let empty = match tree.get_mut(&IndexKey(old.copy())) {
+            Option::Some(v) => {
+                v.retain(|s|*s != seek);
+                v.is_empty()
+            },
+            Option::None => false
+        };
+        if empty {tree.remove(&IndexKey(old.copy()));}
+        tree.entry(IndexKey(key)).or_insert(vec![]).push(seek);
+    }
+    fn index_remove(&mut self, name:&str, t:FieldType, seeks:&[usize], moves:&[(usize, usize)]) {
+        //删除记录之后维护索引：去掉被删除的行；从表尾移来的行，索引页中的记录同样移到新的位置并改写行号。
+        //多出来的索引页归还到空闲页表。
+        self.load_index(name, t);
+        let mut temp = self.conf.indexes[name].get_template(&t);
+        let pages = self.conf.index_pages.get(name).map(|p|p.to_vec()).unwrap_or(vec![]);
+        for &(from, to) in moves.iter() {
+            self.file.read(&pages[..], self.conf.pages.len(), from, &mut temp);
+            temp.li[1] = DataItem::Integer(to as i64);
+            self.file.write(&pages[..], self.conf.pages.len(), to, &temp);
+        }
+        let deleted:HashSet<usize> = seeks.iter().copied().collect();
+        let moved:HashMap<usize, usize> = moves.iter().copied().collect();
+        {
+            let tree = self.conf.index_trees.get_mut(name).unwrap();
+            for v in tree.values_mut() {
+                v.retain(|s|!deleted.contains(s));
+                for s in v.iter_mut() {
+                    if let Option::Some(to) = moved.get(s) {*s = *to;}
+                }
+            }
+            tree.retain(|_, v|!v.is_empty());
+        }
+        self.conf.indexes.get_mut(name).unwrap().count -= seeks.len();
+        self.trim_index_pages(name, t);
+    }
+    fn trim_index_pages(&mut self, name:&str, t:FieldType) {
+        //索引的记录变少之后，把超出需要的索引页归还到空闲页表。
+        let count = self.conf.indexes[name].count;
+        let len = self.conf.indexes[name].get_template(&t).len();
+        let need = if count == 0 {0}else{count * len / PAGE_SIZE + 1};
+        let rest = match self.conf.index_pages.get_mut(name) {
+            Option::Some(pages) if pages.len() > need => pages.split_off(need),
+            _ => {return;}
+        };
+        self.conf.free_pages(&rest[..]);
+    }
+    fn rebuild_index(&mut self, name:&str) {
+        //按表中的现有数据重建整个索引，并重写索引页。
+        let table_name = self.conf.indexes[name].table.to_string();
+        let field = self.conf.indexes[name].field.to_string();
+        let mut field_index = 0;
+        for (i, f) in self.conf.tables[&table_name].fields.iter().enumerate() {
+            if f.name == field {field_index = i;}
+        }
+        let mut tree:IndexTree = BTreeMap::new();
+        let mut count = 0;
+        if let DResult::Table(dt) = self.get_table_name(table_name.as_str()) {
+            for (seek, row) in dt.content.iter().enumerate() {
+                let d = Data::new(vec![row.li[field_index].copy(), DataItem::Integer(seek as i64)]);
+                self.write_index_entry(name, seek, &d);
+                tree.entry(IndexKey(row.li[field_index].copy())).or_insert(vec![]).push(seek);
+                count += 1;
+            }
+        }
+        self.conf.indexes.get_mut(name).unwrap().count = count;
+        self.conf.index_trees.insert(name.to_string(), tree);
+        if let Option::Some(t) = self.get_field_type(table_name.as_str(), field.as_str()) {
+            self.trim_index_pages(name, t);
+        }
+    }
+    fn rebuild_table_indexes(&mut self, table_name:&str) {
+        for name in self.get_table_indexes(table_name) {
+            self.rebuild_index(name.as_str());
+        }
+    }
+    fn join_rows(&mut self, rows:Vec<HashMap<String, Data>>, origin:&[(String, DTable)], index:usize, join:&JoinSyntax) -> Result<Vec<HashMap<String, Data>>, String> {
+        //把origin[index]的表按join的条件连接到已有的组合记录上。
+        //on中形如 左表.字段 = 右表.字段 的条件用来构造哈希表，其余情况逐对比较。候选的每一对仍要计算完整的on条件。
+        let name = &origin[index].0;
+        let table = &origin[index].1;
+        let left_names:Vec<String> = origin[..index].iter().map(|o| o.0.to_string()).collect();
+        let mut keys:Vec<(Vec<String>, usize)> = Vec::new(); //左侧的变量，右表的列索引。
+        for cond in join.on.split_and().iter() {
+            if cond.li.len() != 3 {continue;}
+            if let ExpressionType::Signal(ref s) = cond.li[2] {
+                if s != "=" {continue;}
+            }else{continue;}
+            let (left, right) = match (&cond.li[0], &cond.li[1]) {
+                (ExpressionType::Var(a), ExpressionType::Var(b)) if a.len() == 2 && b.len() == 2 => {
+                    if &b[0] == name && left_names.contains(&a[0]) {(a, b)}
+                    else if &a[0] == name && left_names.contains(&b[0]) {(b, a)}
+                    else {continue;}
+                },
+                _ => continue
+            };
+            match table.head.iter().position(|h| h == &right[1]) {
+                Option::Some(i) => {keys.push((left.clone(), i));},
+                Option::None => {return Result::Err(format!("Field {} not found.", right[1]));}
+            }
+        }
+        let mut hash:HashMap<Vec<String>, Vec<usize>> = HashMap::new();
+        if !keys.is_empty() {
+            for (i, d) in table.content().iter().enumerate() {
+                let mut key = Vec::new();
+                for &(_, col) in keys.iter() {
+                    match join_key(&d.li[col].to_expt()) {
+                        Option::Some(k) => {key.push(k);},
+                        Option::None => {key.clear(); break;}
+                    }
+                }
+                if key.len() == keys.len() {
+                    hash.entry(key).or_default().push(i);
+                }
+            }
+        }
+        let all:Vec<usize> = (0..table.content().len()).collect();
+        let empty:Vec<usize> = Vec::new();
+        let mut matched:HashSet<usize> = HashSet::new(); //右表中有过匹配的记录。
+        let mut ret = Vec::new();
+        for map in rows.into_iter() {
+            let candidates = if keys.is_empty() {&all}else{
+                let mut key = Vec::new();
+                for (var, _) in keys.iter() {
+                    match get_resource_value(origin, &map, var) {
+                        Result::Ok(v) => match join_key(&v) {
+                            Option::Some(k) => {key.push(k);},
+                            Option::None => {break;}
+                        },
+                        Result::Err(e) => {return Result::Err(e);}
+                    }
+                }
+                if key.len() == keys.len() {hash.get(&key).unwrap_or(&empty)}else{&empty}
+            };
+            let mut found = false;
+            for &i in candidates.iter() {
+                let mut row = HashMap::new();
+                for (k, v) in map.iter() {row.insert(k.to_string(), v.copy());}
+                row.insert(name.to_string(), table.content()[i].copy());
+                match self.eval_condition(&join.on, &|prop:&[String]| get_resource_value(origin, &row, prop)) {
+                    Result::Ok(true) => {
+                        found = true;
+                        matched.insert(i);
+                        ret.push(row);
+                    },
+                    Result::Ok(false) => {},
+                    Result::Err(e) => {return Result::Err(e);}
+                }
+            }
+            if !found && join.kind == "left" {
+                let mut row = map;
+                row.insert(name.to_string(), null_data(table.head.len()));
+                ret.push(row);
+            }
+        }
+        if join.kind == "right" {
+            for i in 0..table.content().len() {
+                if matched.contains(&i) {continue;}
+                let mut row = HashMap::new();
+                for o in origin[..index].iter() {
+                    row.insert(o.0.to_string(), null_data(o.1.head.len()));
+                }
+                row.insert(name.to_string(), table.content()[i].copy());
+                ret.push(row);
+            }
+        }
+        Result::Ok(ret)
     }
+    fn get_index_candidates(&mut self, alias:&str, table_name:&str, conditions:&[Expression], single:bool) -> Option<Vec<usize>> {
+        //从where的各个&&条件中找出形如 字段 比较符 常量 且字段上有索引的条件，
+        //用索引求出候选行号。多个条件的结果取交集。没有可用条件时返回None。
+        if ! self.conf.tables.contains_key(table_name) {return Option::None;}
+        let mut ret:Option<Vec<usize>> = Option::None;
+        for cond in conditions.iter() {
+            if cond.li.len() != 3 {continue;}
+            let oper = if let ExpressionType::Signal(ref s) = cond.li[2] {s.to_string()}else{continue;};
+            //常量在左侧时需要翻转比较符。
+            let (var, value, oper) = match (&cond.li[0], &cond.li[1]) {
+                (ExpressionType::Var(v), value) => (v, value, oper),
+                (value, ExpressionType::Var(v)) => (v, value, match oper.as_str() {
+                    "<" => ">".to_string(), "<=" => ">=".to_string(),
+                    ">" => "<".to_string(), ">=" => "<=".to_string(),
+                    other => other.to_string()
+                }),
+                _ => continue
+            };
+            if !(oper == "=" || oper == "<" || oper == "<=" || oper == ">" || oper == ">=") {continue;}
+            let field = if var.len() == 2 && var[0] == alias {var[1].to_string()}
+                else if var.len() == 1 && single {var[0].to_string()}
+                else {continue;};
+            let name = match self.get_field_index(table_name, field.as_str()) {
+                Option::Some(n) => n,
+                Option::None => continue
+            };
+            let t = self.get_field_type(table_name, field.as_str()).unwrap();
+            //常量必须能够不失真地转换为字段的类型。浮点数的等值比较带有误差，不使用索引。
+            let key = match (t, value) {
+                (FieldType::Integer, &ExpressionType::Integer(i)) => DataItem::Integer(i),
+                (FieldType::Float, &ExpressionType::Integer(i)) if oper != "=" => DataItem::Float(i as f64),
+                (FieldType::Float, &ExpressionType::Float(f)) if oper != "=" => DataItem::Float(f),
+                (FieldType::Bool, &ExpressionType::Bool(b)) => DataItem::Bool(b),
+                (FieldType::Str(u), ExpressionType::Str(s)) => DataItem::Str(u, s.to_string()),
+                //定点小数的常量按字段的小数位数舍入后必须与原值相等，否则舍入会改变比较的结果。
+                (FieldType::Decimal(..), value) => {
+                    match DataItem::from_expt_typed(value, t) {
+                        Option::Some(ref d) if d.is_null() => continue,
+                        Option::Some(d) => match ExpressionType::make_two(&d.to_expt(), value, "=") {
+                            Result::Ok(ExpressionType::Bool(true)) => d,
+                            _ => continue
+                        },
+                        Option::None => continue
+                    }
+                },
+                (FieldType::Date, value) | (FieldType::Time, value) | (FieldType::Timestamp, value) => {
+                    match DataItem::from_expt_typed(value, t) {
+                        Option::Some(ref d) if d.is_null() => continue,
+                        Option::Some(d) => d,
+                        Option::None => continue
+                    }
+                },
+                _ => continue
+            };
+            let seeks = self.index_lookup(name.as_str(), t, oper.as_str(), &key);
+            ret = Option::Some(match ret {
+                Option::None => seeks,
+                Option::Some(prev) => {
+                    let set:HashSet<usize> = seeks.into_iter().collect();
+                    prev.into_iter().filter(|i| set.contains(i)).collect()
+                }
+            });
+        }
+        ret
+    }
+
     pub fn help(&mut self, syntax:&HelpSyntax) -> DResult {
         // help有4种理论支持的语法。
         /*  1. database 显示所有table/view/index的信息以及对象类型
@@ -1330,6 +2667,12 @@ impl<'t> Database<'t> {
                         _ => {}
                     }
                 }
+                if !self.conf.indexes.is_empty() {
+                    multi.push(MultiResult::String("INDEX".to_string()));
+                    if let DResult::Table(dt) = self.help_index(Option::None) {
+                        multi.push(MultiResult::Table(dt));
+                    }
+                }
                 DResult::Multi(multi)
             },
             "table" => {
@@ -1347,6 +2690,18 @@ impl<'t> Database<'t> {
                 }else {
                     DResult::String(format!("View {} is not found.", name))
                 }
+            },
+            "index" => {
+                if syntax.params.len() < 2 {
+                    self.help_index(Option::None)
+                }else{
+                    let name = syntax.params[1].as_str();
+                    if self.conf.indexes.contains_key(name) {
+                        self.help_index(Option::Some(name))
+                    }else {
+                        DResult::String(format!("Index {} is not found.", name))
+                    }
+                }
             }
             other@_ => DResult::String(format!("Syntax Error: No this syntax [{}].", other))
         }
@@ -1378,16 +2733,58 @@ impl<'t> Database<'t> {
                 DataItem::Bool(field.auto_inc),
                 if table_conf.foreign.contains_key(field.name.as_str()) {
                     let foreign = table_conf.foreign.get(field.name.as_str()).unwrap();
-                    DataItem::Str(0, format!("{}({})", foreign.foreign_table, foreign.foreign_field))
+                    DataItem::Str(0, format!("{}({}) {}", foreign.foreign_table, foreign.foreign_field, foreign.delete_action.to_string()))
                 }else{
                     DataItem::Str(0, "".to_string())
                 }
             ];
             content.push(Data{li:li});
         }
-        DResult::Table(DTable{
+        let table = DTable{
             head: head,
             content: content
+        };
+        //有组合主键、组合unique或check约束时在表格之后逐条列出。
+        let mut multi = vec![MultiResult::Table(table)];
+        if table_conf.primary.len() > 1 {
+            multi.push(MultiResult::String(format!("PRIMARY KEY ({})", table_conf.primary.join(", "))));
+        }
+        for u in table_conf.uniques.iter() {
+            multi.push(MultiResult::String(format!("UNIQUE ({})", u.join(", "))));
+        }
+        for (name, e) in table_conf.checks.iter() {
+            multi.push(MultiResult::String(format!("CHECK {}: {}", name, e.setence)));
+        }
+        if multi.len() == 1 {
+            if let Option::Some(MultiResult::Table(t)) = multi.pop() {return DResult::Table(t);}
+        }
+        DResult::Multi(multi)
+    }
+    fn help_index(&self, index_name:Option<&str>) -> DResult {
+        //用表格罗列索引的信息。不指定索引名时列出全部索引。
+        //|index|table|field|
+        let head = vec![
+            "index".to_string(),
+            "table".to_string(),
+            "field".to_string()
+        ];
+        let mut names:Vec<&String> = self.conf.indexes.keys().collect();
+        names.sort();
+        let mut content = Vec::new();
+        for name in names {
+            if let Option::Some(n) = index_name {
+                if n != name.as_str() {continue;}
+            }
+            let index = &self.conf.indexes[name];
+            content.push(Data{li: vec![
+                DataItem::Str(0, index.name.to_string()),
+                DataItem::Str(0, index.table.to_string()),
+                DataItem::Str(0, index.field.to_string())
+            ]});
+        }
+        DResult::Table(DTable{
+            head,
+            content
         })
     }
     fn help_view(&self, view_name:&str) -> DResult {
@@ -1398,13 +2795,59 @@ impl<'t> Database<'t> {
     }
     
 
-    pub fn commit_config(&self) {
+    pub fn commit_config(&mut self) {
         //提交对配置信息的修改到文件，一般包括对表的修改。
-        self.conf.save();
+        //表的修改可能伴随着数据的改写，因此与commit走同样的流程。
+        self.commit();
     }
-    pub fn commit(&self) {
+    pub fn commit(&mut self) {
         //提交对数据的修改到文件。
-        self.conf.save();
+        /*  1. 将脏页与新的配置写入预写日志并落盘；
+            2. 覆写.db与.dba；
+            3. 清除日志。
+            任何一步中断，下次打开数据库时都会得到完整提交前或完整提交后的状态。
+            只修改了配置的提交同样经过日志，覆写.dba时中断也不会留下写了一半的配置。
+        */
+        let config = self.conf.to_json_string();
+        let pages = self.file.take_dirty();
+        let wal = self.conf.wal();
+        wal.write(&pages, &config);
+        self.file.write_pages(&pages);
+        self.conf.save_json(&config);
+        wal.clear();
+    }
+
+    pub fn upgrade(&mut self) {
+        //将旧版本的数据文件升级为当前格式：旧的记录没有空值位图，需要逐表读出后按新格式重写，再重建全部索引。
+        //旧版本外键的删除动作也改为现在的默认值。
+        let names:Vec<String> = self.conf.tables.keys().map(|k|k.to_string()).collect();
+        for name in names.iter() {
+            let mut temp = self.conf.tables[name].get_template();
+            let count = self.conf.tables[name].count;
+            let mut pages = self.conf.table_pages.remove(name).unwrap_or(vec![]);
+            let mut rows = Vec::with_capacity(count);
+            for i in 0..count {
+                self.file.read_legacy(&pages[..], self.conf.pages.len(), i, &mut temp);
+                rows.push(temp.copy());
+            }
+            for (i, r) in rows.iter().enumerate() {
+                self.reserve_pages(&mut pages, i, r.len(), PageType::Data(name.to_string()));
+                self.file.write(&pages[..], self.conf.pages.len(), i, r);
+            }
+            self.conf.table_pages.insert(name.to_string(), pages);
+        }
+        let indexes:Vec<String> = self.conf.indexes.keys().map(|k|k.to_string()).collect();
+        for name in indexes.iter() {
+            self.rebuild_index(name);
+        }
+        //旧版本不能声明外键的删除动作，保存的cascade只是当时的默认值，按现在的默认值改为restrict。
+        for table in self.conf.tables.values_mut() {
+            for foreign in table.foreign.values_mut() {
+                foreign.delete_action = ForeignType::Restrict;
+            }
+        }
+        self.conf.version = DATA_VERSION;
+        self.commit();
     }
 
     pub fn has_table(&self, table_name:&str) -> bool {
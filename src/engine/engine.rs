@@ -1,14 +1,19 @@
 use super::ini::{Config};
 use std::collections::HashMap;
-use super::save::{ConfigFile, DataItem};
+use std::mem::{take};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration};
+use super::save::{ConfigFile, Data, DataItem};
 use super::database::{Database, DResult};
+use super::lock::{SharedConfig, ReadGuard, WriteGuard, LockMode, new_owner};
 use super::super::syntax::structures::{
     Switch, Expression, ExpressionType, SelectSyntax, InsertSyntax, DeleteSyntax, UpdateSyntax,
-    CreateTableSyntax, TableFieldSyntax,
+    CreateTableSyntax, AlterTableSyntax, TableFieldSyntax, DropTableSyntax,
     CreateDatabaseSyntax, DropDatabaseSyntax, UseSyntax, ColSyntax,
-    CreateUserSyntax, AlterUserSyntax, DropUserSyntax, GrantSyntax
+    CreateUserSyntax, AlterUserSyntax, DropUserSyntax, CreateRoleSyntax, DropRoleSyntax, GrantSyntax
 };
 use super::super::analyse::dfa::{DfaWord};
+use super::super::analyse::password;
 
 macro_rules! hmap {
 ( $( $x:expr => $y:expr ),* ) => {
@@ -25,21 +30,31 @@ macro_rules! hmap {
 
 pub struct Engine{
     conf:Config,  //配置文件
-    system: ConfigFile,  //系统数据库
+    timeout: Duration,  //等待锁的超时时间
+    system: SharedConfig,  //系统数据库
     /*系统数据库包括如下表：
     普通数据库列表
     用户列表
-    用户权限列表
+    用户权限列表(授予用户或角色)
+    角色列表
+    角色成员列表
     */
-    pub databases: HashMap<String, ConfigFile>,  //普通数据库
+    databases: RwLock<HashMap<String, Arc<SharedConfig>>>,  //普通数据库。多个会话可以同时使用引擎。
 }
 impl Engine {
-    pub fn new() -> Self {
-        //加载一个数据库引擎
+    pub fn new() -> Result<Self, String> {
+        //加载一个数据库引擎，使用当前目录下的配置文件。
+        Self::load("dba.ini")
+    }
+    pub fn load(ini:&str) -> Result<Self, String> {
+        //按指定的配置文件加载数据库引擎
         //加载配置文件
-        let conf = Config::load("dba.ini");
-        //加载系统数据库
-        let mut system = ConfigFile::new(conf.database.to_string(), conf.systembase.to_string());
+        let conf = Config::load(ini);
+        //加载系统数据库。系统数据库或任何一个数据库被其他进程占用时，引擎无法启动。
+        let mut system = match ConfigFile::new(conf.database.to_string(), conf.systembase.to_string()) {
+            Result::Ok(c) => c,
+            Result::Err(e) => {return Result::Err(e);}
+        };
         let mut databases = HashMap::new();
         //处理系统数据库内的信息
         {
@@ -51,31 +66,30 @@ impl Engine {
                     fields: vec![
                         TableFieldSyntax{name: "id".to_string(), t: "integer".to_string(), unique: true, primary: true, not_null: true, auto_inc: true, default: Option::None},
                         TableFieldSyntax{name: "name".to_string(), t: "str:64".to_string(), unique: true, primary: false, not_null: true, auto_inc: false, default: Option::None}
-                    ], foreigns: vec![]
-                });
+                    ], foreigns: vec![], checks: vec![], primary: vec![], uniques: vec![]
+                }).ok();
                 //println!("create table database.");
             }
             if !system_db.has_table("user") {
-                system_db.create_table(&CreateTableSyntax{
-                    name: "user".to_string(),
-                    fields: vec![
-                        TableFieldSyntax{name: "id".to_string(), t: "integer".to_string(), unique: true, primary: true, not_null: true, auto_inc: true, default: Option::None},
-                        TableFieldSyntax{name: "username".to_string(), t: "str:24".to_string(), unique: true, primary: false, not_null: true, auto_inc: false, default: Option::None},
-                        TableFieldSyntax{name: "password".to_string(), t: "str:64".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None},
-                        TableFieldSyntax{name: "is_root".to_string(), t: "bool".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::Some("false".to_string())}
-                    ], foreigns: vec![]
-                });
-                //插入默认的超级管理员账户。
+                create_user_table(&mut system_db);
+                //插入默认的超级管理员账户。默认密码是公开的，第一次登录后必须修改。
                 system_db.insert_into(&InsertSyntax{
                     table_name: "user".to_string(),
                     has_head: true,
+                    columns: vec![],
+                    select: Option::None,
                     values: vec![hmap![
                         "username" => DfaWord::Str("root".to_string()),
-                        "password" => DfaWord::Str("root".to_string()),
-                        "is_root" => DfaWord::Bool(true)
+                        "password" => DfaWord::Str(password::hash("root")),
+                        "is_root" => DfaWord::Bool(true),
+                        "must_change" => DfaWord::Bool(true)
                     ]]
-                });
+                }).ok();
                 //println!("create table database.");
+            }else if !system_db.conf.tables["user"].fields.iter().any(|f|f.name == "must_change") {
+                //旧版本的用户表：密码字段放不下散列值，也没有must_change字段，按新的结构重建。
+                //原有的明文密码保持不变，在用户第一次登录成功时换成散列值。
+                migrate_user_table(&mut system_db);
             }
             if !system_db.has_table("privilege") {
                 system_db.create_table(&CreateTableSyntax{
@@ -85,10 +99,41 @@ impl Engine {
                         TableFieldSyntax{name: "username".to_string(), t: "str:24".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None},
                         TableFieldSyntax{name: "database".to_string(), t: "str:64".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None},
                         TableFieldSyntax{name: "table".to_string(), t: "str:64".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None},
-                        TableFieldSyntax{name: "type".to_string(), t: "str:16".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None}
-                    ], foreigns: vec![]
-                });
+                        TableFieldSyntax{name: "type".to_string(), t: "str:16".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None},
+                        TableFieldSyntax{name: "grantor".to_string(), t: "str:24".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::Some("".to_string())},
+                        TableFieldSyntax{name: "grant_option".to_string(), t: "bool".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::Some("false".to_string())}
+                    ], foreigns: vec![], checks: vec![], primary: vec![], uniques: vec![]
+                }).ok();
                 //println!("create table database.");
+            }else if !system_db.conf.tables["privilege"].fields.iter().any(|f|f.name == "grantor") {
+                //旧版本的权限表没有授权者与转授标记。原有的记录视作没有授权者、不能转授。
+                system_db.alter_table(&AlterTableSyntax{
+                    name: "privilege".to_string(),
+                    adds: vec![
+                        TableFieldSyntax{name: "grantor".to_string(), t: "str:24".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::Some("".to_string())},
+                        TableFieldSyntax{name: "grant_option".to_string(), t: "bool".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::Some("false".to_string())}
+                    ],
+                    alters: vec![], drops: vec![], checks: vec![], drop_checks: vec![], uniques: vec![]
+                }).ok();
+            }
+            if !system_db.has_table("role") {
+                system_db.create_table(&CreateTableSyntax{
+                    name: "role".to_string(),
+                    fields: vec![
+                        TableFieldSyntax{name: "id".to_string(), t: "integer".to_string(), unique: true, primary: true, not_null: true, auto_inc: true, default: Option::None},
+                        TableFieldSyntax{name: "name".to_string(), t: "str:24".to_string(), unique: true, primary: false, not_null: true, auto_inc: false, default: Option::None}
+                    ], foreigns: vec![], checks: vec![], primary: vec![], uniques: vec![]
+                }).ok();
+            }
+            if !system_db.has_table("role_member") {
+                system_db.create_table(&CreateTableSyntax{
+                    name: "role_member".to_string(),
+                    fields: vec![
+                        TableFieldSyntax{name: "id".to_string(), t: "integer".to_string(), unique: true, primary: true, not_null: true, auto_inc: true, default: Option::None},
+                        TableFieldSyntax{name: "username".to_string(), t: "str:24".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None},
+                        TableFieldSyntax{name: "role".to_string(), t: "str:24".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None}
+                    ], foreigns: vec![], checks: vec![], primary: vec![], uniques: vec![]
+                }).ok();
             }
             //加载普通数据库配置,从系统数据库读取数据库列表，然后依次加载配置文件。
             if let DResult::Table(ref table) = system_db.select(&SelectSyntax{
@@ -96,6 +141,11 @@ impl Engine {
                 froms: hmap!["database" => Switch::One("database".to_string())],
                 goals: vec![("name".to_string(), Expression::new_single("name"))],
                 wheres: Expression::empty(),
+                joins: vec![],
+                groups: vec![],
+                havings: Expression::empty(),
+                limit: Option::None,
+                offset: 0,
                 orders: vec![]
             }) {
                 //println!("select count: {}", table.content.len());
@@ -103,28 +153,47 @@ impl Engine {
                     if let &DataItem::Str(_, ref ss) = i {
                         //println!("This name is [{}]", ss.trim());
                         let s = ss.trim();
-                        let conf = ConfigFile::new(conf.database.to_string(), s.to_string());
-                        databases.insert(s.to_string(), conf);
+                        let conf = match ConfigFile::new(conf.database.to_string(), s.to_string()) {
+                            Result::Ok(c) => c,
+                            Result::Err(e) => {return Result::Err(e);}
+                        };
+                        databases.insert(s.to_string(), Arc::new(SharedConfig::new(conf)));
                     }
                 }
             }
             system_db.commit_config();
         }
-        Self {
+        Result::Ok(Self {
+            timeout: Duration::from_millis(conf.lock_timeout),
             conf: conf,
-            system: system,
-            databases: databases,
-        }
+            system: SharedConfig::new(system),
+            databases: RwLock::new(databases),
+        })
     }
-    pub fn session(&mut self, user: &str, password: &str) -> Result<Session, String> {
+    fn database(&self, name:&str) -> Option<Arc<SharedConfig>> {
+        self.databases.read().unwrap().get(name).map(|db| db.clone())
+    }
+    fn has_database(&self, name:&str) -> bool {
+        self.databases.read().unwrap().contains_key(name)
+    }
+    fn system_read(&self) -> Result<ReadGuard<'_>, String> {
+        self.system.read_locked(self.timeout)
+    }
+    fn system_write(&self) -> Result<WriteGuard<'_>, String> {
+        self.system.write_locked(self.timeout)
+    }
+    pub fn session(&self, user: &str, password: &str) -> Result<Session<'_>, String> {
         //登陆一个会话进程。
         let result = self.check_user(user, password);
         match result {
-            Result::Ok(..) => {
+            Result::Ok(must_change) => {
                 Result::Ok(Session {
                     engine: self,
+                    id: new_owner(),
                     using: Option::None,
-                    user: user.to_string()
+                    user: user.to_string(),
+                    must_change,
+                    transaction: Option::None
                 })
             },
             Result::Err(ref e) => {
@@ -133,69 +202,134 @@ impl Engine {
         }
     }
 
-    fn check_user(&mut self, user: &str, password: &str) -> Result<(), String> {
-        let mut session = self.system.session();
-        if let DResult::Table(ref dt) = session.select(&SelectSyntax{
-            distinct: false,
-            froms: hmap!["user" => Switch::One("user".to_string())],
-            goals: vec![
-                ("username".to_string(), Expression::new_single("username")),
-                ("password".to_string(), Expression::new_single("password"))
-            ],
-            wheres: Expression{li: vec![
-                ExpressionType::Var(vec!["username".to_string()]),
-                ExpressionType::Str(user.to_string()),
-                ExpressionType::Signal("=".to_string())
-            ], setence: format!("username=\"{}\"", user)},
-            orders: vec![]
-        }) {
-            if dt.content.len() == 1 {
-                let record = dt.get_column("password");
-                if let DataItem::Str(_, ref s) = record[0] {
-                    if s == password {
-                        Result::Ok(())
-                    }else{
-                        Result::Err(format!("Password wrong."))
+    fn check_user(&self, user: &str, password: &str) -> Result<bool, String> {
+        //校验用户的密码，返回该用户是否必须修改密码。
+        //保存的仍是旧的明文密码时，登录成功后换成散列值。
+        let (stored, must_change) = {
+            let system = match self.system_read() {
+                Result::Ok(g) => g,
+                Result::Err(e) => {return Result::Err(e);}
+            };
+            let mut session = system.reader();
+            if let DResult::Table(ref dt) = session.select(&SelectSyntax{
+                distinct: false,
+                froms: hmap!["user" => Switch::One("user".to_string())],
+                goals: vec![
+                    ("password".to_string(), Expression::new_single("password")),
+                    ("must_change".to_string(), Expression::new_single("must_change"))
+                ],
+                wheres: Expression{li: vec![
+                    ExpressionType::Var(vec!["username".to_string()]),
+                    ExpressionType::Str(user.to_string()),
+                    ExpressionType::Signal("=".to_string())
+                ], setence: format!("username=\"{}\"", user)},
+                joins: vec![],
+                groups: vec![],
+                havings: Expression::empty(),
+                limit: Option::None,
+                offset: 0,
+                orders: vec![]
+            }) {
+                if dt.content.len() == 1 {
+                    match (&dt.content[0].li[0], &dt.content[0].li[1]) {
+                        (DataItem::Str(_, s), &DataItem::Bool(b)) => (s.to_string(), b),
+                        _ => {return Result::Err("External Error: wrong data type.".to_string());}
                     }
                 }else{
-                    Result::Err(format!("External Error: wrong data type."))
+                    return Result::Err(format!("User {} is not exists.", user));
                 }
             }else{
-                Result::Err(format!("User {} is not exists.", user))
+                return Result::Err("External Error: cannot read user database.".to_string());
             }
-        }else{
-            Result::Err(format!("External Error: cannot read user database."))
+        };
+        if !password::verify(password, &stored) {
+            return Result::Err("Password wrong.".to_string());
         }
+        if !password::is_hashed(&stored) {
+            if let Result::Err(e) = self.set_password(user, password, must_change) {
+                return Result::Err(e);
+            }
+        }
+        Result::Ok(must_change)
+    }
+    fn set_password(&self, username: &str, pw: &str, must_change: bool) -> Result<(), String> {
+        //把用户的密码换成新的散列值。
+        let hashed = password::hash(pw);
+        let mut system = match self.system_write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return Result::Err(e);}
+        };
+        let mut session = system.session();
+        session.update(&UpdateSyntax{
+            table_name: "user".to_string(),
+            sets: hmap![
+                "password" => Expression{
+                    li: vec![ExpressionType::Str(hashed.to_string())],
+                    setence: format!("\"{}\"", hashed)
+                },
+                "must_change" => Expression{
+                    li: vec![ExpressionType::Bool(must_change)],
+                    setence: format!("{}", must_change)
+                }
+            ],
+            wheres: Expression{li:vec![
+                ExpressionType::Var(vec!["username".to_string()]),
+                ExpressionType::Str(username.to_string()),
+                ExpressionType::Signal("=".to_string())
+            ], setence: format!("username=\"{}\"", username)}
+        }).ok();
+        session.commit();
+        Result::Ok(())
     }
 
-    pub fn create_database(&mut self, syntax:&CreateDatabaseSyntax) -> DResult {
+    pub fn create_database(&self, syntax:&CreateDatabaseSyntax) -> DResult {
         let name = syntax.name.as_str();
-        if self.databases.contains_key(name) {
+        let mut system = match self.system_write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return DResult::String(e);}
+        };
+        if self.has_database(name) {
             DResult::String(format!("Database {} exists.", name))
         }else if name == "" {
             DResult::String(format!("Database name cannot be empty."))
         }else{
-            self.databases.insert(name.to_string(), ConfigFile::new(self.conf.database.to_string(), name.to_string()));
-            self.databases[name].save();
-            let mut session = self.system.session();
+            let conf = match ConfigFile::new(self.conf.database.to_string(), name.to_string()) {
+                Result::Ok(c) => c,
+                Result::Err(e) => {return DResult::String(e);}
+            };
+            conf.save();
+            self.databases.write().unwrap().insert(name.to_string(), Arc::new(SharedConfig::new(conf)));
+            let mut session = system.session();
             session.insert_into(&InsertSyntax{
                 table_name: "database".to_string(),
                 has_head: true,
+                columns: vec![],
+                select: Option::None,
                 values: vec![hmap!["name"=>DfaWord::Str(name.to_string())]]
-            });
+            }).ok();
             session.commit();
             DResult::String(format!("Database {} has created.", name))
         }
     }
-    pub fn drop_database(&mut self, syntax:&DropDatabaseSyntax) -> DResult {
+    pub fn drop_database(&self, syntax:&DropDatabaseSyntax) -> DResult {
         let name = syntax.name.as_str();
         //要删除一个数据库，只需要从engine中清除数据库对象，从system表中删除数据库记录，并删除对应的实体文件。
-        if ! self.databases.contains_key(name) {
-            return DResult::String(format!("Database {} is not exists.", name));
-        }
-        let conf = self.databases.remove(name).unwrap();
+        //删除前要等待正在使用它的会话释放锁。
+        let db = match self.database(name) {
+            Option::Some(db) => db,
+            Option::None => {return DResult::String(format!("Database {} is not exists.", name));}
+        };
+        let conf = match db.write_locked(self.timeout) {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return DResult::String(e);}
+        };
+        let mut system = match self.system_write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return DResult::String(e);}
+        };
+        self.databases.write().unwrap().remove(name);
         conf.delete_file();
-        let mut session = self.system.session();
+        let mut session = system.session();
         session.delete(&DeleteSyntax{
             table_name: "database".to_string(),
             wheres: Expression{li: vec![
@@ -203,17 +337,26 @@ impl Engine {
                 ExpressionType::Str(name.to_string()),
                 ExpressionType::Signal("=".to_string())
             ], setence: format!("name=\"{}\"", name)}
-        });
+        }).ok();
         session.commit();
         DResult::String(format!("Database {} droped.", name))
     }
 
-    pub fn create_user(&mut self, syntax: &CreateUserSyntax) -> DResult {
+    pub fn create_user(&self, syntax: &CreateUserSyntax) -> DResult {
         let username = syntax.username.trim();
         if username == "" {
             return DResult::String(format!("Illegal user name."));
         }
-        let mut session = self.system.session();
+        if syntax.password.trim() == "" {
+            return DResult::String("Password cannot be empty.".to_string());
+        }
+        //散列的计算比较慢，在取得系统数据库的锁之前完成。
+        let hashed = password::hash(syntax.password.trim());
+        let mut system = match self.system_write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return DResult::String(e);}
+        };
+        let mut session = system.session();
         let mut ok = false;
         if let DResult::Table(ref dt) = session.select(&SelectSyntax{
             distinct: false,
@@ -224,6 +367,11 @@ impl Engine {
                 ExpressionType::Str(username.to_string()),
                 ExpressionType::Signal("=".to_string())
             ], setence: format!("username=\"{}\"", username)},
+            joins: vec![],
+            groups: vec![],
+            havings: Expression::empty(),
+            limit: Option::None,
+            offset: 0,
             orders: vec![]
         }) {
             if dt.content.len() > 0 {
@@ -232,64 +380,87 @@ impl Engine {
                 ok = true;
             }
         }
+        match exists(&mut session, "role", "name", username) {
+            Option::Some(false) => {},
+            Option::Some(true) => {return DResult::String(format!("User or role {} is already exists.", username));},
+            Option::None => {return DResult::String("External error: cannot read role list.".to_string());}
+        }
         if ok {
             session.insert_into(&InsertSyntax{
                 table_name: "user".to_string(),
                 has_head: true,
+                columns: vec![],
+                select: Option::None,
                 values:vec![hmap![
                     "username" => DfaWord::Str(username.to_string()),
-                    "password" => DfaWord::Str(syntax.password.trim().to_string()),
+                    "password" => DfaWord::Str(hashed),
                     "is_root" => DfaWord::Bool(syntax.staff)
                 ]]
-            });
+            }).ok();
             session.commit();
             DResult::String(format!("User {} has created.", username))
         }else{
             DResult::String(format!("External error: cannot read user list."))
         }
     }
-    pub fn alter_user(&mut self, syntax: &AlterUserSyntax) -> DResult {
+    pub fn alter_user(&self, syntax: &AlterUserSyntax, by: &str) -> Result<(), String> {
+        //修改用户的密码。用户自己修改时取消必须修改密码的标记；由管理员替别人设置的密码，对方下次登录后必须先修改。
         let username = syntax.username.trim();
-        let mut session = self.system.session();
-        let mut ok = false;
-        if let DResult::Table(ref dt) = session.select(&SelectSyntax{
-            distinct: false,
-            froms: hmap!["user"=>Switch::One("user".to_string())],
-            goals: vec![("username".to_string(), Expression::new_single("username"))],
-            wheres: Expression{li:vec![
-                ExpressionType::Var(vec!["username".to_string()]),
-                ExpressionType::Str(username.to_string()),
-                ExpressionType::Signal("=".to_string())
-            ], setence: format!("username=\"{}\"", username)},
-            orders: vec![]
-        }) {
-            if dt.content.len() <= 0 {
-                return DResult::String(format!("User {} is not exists.", username));
-            }else{
-                ok = true;
-            }
+        let pw = syntax.password.trim();
+        if pw.is_empty() {
+            return Result::Err("Password cannot be empty.".to_string());
         }
-        if ok {
-            session.update(&UpdateSyntax{
-                table_name: "user".to_string(),
-                sets: hmap!["password" => DfaWord::Str(syntax.password.trim().to_string())],
+        let (stored, must_change) = {
+            let system = match self.system_read() {
+                Result::Ok(g) => g,
+                Result::Err(e) => {return Result::Err(e);}
+            };
+            let mut session = system.reader();
+            if let DResult::Table(ref dt) = session.select(&SelectSyntax{
+                distinct: false,
+                froms: hmap!["user"=>Switch::One("user".to_string())],
+                goals: vec![
+                    ("password".to_string(), Expression::new_single("password")),
+                    ("must_change".to_string(), Expression::new_single("must_change"))
+                ],
                 wheres: Expression{li:vec![
                     ExpressionType::Var(vec!["username".to_string()]),
                     ExpressionType::Str(username.to_string()),
                     ExpressionType::Signal("=".to_string())
-                ], setence: format!("username=\"{}\"", username)}
-            });
-            session.commit();
-            DResult::String(format!("User {} has been altered.", username))
-        }else{
-            DResult::String(format!("External error: cannot read user list."))
+                ], setence: format!("username=\"{}\"", username)},
+                joins: vec![],
+                groups: vec![],
+                havings: Expression::empty(),
+                limit: Option::None,
+                offset: 0,
+                orders: vec![]
+            }) {
+                if dt.content.len() <= 0 {
+                    return Result::Err(format!("User {} is not exists.", username));
+                }
+                match (&dt.content[0].li[0], &dt.content[0].li[1]) {
+                    (DataItem::Str(_, s), &DataItem::Bool(b)) => (s.to_string(), b),
+                    _ => {return Result::Err("External error: cannot read user list.".to_string());}
+                }
+            }else{
+                return Result::Err("External error: cannot read user list.".to_string());
+            }
+        };
+        //被要求修改密码时，不能改回原来的密码。
+        if must_change && password::verify(pw, &stored) {
+            return Result::Err("The new password must be different from the old one.".to_string());
         }
+        self.set_password(username, pw, username != by)
     }
-    pub fn drop_user(&mut self, syntax: &DropUserSyntax) -> DResult {
+    pub fn drop_user(&self, syntax: &DropUserSyntax) -> DResult {
         let username = syntax.username.trim();
         if username == "" {return DResult::String(format!("User name cannot be empty."));}
         if username == "root" {return DResult::String(format!("Cannot delete root user."));}
-        let mut session = self.system.session();
+        let mut system = match self.system_write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return DResult::String(e);}
+        };
+        let mut session = system.session();
         let mut ok = false;
         if let DResult::Table(ref dt) = session.select(&SelectSyntax{
             distinct: false,
@@ -300,6 +471,11 @@ impl Engine {
                 ExpressionType::Str(username.to_string()),
                 ExpressionType::Signal("=".to_string())
             ], setence: format!("username=\"{}\"", username)},
+            joins: vec![],
+            groups: vec![],
+            havings: Expression::empty(),
+            limit: Option::None,
+            offset: 0,
             orders: vec![]
         }) {
             if dt.content.len() <= 0 {
@@ -309,6 +485,9 @@ impl Engine {
             }
         }
         if ok {
+            if !drop_grantee(&mut session, username) {
+                return DResult::String("External error: cannot read privileges list.".to_string());
+            }
             session.delete(&DeleteSyntax{
                 table_name: "user".to_string(),
                 wheres: Expression{li:vec![
@@ -316,7 +495,8 @@ impl Engine {
                     ExpressionType::Str(username.to_string()),
                     ExpressionType::Signal("=".to_string())
                 ], setence: format!("username=\"{}\"", username)}
-            });
+            }).ok();
+            session.delete(&DeleteSyntax{table_name: "role_member".to_string(), wheres: where_eq(&[("username", username)])}).ok();
             session.commit();
             DResult::String(format!("User {} has been droped.", username))
         }else{
@@ -324,103 +504,415 @@ impl Engine {
         }
     }
 
-    pub fn grant(&mut self, syntax: &GrantSyntax, db: &str) -> DResult {
+    pub fn create_role(&self, syntax: &CreateRoleSyntax) -> DResult {
+        let name = syntax.name.trim();
+        if name.is_empty() {
+            return DResult::String("Illegal role name.".to_string());
+        }
+        let mut system = match self.system_write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return DResult::String(e);}
+        };
+        let mut session = system.session();
+        //角色与用户共用权限表中的名字，不能重名。
+        match (exists(&mut session, "user", "username", name), exists(&mut session, "role", "name", name)) {
+            (Option::Some(false), Option::Some(false)) => {},
+            (Option::Some(_), Option::Some(_)) => {return DResult::String(format!("User or role {} is already exists.", name));},
+            _ => {return DResult::String("External error: cannot read role list.".to_string());}
+        }
+        session.insert_into(&InsertSyntax{
+            table_name: "role".to_string(),
+            has_head: true,
+            columns: vec![],
+            select: Option::None,
+            values: vec![hmap!["name" => DfaWord::Str(name.to_string())]]
+        }).ok();
+        session.commit();
+        DResult::String(format!("Role {} has created.", name))
+    }
+    pub fn drop_role(&self, syntax: &DropRoleSyntax) -> DResult {
+        //删除角色，同时删除它的成员关系、授予它的权限以及借助它才有效的权限。
+        let name = syntax.name.trim();
+        let mut system = match self.system_write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return DResult::String(e);}
+        };
+        let mut session = system.session();
+        match exists(&mut session, "role", "name", name) {
+            Option::Some(true) => {},
+            Option::Some(false) => {return DResult::String(format!("Role {} is not exists.", name));},
+            Option::None => {return DResult::String("External error: cannot read role list.".to_string());}
+        }
+        if !drop_grantee(&mut session, name) {
+            return DResult::String("External error: cannot read privileges list.".to_string());
+        }
+        session.delete(&DeleteSyntax{table_name: "role".to_string(), wheres: where_eq(&[("name", name)])}).ok();
+        session.delete(&DeleteSyntax{table_name: "role_member".to_string(), wheres: where_eq(&[("role", name)])}).ok();
+        session.commit();
+        DResult::String(format!("Role {} has been droped.", name))
+    }
+
+    pub fn grant(&self, syntax: &GrantSyntax, db: &str, grantor: &str, staff: bool) -> DResult {
         //grant语句赋予或收回权限给目标。
-        let mut session = self.system.session();
-        if syntax.is_grant {
-            let mut values = Vec::new();
-            for user in syntax.users.iter() {
-                for &(ref t, ref obj) in syntax.objects.iter() {
-                    if t == "table" && db == "" {continue;}
-                    if syntax.all {
-                        values.push(hmap![
-                            "username" => DfaWord::Str(user.to_string()),
-                            "database" => DfaWord::Str(if t == "database" {obj.to_string()}else{db.to_string()}),
-                            "table" => DfaWord::Str(if t == "database" {"".to_string()}else{obj.to_string()}),
-                            "type" => DfaWord::Str("all".to_string())
-                        ]);
-                    }else {
-                        for grant in syntax.grants.iter() {
-                            values.push(hmap![
-                                "username" => DfaWord::Str(user.to_string()),
-                                "database" => DfaWord::Str(if t == "database" {obj.to_string()}else{db.to_string()}),
-                                "table" => DfaWord::Str(if t == "database" {"".to_string()}else{obj.to_string()}),
-                                "type" => DfaWord::Str(grant.to_string())
-                            ]);
-                        }
+        //授予时记录授权者；非staff用户只能收回自己授予的权限。
+        let mut system = match self.system_write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return DResult::String(e);}
+        };
+        let mut session = system.session();
+        if !syntax.roles.is_empty() {
+            return grant_roles(&mut session, syntax);
+        }
+        //权限可以授予用户或角色。
+        for user in syntax.users.iter() {
+            match (exists(&mut session, "user", "username", user), exists(&mut session, "role", "name", user)) {
+                (Option::Some(false), Option::Some(false)) => {return DResult::String(format!("User or role {} is not exists.", user));},
+                (Option::Some(_), Option::Some(_)) => {},
+                _ => {return DResult::String("External error: cannot read user list.".to_string());}
+            }
+        }
+        //语句涉及的(用户, 数据库, 表, 权限)
+        let mut values = Vec::new();
+        for user in syntax.users.iter() {
+            for (t, obj) in syntax.objects.iter() {
+                if t == "table" && db.is_empty() {continue;}
+                let database = if t == "database" {obj.to_string()}else{db.to_string()};
+                let table = if t == "database" {"".to_string()}else{obj.to_string()};
+                if syntax.all {
+                    values.push((user.to_string(), database, table, "all".to_string()));
+                }else {
+                    for grant in syntax.grants.iter() {
+                        values.push((user.to_string(), database.to_string(), table.to_string(), grant.to_string()));
                     }
                 }
             }
+        }
+        if syntax.is_grant {
             session.insert_into(&InsertSyntax{
                 table_name: "privilege".to_string(),
                 has_head: true,
-                values: values
-            });
+                columns: vec![],
+                select: Option::None,
+                values: values.iter().map(|(user, database, table, grant)| hmap![
+                    "username" => DfaWord::Str(user.to_string()),
+                    "database" => DfaWord::Str(database.to_string()),
+                    "table" => DfaWord::Str(table.to_string()),
+                    "type" => DfaWord::Str(grant.to_string()),
+                    "grantor" => DfaWord::Str(grantor.to_string()),
+                    "grant_option" => DfaWord::Bool(syntax.grant_option)
+                ]).collect()
+            }).ok();
             session.commit();
             DResult::String(format!("Grant complete."))
         }else{
-            let mut values = Vec::new();
-            for user in syntax.users.iter() {
-                for &(ref t, ref obj) in syntax.objects.iter() {
-                    if t == "table" && db == "" {continue;}
-                    if syntax.all {
-                        values.push(hmap![
-                            "username" => user.to_string(),
-                            "database" => if t == "database" {obj.to_string()}else{db.to_string()},
-                            "table" => if t == "database" {"".to_string()}else{obj.to_string()},
-                            "type" => "all".to_string()
-                        ]);
-                    }else {
-                        for grant in syntax.grants.iter() {
-                            values.push(hmap![
-                                "username" => user.to_string(),
-                                "database" => if t == "database" {obj.to_string()}else{db.to_string()},
-                                "table" => if t == "database" {"".to_string()}else{obj.to_string()},
-                                "type" => grant.to_string()
-                            ]);
-                        }
-                    }
+            let (rows, members, staffs) = match (load_privileges(&mut session, Expression::empty()), load_members(&mut session), load_staffs(&mut session)) {
+                (Option::Some(r), Option::Some(m), Option::Some(s)) => (r, m, s),
+                _ => {return DResult::String("External error: cannot read privileges list.".to_string());}
+            };
+            let mut removed = Vec::new();
+            for r in rows.iter() {
+                if (staff || r.grantor == grantor) && values.iter().any(|(user, database, table, grant)|
+                    &r.username == user && &r.database == database && &r.table == table && &r.kind == grant
+                ) {
+                    removed.push(r.id);
                 }
             }
-            for i in values.iter() {
-                session.delete(&DeleteSyntax{
-                    table_name: "privilege".to_string(),
-                    wheres: Expression{li:vec![
-                        ExpressionType::Var(vec!["username".to_string()]),
-                        ExpressionType::Str(i["username"].to_string()),
-                        ExpressionType::Signal("=".to_string()),
-                        ExpressionType::Var(vec!["database".to_string()]),
-                        ExpressionType::Str(i["database"].to_string()),
-                        ExpressionType::Signal("=".to_string()),
-                        ExpressionType::Signal("&&".to_string()),
-                        ExpressionType::Var(vec!["table".to_string()]),
-                        ExpressionType::Str(i["table"].to_string()),
-                        ExpressionType::Signal("=".to_string()),
-                        ExpressionType::Signal("&&".to_string()),
-                        ExpressionType::Var(vec!["type".to_string()]),
-                        ExpressionType::Str(i["type"].to_string()),
-                        ExpressionType::Signal("=".to_string()),
-                        ExpressionType::Signal("&&".to_string())
-                    ], setence: format!("username=\"{}\"&&database=\"{}\"&&table=\"{}\"&&type=\"{}\"", i["username"], i["database"], i["table"], i["type"])}
-                });
+            //收回后不能再从根授权到达的记录都要删除；其中原本有效的记录只有在cascade时才能删除。
+            let before = abandoned(&rows, &[], &members, &staffs);
+            let dependents = abandoned(&rows, &removed, &members, &staffs);
+            if dependents.iter().any(|i|!before.contains(i)) && !syntax.cascade {
+                return DResult::String("Some privileges were granted to others by these grants. Use revoke ... cascade to revoke them too.".to_string());
             }
+            let ids:Vec<i64> = removed.iter().chain(dependents.iter()).cloned().collect();
+            delete_privileges(&mut session, &ids);
             session.commit();
             DResult::String(format!("Revoke complete."))
         }
-        
     }
 }
 
+fn grant_roles(session:&mut Database, syntax:&GrantSyntax) -> DResult {
+    //把角色授予用户，或者从用户收回角色。角色只能授予用户，不能授予其他角色。
+    for role in syntax.roles.iter() {
+        match exists(session, "role", "name", role) {
+            Option::Some(true) => {},
+            Option::Some(false) => {return DResult::String(format!("Role {} is not exists.", role));},
+            Option::None => {return DResult::String("External error: cannot read role list.".to_string());}
+        }
+    }
+    for user in syntax.users.iter() {
+        match exists(session, "user", "username", user) {
+            Option::Some(true) => {},
+            Option::Some(false) => {return DResult::String(format!("User {} is not exists.", user));},
+            Option::None => {return DResult::String("External error: cannot read user list.".to_string());}
+        }
+    }
+    if syntax.is_grant {
+        let mut values = Vec::new();
+        for user in syntax.users.iter() {
+            for role in syntax.roles.iter() {
+                match select_rows(session, "role_member", &["id"], where_eq(&[("username", user), ("role", role)])) {
+                    Option::Some(ref rows) if !rows.is_empty() => {},
+                    _ => values.push(hmap![
+                        "username" => DfaWord::Str(user.to_string()),
+                        "role" => DfaWord::Str(role.to_string())
+                    ])
+                }
+            }
+        }
+        session.insert_into(&InsertSyntax{
+            table_name: "role_member".to_string(),
+            has_head: true,
+            columns: vec![],
+            select: Option::None,
+            values
+        }).ok();
+        session.commit();
+        DResult::String("Grant complete.".to_string())
+    }else{
+        //收回角色后，用户借助这个角色的转授权限授予别人的权限也要按cascade处理。
+        let (rows, mut members, staffs) = match (load_privileges(session, Expression::empty()), load_members(session), load_staffs(session)) {
+            (Option::Some(r), Option::Some(m), Option::Some(s)) => (r, m, s),
+            _ => {return DResult::String("External error: cannot read privileges list.".to_string());}
+        };
+        let before = abandoned(&rows, &[], &members, &staffs);
+        for user in syntax.users.iter() {
+            if let Option::Some(roles) = members.get_mut(user) {
+                roles.retain(|r|!syntax.roles.contains(r));
+            }
+        }
+        let dependents = abandoned(&rows, &[], &members, &staffs);
+        if dependents.iter().any(|i|!before.contains(i)) && !syntax.cascade {
+            return DResult::String("Some privileges were granted to others by these grants. Use revoke ... cascade to revoke them too.".to_string());
+        }
+        for user in syntax.users.iter() {
+            for role in syntax.roles.iter() {
+                session.delete(&DeleteSyntax{table_name: "role_member".to_string(), wheres: where_eq(&[("username", user), ("role", role)])}).ok();
+            }
+        }
+        delete_privileges(session, &dependents);
+        session.commit();
+        DResult::String("Revoke complete.".to_string())
+    }
+}
+
+struct PrivilegeRow {
+    //权限表中的一条记录。
+    id: i64,
+    username: String,  //被授予的用户或角色
+    database: String,
+    table: String,  //为空时是数据库本身的权限
+    kind: String,
+    grantor: String,  //授权者。旧版本留下的记录为空
+    grant_option: bool
+}
+fn covers(r:&PrivilegeRow, database:&str, table:&str, kind:&str) -> bool {
+    //记录r是否包含了对(数据库, 表, 权限)的权限。数据库的权限包含其中所有的表，all包含所有权限。
+    r.database == database && (r.table.is_empty() || r.table == table) && (r.kind == "all" || r.kind == kind)
+}
+fn abandoned(rows:&[PrivilegeRow], removed:&[i64], members:&HashMap<String, Vec<String>>, staffs:&[String]) -> Vec<i64> {
+    //删除removed中的记录之后，不再能从根授权到达的记录。
+    //staff授予的记录，以及旧版本留下的没有授权者的记录是根授权；其他记录只有在授权者(或授权者所属的角色)
+    //持有一条可以到达的、包含它的可转授记录时才有效。互相转授形成的环不能让彼此有效。
+    let mut reached:Vec<i64> = rows.iter()
+        .filter(|d| !removed.contains(&d.id) && (d.grantor.is_empty() || staffs.contains(&d.grantor)))
+        .map(|d| d.id).collect();
+    loop {
+        let mut found = Vec::new();
+        for d in rows.iter() {
+            if removed.contains(&d.id) || reached.contains(&d.id) {continue;}
+            let valid = rows.iter().any(|e| reached.contains(&e.id) && e.id != d.id && e.grant_option &&
+                (e.username == d.grantor || members.get(&d.grantor).is_some_and(|roles|roles.contains(&e.username))) &&
+                covers(e, &d.database, &d.table, &d.kind));
+            if valid {found.push(d.id);}
+        }
+        if found.is_empty() {break;}
+        reached.extend(found);
+    }
+    rows.iter().filter(|d| !removed.contains(&d.id) && !reached.contains(&d.id)).map(|d| d.id).collect()
+}
+fn drop_grantee(session:&mut Database, name:&str) -> bool {
+    //删除用户或角色之前，删除授予它的权限，以及之后不能再从根授权到达的权限：
+    //它授予别人的权限，它的成员借助它的可转授权限授予别人的权限，以及由这些权限继续转授的权限。
+    let (rows, mut members, mut staffs) = match (load_privileges(session, Expression::empty()), load_members(session), load_staffs(session)) {
+        (Option::Some(r), Option::Some(m), Option::Some(s)) => (r, m, s),
+        _ => {return false;}
+    };
+    staffs.retain(|s|s != name);
+    members.remove(name);
+    for roles in members.values_mut() {
+        roles.retain(|r|r != name);
+    }
+    let mut ids:Vec<i64> = rows.iter().filter(|r|r.username == name).map(|r|r.id).collect();
+    let dependents = abandoned(&rows, &ids, &members, &staffs);
+    ids.extend(dependents);
+    delete_privileges(session, &ids);
+    true
+}
+fn delete_privileges(session:&mut Database, ids:&[i64]) {
+    for id in ids.iter() {
+        session.delete(&DeleteSyntax{
+            table_name: "privilege".to_string(),
+            wheres: Expression{li:vec![
+                ExpressionType::Var(vec!["id".to_string()]),
+                ExpressionType::Integer(*id),
+                ExpressionType::Signal("=".to_string())
+            ], setence: format!("id={}", id)}
+        }).ok();
+    }
+}
+fn load_privileges(session:&mut Database, wheres:Expression) -> Option<Vec<PrivilegeRow>> {
+    select_rows(session, "privilege", &["id", "username", "database", "table", "type", "grantor", "grant_option"], wheres).map(|rows|
+        rows.iter().map(|r|PrivilegeRow{
+            id: if let DataItem::Integer(i) = r.li[0] {i}else{0},
+            username: item_str(&r.li[1]),
+            database: item_str(&r.li[2]),
+            table: item_str(&r.li[3]),
+            kind: item_str(&r.li[4]),
+            grantor: item_str(&r.li[5]),
+            grant_option: if let DataItem::Bool(b) = r.li[6] {b}else{false}
+        }).collect()
+    )
+}
+fn load_members(session:&mut Database) -> Option<HashMap<String, Vec<String>>> {
+    //用户所属的角色。
+    select_rows(session, "role_member", &["username", "role"], Expression::empty()).map(|rows|{
+        let mut map:HashMap<String, Vec<String>> = HashMap::new();
+        for r in rows.iter() {
+            map.entry(item_str(&r.li[0])).or_default().push(item_str(&r.li[1]));
+        }
+        map
+    })
+}
+fn load_staffs(session:&mut Database) -> Option<Vec<String>> {
+    select_rows(session, "user", &["username", "is_root"], Expression::empty()).map(|rows|
+        rows.iter().filter(|r|if let DataItem::Bool(b) = r.li[1] {b}else{false}).map(|r|item_str(&r.li[0])).collect()
+    )
+}
+fn exists(session:&mut Database, table:&str, field:&str, value:&str) -> Option<bool> {
+    select_rows(session, table, &[field], where_eq(&[(field, value)])).map(|rows|!rows.is_empty())
+}
+fn select_rows(session:&mut Database, table:&str, columns:&[&str], wheres:Expression) -> Option<Vec<Data>> {
+    //从系统表中查询若干字段。
+    match session.select(&SelectSyntax{
+        distinct: false,
+        froms: hmap![table => Switch::One(table.to_string())],
+        goals: columns.iter().map(|c|(c.to_string(), Expression::new_single(c))).collect(),
+        wheres,
+        joins: vec![],
+        groups: vec![],
+        havings: Expression::empty(),
+        limit: Option::None,
+        offset: 0,
+        orders: vec![]
+    }) {
+        DResult::Table(dt) => Option::Some(dt.content),
+        _ => Option::None
+    }
+}
+fn where_eq(conds:&[(&str, &str)]) -> Expression {
+    //若干个"字段=字符串"用&&连接成的条件。
+    let mut li = Vec::new();
+    let mut setence = Vec::new();
+    for (i, &(field, value)) in conds.iter().enumerate() {
+        li.push(ExpressionType::Var(vec![field.to_string()]));
+        li.push(ExpressionType::Str(value.to_string()));
+        li.push(ExpressionType::Signal("=".to_string()));
+        if i > 0 {li.push(ExpressionType::Signal("&&".to_string()));}
+        setence.push(format!("{}=\"{}\"", field, value));
+    }
+    Expression{li, setence: setence.join("&&")}
+}
+fn item_str(d:&DataItem) -> String {
+    if let DataItem::Str(_, s) = d {s.to_string()}else{"".to_string()}
+}
+
+fn create_user_table(system_db:&mut Database) {
+    //用户表。password保存带算法标记的散列值，must_change为true的用户登录后必须先修改密码。
+    system_db.create_table(&CreateTableSyntax{
+        name: "user".to_string(),
+        fields: vec![
+            TableFieldSyntax{name: "id".to_string(), t: "integer".to_string(), unique: true, primary: true, not_null: true, auto_inc: true, default: Option::None},
+            TableFieldSyntax{name: "username".to_string(), t: "str:24".to_string(), unique: true, primary: false, not_null: true, auto_inc: false, default: Option::None},
+            TableFieldSyntax{name: "password".to_string(), t: "str:160".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::None},
+            TableFieldSyntax{name: "is_root".to_string(), t: "bool".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::Some("false".to_string())},
+            TableFieldSyntax{name: "must_change".to_string(), t: "bool".to_string(), unique: false, primary: false, not_null: true, auto_inc: false, default: Option::Some("false".to_string())}
+        ], foreigns: vec![], checks: vec![], primary: vec![], uniques: vec![]
+    }).ok();
+}
+fn migrate_user_table(system_db:&mut Database) {
+    //读出全部用户，删除旧表后按新结构重建，再连同原来的id一起写回。仍在使用默认密码的root账户要求修改密码。
+    let mut users = Vec::new();
+    if let DResult::Table(ref dt) = system_db.select(&SelectSyntax{
+        distinct: false,
+        froms: hmap!["user" => Switch::One("user".to_string())],
+        goals: vec![
+            ("id".to_string(), Expression::new_single("id")),
+            ("username".to_string(), Expression::new_single("username")),
+            ("password".to_string(), Expression::new_single("password")),
+            ("is_root".to_string(), Expression::new_single("is_root"))
+        ],
+        wheres: Expression::empty(),
+        joins: vec![],
+        groups: vec![],
+        havings: Expression::empty(),
+        limit: Option::None,
+        offset: 0,
+        orders: vec![]
+    }) {
+        for row in dt.content.iter() {
+            if let (&DataItem::Integer(id), DataItem::Str(_, username), DataItem::Str(_, pw), &DataItem::Bool(is_root)) = (&row.li[0], &row.li[1], &row.li[2], &row.li[3]) {
+                users.push((id, username.to_string(), pw.to_string(), is_root));
+            }
+        }
+    }
+    system_db.drop_table(&DropTableSyntax{name: "user".to_string()}).ok();
+    create_user_table(system_db);
+    let mut next = 1;
+    for (id, username, pw, is_root) in users {
+        let must_change = username == "root" && pw == "root";
+        //自增字段总是取计数器的值，把计数器拨到原来的id上再插入。
+        system_db.conf.tables.get_mut("user").unwrap().auto_config.insert("id".to_string(), id as usize);
+        next = next.max(id as usize + 1);
+        system_db.insert_into(&InsertSyntax{
+            table_name: "user".to_string(),
+            has_head: true,
+            columns: vec![],
+            select: Option::None,
+            values: vec![hmap![
+                "username" => DfaWord::Str(username),
+                "password" => DfaWord::Str(pw),
+                "is_root" => DfaWord::Bool(is_root),
+                "must_change" => DfaWord::Bool(must_change)
+            ]]
+        }).ok();
+    }
+    system_db.conf.tables.get_mut("user").unwrap().auto_config.insert("id".to_string(), next);
+}
+
 pub struct Session<'t>{
-    engine:&'t mut Engine,
+    engine:&'t Engine,
+    id: usize,  //会话的编号，作为锁的持有者
     using: Option<String>,
-    user: String
+    user: String,
+    must_change: bool,  //必须先修改自己的密码才能执行其他语句
+    transaction: Option<Transaction>
+}
+struct Transaction {
+    //正在进行的事务。事务期间的修改停留在脏页中，配置的修改停留在内存中，
+    //直到COMMIT时才写入文件；ROLLBACK时使用事务开始时的配置快照恢复。
+    //事务开始时取得数据库的共享锁，第一次写时升级为排他锁，直到事务结束才释放，因此快照一直有效。
+    database: Arc<SharedConfig>,
+    snapshot: String,
+    dirty: HashMap<usize, Vec<u8>>
 }
 impl<'t> Session<'t> {
     pub fn use_database(&mut self, syntax:&UseSyntax) -> DResult {
         let name = syntax.name.as_str();
         // todo 检查权限
-        if self.engine.databases.contains_key(name) {
+        if self.transaction.is_some() {
+            DResult::String("Cannot change database inside a transaction.".to_string())
+        }else if self.engine.has_database(name) {
             self.using = Option::Some(name.to_string());
             DResult::String(format!("use {}.", name))
         }else {
@@ -428,100 +920,197 @@ impl<'t> Session<'t> {
         }
     }
 
-    fn get_using(&mut self) -> Result<Database, DResult> {
-        if let Option::Some(ref db) = self.using {
-            if ! self.engine.databases.contains_key(db) {
-                Result::Err(DResult::String(format!("No using database.")))
-            }else{
-                Result::Ok(self.engine.databases.get_mut(db).unwrap().session())
-            }
-        } else {Result::Err(DResult::String(format!("No using database.")))}
-    }
-
-    pub fn execute(&mut self, syntax:&ColSyntax) -> DResult {
-        if let Result::Err(ref e) = self.check_grant(syntax) {
-            return DResult::String(e.to_string());
-        }
-        match syntax {
-            &ColSyntax::Select(ref s) => match self.get_using() {
-                Result::Ok(mut db) => db.select(s),
-                Result::Err(dr) => dr
-            },
-            &ColSyntax::Insert(ref s) => match self.get_using() {
-                Result::Ok(mut db) => {
-                    let ret = db.insert_into(s);
-                    db.commit();
-                    ret
-                },
-                Result::Err(dr) => dr
+    fn run_using<F>(&mut self, write:bool, f:F) -> DResult where F: FnOnce(&mut Database) -> Result<DResult, String> {
+        //在当前使用的数据库上执行一条语句。
+        //没有事务时，语句执行期间持有锁，写语句成功后立即提交；在事务中时，修改被暂存回事务。
+        //写语句失败时撤销它已经做出的修改，不会提交或留在事务中。
+        let shared = match self.using {
+            Option::Some(ref name) => match self.engine.database(name) {
+                Option::Some(db) => db,
+                Option::None => return DResult::String("No using database.".to_string())
             },
-            &ColSyntax::Update(ref s) => match self.get_using() {
-                Result::Ok(mut db) => {
-                    let ret = db.update(s);
-                    db.commit();
-                    ret
-                },
-                Result::Err(dr) => dr
-            },
-            &ColSyntax::Delete(ref s) => match self.get_using() {
-                Result::Ok(mut db) => {
-                    let ret = db.delete(s);
-                    db.commit();
+            _ => return DResult::String("No using database.".to_string())
+        };
+        match self.transaction {
+            Option::Some(ref mut t) => {
+                if write {
+                    if let Result::Err(e) = t.database.lock(self.id, LockMode::Exclusive, self.engine.timeout) {
+                        return DResult::String(e);
+                    }
+                }
+                let dirty = take(&mut t.dirty);
+                if t.database.is_exclusive(self.id) {
+                    let mut conf = t.database.write();
+                    let mut db = conf.session_with(dirty);
+                    let point = db.savepoint();
+                    let ret = match f(&mut db) {
+                        Result::Ok(r) => r,
+                        Result::Err(e) => {
+                            db.restore(point);
+                            DResult::String(e)
+                        }
+                    };
+                    t.dirty = db.file.detach_dirty();
                     ret
-                },
-                Result::Err(dr) => dr
-            },
-            &ColSyntax::CreateTable(ref s) => match self.get_using() {
-                Result::Ok(mut db) => {
-                    let ret = db.create_table(s);
-                    db.commit();
+                }else{
+                    let (ret, loaded) = {
+                        let conf = t.database.read();
+                        let mut db = conf.reader_with(dirty);
+                        let ret = match f(&mut db) {
+                            Result::Ok(r) => r,
+                            Result::Err(e) => DResult::String(e)
+                        };
+                        t.dirty = db.file.detach_dirty();
+                        (ret, take(&mut db.loaded))
+                    };
+                    t.database.cache_indexes(loaded);
                     ret
-                },
-                Result::Err(dr) => dr
+                }
             },
-            &ColSyntax::AlterTable(ref s) => match self.get_using() {
-                Result::Ok(mut db) => {
-                    let ret = db.alter_table(s);
-                    db.commit();
-                    ret
-                },
-                Result::Err(dr) => dr
+            Option::None if write => {
+                let mut conf = match shared.write_locked(self.engine.timeout) {
+                    Result::Ok(g) => g,
+                    Result::Err(e) => return DResult::String(e)
+                };
+                let mut db = conf.session();
+                let point = db.savepoint();
+                match f(&mut db) {
+                    Result::Ok(r) => {
+                        db.commit();
+                        r
+                    },
+                    Result::Err(e) => {
+                        db.restore(point);
+                        DResult::String(e)
+                    }
+                }
             },
-            &ColSyntax::DropTable(ref s) => match self.get_using() {
-                Result::Ok(mut db) => {
-                    let ret = db.drop_table(s);
-                    db.commit();
-                    ret
-                },
-                Result::Err(dr) => dr
+            Option::None => {
+                //语句结束、放下配置的引用之后，仍然持有共享锁时把加载的索引放入缓存。
+                let owner = new_owner();
+                if let Result::Err(e) = shared.lock(owner, LockMode::Shared, self.engine.timeout) {
+                    return DResult::String(e);
+                }
+                let (ret, loaded) = {
+                    let conf = shared.read();
+                    let mut db = conf.reader();
+                    let ret = match f(&mut db) {
+                        Result::Ok(r) => r,
+                        Result::Err(e) => DResult::String(e)
+                    };
+                    (ret, take(&mut db.loaded))
+                };
+                shared.cache_indexes(loaded);
+                shared.unlock(owner);
+                ret
+            }
+        }
+    }
+
+    pub fn begin(&mut self) -> DResult {
+        if self.transaction.is_some() {
+            return DResult::String("A transaction is already in progress.".to_string());
+        }
+        let db = match self.using {
+            Option::Some(ref name) => match self.engine.database(name) {
+                Option::Some(db) => db,
+                Option::None => return DResult::String("No using database.".to_string())
             },
-            &ColSyntax::CreateView(ref s) => match self.get_using() {
-                Result::Ok(mut db) => {
-                    let ret = db.create_view(s);
-                    db.commit();
-                    ret
-                },
-                Result::Err(dr) => dr
+            _ => return DResult::String("No using database.".to_string())
+        };
+        if let Result::Err(e) = db.lock(self.id, LockMode::Shared, self.engine.timeout) {
+            return DResult::String(e);
+        }
+        let snapshot = db.read().to_json_string();
+        self.transaction = Option::Some(Transaction{
+            database: db,
+            snapshot,
+            dirty: HashMap::new()
+        });
+        DResult::String("Transaction started.".to_string())
+    }
+    pub fn commit(&mut self) -> DResult {
+        match self.transaction.take() {
+            Option::Some(t) => {
+                //只取得过共享锁的事务没有修改，不需要写入。
+                if t.database.is_exclusive(self.id) {
+                    t.database.write().session_with(t.dirty).commit();
+                }
+                t.database.unlock(self.id);
+                DResult::String("Transaction committed.".to_string())
             },
-            &ColSyntax::DropView(ref s) => match self.get_using() {
-                Result::Ok(mut db) => {
-                    let ret = db.drop_view(s);
-                    db.commit();
-                    ret
-                },
-                Result::Err(dr) => dr
+            Option::None => DResult::String("No transaction in progress.".to_string())
+        }
+    }
+    pub fn rollback(&mut self) -> DResult {
+        match self.transaction.take() {
+            Option::Some(t) => {
+                //脏页直接丢弃，配置恢复到事务开始时的快照。
+                if t.database.is_exclusive(self.id) {
+                    t.database.write().load(&t.snapshot);
+                }
+                t.database.unlock(self.id);
+                DResult::String("Transaction rolled back.".to_string())
             },
+            Option::None => DResult::String("No transaction in progress.".to_string())
+        }
+    }
+
+    pub fn execute(&mut self, syntax:&ColSyntax) -> DResult {
+        if self.must_change {
+            let own = if let ColSyntax::AlterUser(s) = syntax {s.username.trim() == self.user}else{false};
+            if !own {
+                return DResult::String(format!("You must change your password first: alter user {} with password \"...\".", self.user));
+            }
+        }
+        if let Result::Err(ref e) = self.check_grant(syntax) {
+            return DResult::String(e.to_string());
+        }
+        match syntax {
+            ColSyntax::Select(s) => self.run_using(false, |db| Result::Ok(db.select(s))),
+            ColSyntax::Insert(s) => self.run_using(true, |db| db.insert_into(s)),
+            ColSyntax::Update(s) => self.run_using(true, |db| db.update(s)),
+            ColSyntax::Delete(s) => self.run_using(true, |db| db.delete(s)),
+            ColSyntax::CreateTable(s) => self.run_using(true, |db| db.create_table(s)),
+            ColSyntax::AlterTable(s) => self.run_using(true, |db| db.alter_table(s)),
+            ColSyntax::DropTable(s) => self.run_using(true, |db| db.drop_table(s)),
+            ColSyntax::CreateView(s) => self.run_using(true, |db| db.create_view(s)),
+            ColSyntax::DropView(s) => self.run_using(true, |db| db.drop_view(s)),
+            ColSyntax::CreateIndex(s) => self.run_using(true, |db| db.create_index(s)),
+            ColSyntax::DropIndex(s) => self.run_using(true, |db| db.drop_index(s)),
+            ColSyntax::Vacuum(s) => self.run_using(true, |db| db.vacuum(s)),
             &ColSyntax::Use(ref s) => self.use_database(s),
-            &ColSyntax::Help(ref s) => match self.get_using() {
-                Result::Ok(mut db) => db.help(s),
-                Result::Err(dr) => dr
-            },
+            ColSyntax::Help(s) => self.run_using(false, |db| Result::Ok(db.help(s))),
+            &ColSyntax::Begin => self.begin(),
+            &ColSyntax::Commit => self.commit(),
+            &ColSyntax::Rollback => self.rollback(),
             &ColSyntax::CreateDatabase(ref s) => self.engine.create_database(s),
-            &ColSyntax::DropDatabase(ref s) => self.engine.drop_database(s),
+            ColSyntax::DropDatabase(s) => {
+                let in_use = match self.transaction {
+                    Option::Some(ref t) => t.database.name == s.name,
+                    Option::None => false
+                };
+                if in_use {DResult::String(format!("Database {} is used by current transaction.", s.name))}
+                else {self.engine.drop_database(s)}
+            },
             &ColSyntax::CreateUser(ref s) => self.engine.create_user(s),
-            &ColSyntax::AlterUser(ref s) => self.engine.alter_user(s),
+            ColSyntax::AlterUser(s) => match self.engine.alter_user(s, &self.user) {
+                Result::Ok(_) => {
+                    if s.username.trim() == self.user {self.must_change = false;}
+                    DResult::String(format!("User {} has been altered.", s.username.trim()))
+                },
+                Result::Err(e) => DResult::String(e)
+            },
             &ColSyntax::DropUser(ref s) => self.engine.drop_user(s),
-            &ColSyntax::Grant(ref s) => self.engine.grant(s, if let Option::Some(ref s) = self.using{s}else{""}),
+            ColSyntax::CreateRole(s) => self.engine.create_role(s),
+            ColSyntax::DropRole(s) => self.engine.drop_role(s),
+            ColSyntax::Grant(s) => {
+                let staff = match self.is_staff() {
+                    Result::Ok(ok) => ok,
+                    Result::Err(e) => {return DResult::String(e);}
+                };
+                self.engine.grant(s, if let Option::Some(ref s) = self.using{s}else{""}, &self.user, staff)
+            },
             &ColSyntax::None => {
                 DResult::String(format!("无效的指令。"))
             }
@@ -544,7 +1133,30 @@ impl<'t> Session<'t> {
         }
         //从这里开始的判定对视已经没有staff的了。
         match syntax {
+            ColSyntax::AlterUser(s) if s.username.trim() == self.user => Result::Ok(()),  //任何用户都可以修改自己的密码
+            ColSyntax::Grant(s) if s.roles.is_empty() => {
+                //非staff可以转授自己持有的、带有grant option的权限；收回时只能收回自己授予的权限，在执行时限制。
+                if !s.is_grant {return Result::Ok(());}
+                for (t, obj) in s.objects.iter() {
+                    let (db, table) = if t == "database" {(obj.to_string(), "".to_string())}else{
+                        match self.using {
+                            Option::Some(ref db) => (db.to_string(), obj.to_string()),
+                            Option::None => {continue;}  //没有use时不会授予表的权限
+                        }
+                    };
+                    let grants:Vec<String> = if s.all {vec!["all".to_string()]}else{s.grants.iter().map(|g|g.to_string()).collect()};
+                    for grant in grants.iter() {
+                        match self.has_grant_option(db.as_str(), table.as_str(), grant.as_str()) {
+                            Result::Ok(ok) => if !ok {return Result::Err(format!("You do not have grant option of {} on {}.", grant, obj));},
+                            Result::Err(ref e) => {return Result::Err(e.to_string());}
+                        }
+                    }
+                }
+                Result::Ok(())
+            },
             &ColSyntax::Grant(..) |
+            &ColSyntax::CreateRole(..) |
+            &ColSyntax::DropRole(..) |
             &ColSyntax::CreateUser(..) | 
             &ColSyntax::AlterUser(..) |
             &ColSyntax::DropUser(..) |
@@ -579,6 +1191,17 @@ impl<'t> Session<'t> {
                     Result::Err(ref e) => Result::Err(e.to_string())
                 }
             },
+            &ColSyntax::CreateIndex(_) | &ColSyntax::DropIndex(_) | &ColSyntax::Vacuum(_) => {
+                //索引属于表结构的一部分，与alter table使用同一权限。整理表的存储也一样。
+                let db = match self.using {
+                    Option::Some(ref db) => db.to_string(), 
+                    Option::None => {return Result::Ok(());}  //在没有use时是默认可以的，反正也执行不了
+                };
+                match self.has_grant_on_database(db.as_str(), "altertable") {
+                    Result::Ok(ok) => if ok {Result::Ok(())}else{Result::Err("You do not have grant on these setences.".to_string())},
+                    Result::Err(ref e) => Result::Err(e.to_string())
+                }
+            },
             &ColSyntax::DropTable(_) => {
                 let db = match self.using {
                     Option::Some(ref db) => db.to_string(), 
@@ -628,7 +1251,10 @@ impl<'t> Session<'t> {
                         Result::Ok(ok) => if ok {Result::Ok(())}else{Result::Err(format!("You do not have grant on these setences."))},
                         Result::Err(ref e) => Result::Err(e.to_string())
                     },
-                    // todo index和view在这里需要添加。
+                    "index" => match self.has_grant_on_database(db.as_str(), "help") {
+                        Result::Ok(ok) => if ok {Result::Ok(())}else{Result::Err("You do not have grant on these setences.".to_string())},
+                        Result::Err(ref e) => Result::Err(e.to_string())
+                    },
                     _ => Result::Err(format!("Unknown help syntax."))
                 }
             },
@@ -638,6 +1264,16 @@ impl<'t> Session<'t> {
                     Option::None => {return Result::Ok(());}  //在没有use时是默认可以的，反正也执行不了
                 };
                 match self.has_grant_on_table(db.as_str(), s.table_name.as_str(), "update") {
+                    Result::Ok(ok) => if !ok {return Result::Err("You do not have grant on these setences.".to_string());},
+                    Result::Err(ref e) => {return Result::Err(e.to_string());}
+                }
+                //where与set的值中的子查询需要select权限。
+                let mut tlist = Vec::new();
+                self.get_expression_tables_names(&mut tlist, &s.wheres);
+                for (_, expression) in s.sets.iter() {
+                    self.get_expression_tables_names(&mut tlist, expression);
+                }
+                match self.has_grant_on_tables(db.as_str(), &tlist[..], "select") {
                     Result::Ok(ok) => if ok {Result::Ok(())}else{Result::Err(format!("You do not have grant on these setences."))},
                     Result::Err(ref e) => Result::Err(e.to_string())
                 }
@@ -648,6 +1284,15 @@ impl<'t> Session<'t> {
                     Option::None => {return Result::Ok(());}  //在没有use时是默认可以的，反正也执行不了
                 };
                 match self.has_grant_on_table(db.as_str(), s.table_name.as_str(), "insert") {
+                    Result::Ok(ok) => if !ok {return Result::Err("You do not have grant on these setences.".to_string());},
+                    Result::Err(ref e) => {return Result::Err(e.to_string());}
+                }
+                //insert ... select读取的表需要select权限。
+                let mut tlist = Vec::new();
+                if let Option::Some(ref select) = s.select {
+                    self.get_tables_names(&mut tlist, select);
+                }
+                match self.has_grant_on_tables(db.as_str(), &tlist[..], "select") {
                     Result::Ok(ok) => if ok {Result::Ok(())}else{Result::Err(format!("You do not have grant on these setences."))},
                     Result::Err(ref e) => Result::Err(e.to_string())
                 }
@@ -658,6 +1303,13 @@ impl<'t> Session<'t> {
                     Option::None => {return Result::Ok(());}  //在没有use时是默认可以的，反正也执行不了
                 };
                 match self.has_grant_on_table(db.as_str(), s.table_name.as_str(), "delete") {
+                    Result::Ok(ok) => if !ok {return Result::Err("You do not have grant on these setences.".to_string());},
+                    Result::Err(ref e) => {return Result::Err(e.to_string());}
+                }
+                //where中的子查询需要select权限。
+                let mut tlist = Vec::new();
+                self.get_expression_tables_names(&mut tlist, &s.wheres);
+                match self.has_grant_on_tables(db.as_str(), &tlist[..], "select") {
                     Result::Ok(ok) => if ok {Result::Ok(())}else{Result::Err(format!("You do not have grant on these setences."))},
                     Result::Err(ref e) => Result::Err(e.to_string())
                 }
@@ -675,6 +1327,7 @@ impl<'t> Session<'t> {
                     Result::Err(ref e) => Result::Err(e.to_string())
                 }
             },
+            &ColSyntax::Begin | &ColSyntax::Commit | &ColSyntax::Rollback |
             &ColSyntax::None => {
                 Result::Ok(())
             }
@@ -687,165 +1340,87 @@ impl<'t> Session<'t> {
                 &Switch::Two(ref s) => {self.get_tables_names(tlist, s);}
             }
         }
+        for j in syntax.joins.iter() {
+            tlist.push(j.table.to_string());
+            self.get_expression_tables_names(tlist, &j.on);
+        }
+        for (_, goal) in syntax.goals.iter() {
+            self.get_expression_tables_names(tlist, goal);
+        }
+        self.get_expression_tables_names(tlist, &syntax.wheres);
+        self.get_expression_tables_names(tlist, &syntax.havings);
+    }
+    fn get_expression_tables_names(&self, tlist:&mut Vec<String>, expression:&Expression) {
+        for sub in expression.sub_selects() {
+            self.get_tables_names(tlist, sub);
+        }
+    }
+    fn privileges(&mut self, database:&str) -> Result<Vec<PrivilegeRow>, String> {
+        //当前用户以及他所属的角色在数据库上的全部权限记录。
+        let system = match self.engine.system_read() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return Result::Err(e);}
+        };
+        let mut session = system.reader();
+        let mut names = vec![self.user.to_string()];
+        match select_rows(&mut session, "role_member", &["role"], where_eq(&[("username", self.user.as_str())])) {
+            Option::Some(rows) => for r in rows.iter() {names.push(item_str(&r.li[0]));},
+            Option::None => {return Result::Err("External error: cannot read privileges list.".to_string());}
+        }
+        match load_privileges(&mut session, where_eq(&[("database", database)])) {
+            Option::Some(rows) => Result::Ok(rows.into_iter().filter(|r|names.contains(&r.username)).collect()),
+            Option::None => Result::Err("External error: cannot read privileges list.".to_string())
+        }
     }
     fn has_any_grant(&mut self, database:&str) -> Result<bool, String> {
-        if !self.engine.databases.contains_key(database) {
+        if !self.engine.has_database(database) {
             return Result::Ok(true);  //不存在的数据库是被允许的。
         }
-        let mut session = self.engine.system.session();
-        if let DResult::Table(ref dt) = session.select(&SelectSyntax{
-            distinct: false,
-            froms: hmap!["privilege"=>Switch::One("privilege".to_string())],
-            goals: vec![("*".to_string(), Expression::new_allin())],
-            wheres: Expression{li:vec![
-                ExpressionType::Var(vec!["username".to_string()]),
-                ExpressionType::Str(self.user.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Var(vec!["database".to_string()]),
-                ExpressionType::Str(database.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Signal("&&".to_string())
-            ], setence: format!("username=\"{}\"&&database=\"{}\"", self.user, database)},
-            orders: vec![]
-        }) { //筛选出与user相关的所有与当前数据库相关的权限记录。
-            Result::Ok(dt.content.len() > 0)
-            //只要存在记录，就表示有任意权限存在。
-        }else{
-            Result::Err(format!("External error: cannot read privileges list."))
+        //只要存在记录，就表示有任意权限存在。
+        match self.privileges(database) {
+            Result::Ok(rows) => Result::Ok(!rows.is_empty()),
+            Result::Err(e) => Result::Err(e)
         }
     }
     fn has_grant_on_database(&mut self, database:&str, grant:&str) -> Result<bool, String> {
-        if !self.engine.databases.contains_key(database) {
+        if !self.engine.has_database(database) {
             return Result::Ok(true);  //不存在的数据库是被允许的。
         }
-        //判断用户对该数据库是否具有grant的权限，或者具有all权限。
-        let mut session = self.engine.system.session();
-        if let DResult::Table(ref dt) = session.select(&SelectSyntax{
-            distinct: false,
-            froms: hmap!["privilege"=>Switch::One("privilege".to_string())],
-            goals: vec![("*".to_string(), Expression::new_allin())],
-            wheres: Expression{li:vec![
-                ExpressionType::Var(vec!["username".to_string()]),
-                ExpressionType::Str(self.user.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Var(vec!["database".to_string()]),
-                ExpressionType::Str(database.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Signal("&&".to_string()),
-                ExpressionType::Var(vec!["table".to_string()]),
-                ExpressionType::Str("".to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Signal("&&".to_string())
-            ], setence: format!("username=\"{}\"&&database=\"{}\"&&table=\"\"", self.user, database)},
-            orders: vec![]
-        }) { //筛选出与user相关的所有与当前数据库相关的权限记录，去掉table不为空的记录，只保留针对database本身的记录。
-            if dt.content.len() > 0 {
-                let record = dt.get_column("type");
-                for i in record.iter() {
-                    if let &DataItem::Str(_, ref s) = i {
-                        if s == grant || s == "all" {
-                            return Result::Ok(true)
-                        }
-                    }
-                }
-            }
-            Result::Ok(false)
-        }else{
-            Result::Err(format!("External error: cannot read privileges list."))
+        //判断用户对该数据库是否具有grant的权限，或者具有all权限。只看针对database本身的记录。
+        match self.privileges(database) {
+            Result::Ok(rows) => Result::Ok(rows.iter().any(|r|covers(r, database, "", grant))),
+            Result::Err(e) => Result::Err(e)
         }
     }
     fn has_grant_on_table(&mut self, database:&str, table:&str, grant:&str) -> Result<bool, String> {
-        if !self.engine.databases.contains_key(database) {
+        self.has_grant_on_tables(database, &[table.to_string()], grant)
+    }
+    fn has_grant_on_tables(&mut self, database:&str, tables:&[String], grant:&str) -> Result<bool, String> {
+        if !self.engine.has_database(database) {
             return Result::Ok(true);  //不存在的数据库是被允许的。
         }
-        //判断用户对该表否具有grant的权限，或者具有all权限。
-        let mut session = self.engine.system.session();
-        if let DResult::Table(ref dt) = session.select(&SelectSyntax{
-            distinct: false,
-            froms: hmap!["privilege"=>Switch::One("privilege".to_string())],
-            goals: vec![("*".to_string(), Expression::new_allin())],
-            wheres: Expression{li:vec![
-                ExpressionType::Var(vec!["table".to_string()]),
-                ExpressionType::Str(table.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Var(vec!["table".to_string()]),
-                ExpressionType::Str("".to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Signal("||".to_string()),
-                ExpressionType::Var(vec!["username".to_string()]),
-                ExpressionType::Str(self.user.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Signal("&&".to_string()),
-                ExpressionType::Var(vec!["database".to_string()]),
-                ExpressionType::Str(database.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Signal("&&".to_string())
-            ],setence: format!(
-                "table=\"{}\"||table=\"\"&&username=\"{}\"&&database=\"{}\"",
-                table, self.user, database
-            )},
-            orders: vec![]
-        }) { //筛选出与user相关的所有与当前表或当前数据库相关的权限记录
-            if dt.content.len() > 0 {
-                let record = dt.get_column("type");
-                for i in record.iter() {
-                    if let &DataItem::Str(_, ref s) = i {
-                        if s == grant || s == "all" {
-                            return Result::Ok(true)
-                        }
-                    }
-                }
-            }
-            Result::Ok(false)
-        }else{
-            Result::Err(format!("External error: cannot read privileges list."))
+        //判断用户对这些表是否都具有grant的权限，或者具有all权限。对数据库的权限包含其中的所有表。
+        match self.privileges(database) {
+            Result::Ok(rows) => Result::Ok(tables.iter().all(|t|rows.iter().any(|r|covers(r, database, t, grant)))),
+            Result::Err(e) => Result::Err(e)
         }
     }
-    fn has_grant_on_tables(&mut self, database:&str, tables:&[String], grant:&str) -> Result<bool, String> {
-        if !self.engine.databases.contains_key(database) {
-            return Result::Ok(true);  //不存在的数据库是被允许的。
+    fn has_grant_option(&mut self, database:&str, table:&str, grant:&str) -> Result<bool, String> {
+        //判断用户能否把权限转授给别人。不存在的数据库上不能转授，以免数据库建立后权限生效。
+        if !self.engine.has_database(database) {
+            return Result::Ok(false);
         }
-        //判断用户对该表否具有grant的权限，或者具有all权限。
-        let mut session = self.engine.system.session();
-        if let DResult::Table(ref dt) = session.select(&SelectSyntax{
-            distinct: false,
-            froms: hmap!["privilege"=>Switch::One("privilege".to_string())],
-            goals: vec![
-                ("table".to_string(), Expression::new_single("table")),
-                ("type".to_string(), Expression::new_single("type"))
-            ],
-            wheres: Expression{li:vec![
-                ExpressionType::Var(vec!["username".to_string()]),
-                ExpressionType::Str(self.user.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Var(vec!["database".to_string()]),
-                ExpressionType::Str(database.to_string()),
-                ExpressionType::Signal("=".to_string()),
-                ExpressionType::Signal("&&".to_string())
-            ], setence: format!("username=\"{}\"&&database=\"{}\"", self.user, database)},
-            orders: vec![]
-        }) { //筛选出与user相关的所有与当前表或当前数据库相关的权限记录
-            for d in dt.content.iter() {
-                for i in tables.iter() {
-                    if let DataItem::Str(_, ref s) = d.li[0] {
-                        if s == i || s == "" {
-                            //确认表名
-                            if let DataItem::Str(_, ref s) = d.li[1] {
-                                if s == grant || s == "all" {
-                                    return Result::Ok(true);
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            Result::Ok(false)
-        }else{
-            Result::Err(format!("External error: cannot read privileges list."))
+        match self.privileges(database) {
+            Result::Ok(rows) => Result::Ok(rows.iter().any(|r|r.grant_option && covers(r, database, table, grant))),
+            Result::Err(e) => Result::Err(e)
         }
-    } 
+    }
     fn is_staff(&mut self) -> Result<bool, String> {
-        let mut session = self.engine.system.session();
+        let system = match self.engine.system_read() {
+            Result::Ok(g) => g,
+            Result::Err(e) => {return Result::Err(e);}
+        };
+        let mut session = system.reader();
         if let DResult::Table(ref dt) = session.select(&SelectSyntax{
             distinct: false,
             froms: hmap!["user"=>Switch::One("user".to_string())],
@@ -858,6 +1433,11 @@ impl<'t> Session<'t> {
                 ExpressionType::Str(self.user.to_string()),
                 ExpressionType::Signal("=".to_string())
             ], setence: format!("username=\"{}\"", self.user)},
+            joins: vec![],
+            groups: vec![],
+            havings: Expression::empty(),
+            limit: Option::None,
+            offset: 0,
             orders: vec![]
         }) {
             if dt.content.len() != 1 {
@@ -876,6 +1456,9 @@ impl<'t> Session<'t> {
     pub fn get_username(&self) -> String {
         self.user.to_string()
     }
+    pub fn must_change_password(&self) -> bool {
+        self.must_change
+    }
     pub fn get_using_database(&self) -> Option<String> {
         if let Option::Some(ref s) = self.using {
             Option::Some(s.to_string())
@@ -884,4 +1467,10 @@ impl<'t> Session<'t> {
         }
     }
 
-}
\ No newline at end of file
+}
+impl<'t> Drop for Session<'t> {
+    fn drop(&mut self) {
+        //会话结束时仍未提交的事务视作回滚。
+        self.rollback();
+    }
+}
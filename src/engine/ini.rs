@@ -5,7 +5,10 @@ use std::io::{Read};
 
 pub struct Config{
     pub database: String,
-    pub systembase: String
+    pub systembase: String,
+    pub host: String,  //服务器监听的地址与端口
+    pub port: u16,
+    pub lock_timeout: u64  //等待锁的超时时间，毫秒
 }
 impl Config{
     pub fn load(filepath:&str) -> Self {
@@ -22,7 +25,10 @@ impl Config{
         }
         Self {
             database: if let Option::Some(ref s) = map.get("database") {s}else{"database/"}.to_string(),
-            systembase: if let Option::Some(ref s) = map.get("systembase") {s}else{"system"}.to_string()
+            systembase: if let Option::Some(ref s) = map.get("systembase") {s}else{"system"}.to_string(),
+            host: if let Option::Some(ref s) = map.get("host") {s}else{"127.0.0.1"}.to_string(),
+            port: if let Option::Some(s) = map.get("port") {s.trim().parse().unwrap_or(7070)}else{7070},
+            lock_timeout: if let Option::Some(s) = map.get("lock_timeout") {s.trim().parse().unwrap_or(5000)}else{5000}
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,209 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, Condvar, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use super::save::{ConfigFile, IndexTree};
+// 会话之间的数据库锁
+
+/*
+    每个数据库(包括系统数据库)有一把读写锁，持有者是会话的编号。
+    读语句持有共享锁，写语句持有排他锁，语句结束时释放；事务中取得的锁直到提交或回滚时才释放。
+    已经持有共享锁的会话可以升级为排他锁。有会话在等待排他锁时，新的共享锁也要等待，避免写者一直等不到。
+    等待超过超时时间时加锁失败。
+    两个会话都持有共享锁并都要升级时，它们在互相等待，哪一个都等不到。这时后来的一个立即失败，不必等到超时。
+*/
+static NEXT_OWNER:AtomicUsize = AtomicUsize::new(1);
+
+pub fn new_owner() -> usize {
+    //取得一个新的锁持有者编号。
+    NEXT_OWNER.fetch_add(1, Ordering::SeqCst)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LockMode {
+    Shared,
+    Exclusive
+}
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LockFailure {
+    Timeout,
+    Deadlock
+}
+struct LockState {
+    readers: HashSet<usize>,
+    writer: Option<usize>,
+    waiting: usize,  //正在等待排他锁的会话数目
+    upgrading: Option<usize>  //持有共享锁、正在等待升级为排他锁的会话
+}
+pub struct Lock {
+    state: Mutex<LockState>,
+    cond: Condvar
+}
+impl Default for Lock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lock {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(LockState{readers: HashSet::new(), writer: Option::None, waiting: 0, upgrading: Option::None}),
+            cond: Condvar::new()
+        }
+    }
+    pub fn acquire(&self, owner:usize, mode:LockMode, timeout:Duration) -> Result<(), LockFailure> {
+        //加锁。已经持有足够的锁时直接返回；失败时已经持有的锁不受影响。
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock().unwrap();
+        if state.writer == Option::Some(owner) {return Result::Ok(());}
+        if mode == LockMode::Shared && state.readers.contains(&owner) {return Result::Ok(());}
+        if mode == LockMode::Exclusive && state.readers.contains(&owner) {
+            //另一个会话已经在等待升级，它在等这个会话释放共享锁，而这个会话又要等它。
+            if state.upgrading.is_some() {return Result::Err(LockFailure::Deadlock);}
+            state.upgrading = Option::Some(owner);
+        }
+        if mode == LockMode::Exclusive {state.waiting += 1;}
+        loop {
+            let free = match mode {
+                LockMode::Shared => state.writer.is_none() && state.waiting == 0,
+                LockMode::Exclusive => state.writer.is_none() && state.readers.iter().all(|r|*r == owner)
+            };
+            if free {break;}
+            let now = Instant::now();
+            if now >= deadline {
+                if mode == LockMode::Exclusive {
+                    state.waiting -= 1;
+                    if state.upgrading == Option::Some(owner) {state.upgrading = Option::None;}
+                    self.cond.notify_all();
+                }
+                return Result::Err(LockFailure::Timeout);
+            }
+            state = self.cond.wait_timeout(state, deadline - now).unwrap().0;
+        }
+        match mode {
+            LockMode::Shared => {state.readers.insert(owner);},
+            LockMode::Exclusive => {
+                state.waiting -= 1;
+                if state.upgrading == Option::Some(owner) {state.upgrading = Option::None;}
+                state.readers.remove(&owner);
+                state.writer = Option::Some(owner);
+            }
+        }
+        Result::Ok(())
+    }
+    pub fn release(&self, owner:usize) {
+        let mut state = self.state.lock().unwrap();
+        state.readers.remove(&owner);
+        if state.writer == Option::Some(owner) {state.writer = Option::None;}
+        self.cond.notify_all();
+    }
+    pub fn is_exclusive(&self, owner:usize) -> bool {
+        self.state.lock().unwrap().writer == Option::Some(owner)
+    }
+}
+
+pub struct SharedConfig {
+    //可以被多个会话共用的数据库配置。
+    //ConfigFile放在RwLock中只是为了取得引用：持有共享锁时取读引用，持有排他锁时取写引用，因此不会在RwLock上等待。
+    pub name: String,
+    lock: Lock,
+    conf: RwLock<ConfigFile>
+}
+impl SharedConfig {
+    pub fn new(conf:ConfigFile) -> Self {
+        Self {
+            name: conf.name.to_string(),
+            lock: Lock::new(),
+            conf: RwLock::new(conf)
+        }
+    }
+    pub fn lock(&self, owner:usize, mode:LockMode, timeout:Duration) -> Result<(), String> {
+        match self.lock.acquire(owner, mode, timeout) {
+            Result::Ok(_) => Result::Ok(()),
+            Result::Err(LockFailure::Timeout) => Result::Err(format!("Lock wait timeout on database {}.", self.name)),
+            Result::Err(LockFailure::Deadlock) => Result::Err(format!("Deadlock on database {}: another transaction is waiting to write it too.", self.name))
+        }
+    }
+    pub fn unlock(&self, owner:usize) {
+        self.lock.release(owner);
+    }
+    pub fn is_exclusive(&self, owner:usize) -> bool {
+        self.lock.is_exclusive(owner)
+    }
+    pub fn read(&self) -> RwLockReadGuard<'_, ConfigFile> {
+        //调用者需要已经持有锁。
+        match self.conf.read() {
+            Result::Ok(g) => g,
+            Result::Err(e) => e.into_inner()
+        }
+    }
+    pub fn write(&self) -> RwLockWriteGuard<'_, ConfigFile> {
+        //调用者需要已经持有排他锁。
+        match self.conf.write() {
+            Result::Ok(g) => g,
+            Result::Err(e) => e.into_inner()
+        }
+    }
+    pub fn cache_indexes(&self, loaded:HashMap<String, IndexTree>) {
+        //把只读的会话加载的索引放入共享的缓存。调用者需要持有共享锁，但不能持有read()的引用：
+        //共享锁保证期间没有会话修改数据，索引仍然是最新的；其他会话正在读配置时放弃，下次再加载。
+        if loaded.is_empty() {return;}
+        if let Result::Ok(mut conf) = self.conf.try_write() {
+            for (name, tree) in loaded {
+                if conf.indexes.contains_key(&name) && !conf.index_trees.contains_key(&name) {
+                    conf.index_trees.insert(name, tree);
+                }
+            }
+        }
+    }
+    pub fn read_locked(&self, timeout:Duration) -> Result<ReadGuard<'_>, String> {
+        //在一次操作期间持有共享锁，guard释放时解锁。
+        let owner = new_owner();
+        match self.lock(owner, LockMode::Shared, timeout) {
+            Result::Ok(_) => Result::Ok(ReadGuard{shared: self, owner, conf: self.read()}),
+            Result::Err(e) => Result::Err(e)
+        }
+    }
+    pub fn write_locked(&self, timeout:Duration) -> Result<WriteGuard<'_>, String> {
+        //在一次操作期间持有排他锁，guard释放时解锁。
+        let owner = new_owner();
+        match self.lock(owner, LockMode::Exclusive, timeout) {
+            Result::Ok(_) => Result::Ok(WriteGuard{shared: self, owner, conf: self.write()}),
+            Result::Err(e) => Result::Err(e)
+        }
+    }
+}
+
+pub struct ReadGuard<'a> {
+    shared: &'a SharedConfig,
+    owner: usize,
+    conf: RwLockReadGuard<'a, ConfigFile>
+}
+impl<'a> Deref for ReadGuard<'a> {
+    type Target = ConfigFile;
+    fn deref(&self) -> &ConfigFile {&self.conf}
+}
+impl<'a> Drop for ReadGuard<'a> {
+    fn drop(&mut self) {
+        self.shared.unlock(self.owner);
+    }
+}
+pub struct WriteGuard<'a> {
+    shared: &'a SharedConfig,
+    owner: usize,
+    conf: RwLockWriteGuard<'a, ConfigFile>
+}
+impl<'a> Deref for WriteGuard<'a> {
+    type Target = ConfigFile;
+    fn deref(&self) -> &ConfigFile {&self.conf}
+}
+impl<'a> DerefMut for WriteGuard<'a> {
+    fn deref_mut(&mut self) -> &mut ConfigFile {&mut self.conf}
+}
+impl<'a> Drop for WriteGuard<'a> {
+    fn drop(&mut self) {
+        self.shared.unlock(self.owner);
+    }
+}
@@ -1,4 +1,6 @@
 pub mod save;
 pub mod engine;
 pub mod ini;
-pub mod database;
\ No newline at end of file
+pub mod database;
+pub mod wal;
+pub mod lock;
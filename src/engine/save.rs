@@ -1,15 +1,22 @@
 extern crate serde_json;
 use std::cmp::Ordering;
-use std::fs::{File, OpenOptions, remove_file};
+use std::fs::{File, OpenOptions, TryLockError, remove_file};
 use std::io::{Read, Write, Seek, SeekFrom};
-use std::mem::{transmute};
-use std::collections::HashMap;
+use std::mem::{transmute, take};
+use std::collections::{HashMap, BTreeMap};
 use self::serde_json::{Value, Number, Map};
-use super::database::{Database};
+use super::database::{Database, ConfRef};
+use super::wal::{WriteAheadLog};
 use super::super::analyse::dfa::{DfaWord};
-use super::super::syntax::structures::{ExpressionType, SelectSyntax, Expression, Switch};
+use super::super::analyse::datetime;
+use super::super::analyse::decimal;
+use super::super::syntax::structures::{ExpressionType, SelectSyntax, JoinSyntax, Expression, Switch};
 
-const PAGE_SIZE:usize = 64 * 1024; // 64K
+pub const PAGE_SIZE:usize = 64 * 1024; // 64K
+pub const OVERFLOW_HEAD:usize = 16; // 溢出页的页头：下一页的页号+1(0表示最后一页)，本页中内容的字节数。
+pub const INLINE_SIZE:usize = 24; // text/blob的内容不超过这个长度时直接保存在记录中。
+pub const SHARED_MAX:usize = (PAGE_SIZE - OVERFLOW_HEAD) / 4; // 内容不超过这个长度时与其他值共用溢出页，更长的内容单独占用一条溢出页链。
+pub const DATA_VERSION:usize = 1; // 数据文件格式的版本。1: 记录带有空值位图。
 
 //= 存储数据库行为描述和文件划分的结构 =======================================
 /*
@@ -20,17 +27,35 @@ const PAGE_SIZE:usize = 64 * 1024; // 64K
 */
 pub struct ConfigFile {
     pub name:String,
+    pub version:usize,
     filepath:String,
     basepath:String,
     pub pages:Vec<PageType>,
     pub table_pages: HashMap<String, Vec<usize>>,
     pub tables: HashMap<String, TableConfig>,
-    pub views: HashMap<String, SelectSyntax>
+    pub views: HashMap<String, SelectSyntax>,
+    pub indexes: HashMap<String, IndexConfig>,
+    pub index_pages: HashMap<String, Vec<usize>>,
+    pub index_trees: HashMap<String, IndexTree>,  //已经加载到内存的索引，不写入配置文件。
+    _lock: File  //持有排他锁的.lock文件，防止其他进程同时打开这个数据库
 }
 impl ConfigFile {
-    pub fn new(basepath:String, name:String) -> Self {
+    pub fn new(basepath:String, name:String) -> Result<Self, String> {
         let filepath = basepath.to_string() + name.as_str() + ".dba";
         //println!("open file {}", filepath);
+        //先取得.lock文件的排他锁。锁由操作系统在进程结束时释放，不会残留。
+        let lock = match OpenOptions::new().create(true).read(true).write(true).open(basepath.to_string() + name.as_str() + ".lock") {
+            Result::Ok(f) => f,
+            Result::Err(e) => {return Result::Err(format!("Cannot open database {}: {}.", name, e));}
+        };
+        match lock.try_lock() {
+            Result::Ok(_) => {},
+            Result::Err(TryLockError::WouldBlock) => {return Result::Err(format!("Database {} is in use by another process.", name));},
+            Result::Err(TryLockError::Error(e)) => {return Result::Err(format!("Cannot lock database {}: {}.", name, e));}
+        }
+        //如果上一次提交中断，先重放预写日志。
+        WriteAheadLog::new(basepath.to_string() + name.as_str() + ".wal").recover(
+            (basepath.to_string() + name.as_str() + ".db").as_str(), filepath.as_str());
         let mut f = OpenOptions::new().create(true).read(true).write(true).open(filepath.to_string()).unwrap();
         {
             //开一下存储文件试试。
@@ -38,7 +63,29 @@ impl ConfigFile {
         }
         let mut s = String::new();
         f.read_to_string(&mut s).unwrap();;
-        let v: Value = match serde_json::from_str(&s) {
+        let mut conf = ConfigFile {
+            name,
+            version: DATA_VERSION,
+            filepath,
+            basepath,
+            pages: Vec::new(),
+            table_pages: HashMap::new(),
+            tables: HashMap::new(),
+            views: HashMap::new(),
+            indexes: HashMap::new(),
+            index_pages: HashMap::new(),
+            index_trees: HashMap::new(),
+            _lock: lock
+        };
+        conf.load(&s);
+        if conf.version < DATA_VERSION {
+            conf.session().upgrade();
+        }
+        Result::Ok(conf)
+    }
+    pub fn load(&mut self, s:&str) {
+        //从JSON字符串加载配置，覆盖内存中的全部配置。空的或无法解析的内容视作空数据库。
+        let v: Value = match serde_json::from_str(s) {
             Ok(ok) => ok,
             Err(..) => Value::Null
         };
@@ -55,8 +102,9 @@ impl ConfigFile {
                 }
                 nw
             }else{panic!("Wrong config type.");};
-            //从分页加载每个表的分页
+            //从分页加载每个表和每个索引的分页
             let mut table_pages:HashMap<String, Vec<usize>> = HashMap::new();
+            let mut index_pages:HashMap<String, Vec<usize>> = HashMap::new();
             for (i, m) in pages.iter().enumerate() {
                 match m {
                     &PageType::Data(ref s) => {
@@ -65,6 +113,30 @@ impl ConfigFile {
                         }else{
                             table_pages.insert(s.to_string(), vec![i]);
                         }
+                    },
+                    PageType::Index(s) => {
+                        if index_pages.contains_key(s.as_str()) {
+                            index_pages.get_mut(s.as_str()).unwrap().push(i);
+                        }else{
+                            index_pages.insert(s.to_string(), vec![i]);
+                        }
+                    },
+                    &PageType::Overflow(_) | &PageType::Shared(..) | &PageType::Free => {}
+                }
+            }
+            //复用空闲页之后，页链的顺序不再与页号顺序一致，因此页链的顺序单独保存。
+            //旧版本的配置文件中没有这两项，按页号顺序即可。
+            if let Option::Some(Value::Object(fl)) = map.get("table_pages") {
+                for (k, v) in fl.iter() {
+                    if table_pages.contains_key(k.as_str()) {
+                        table_pages.insert(k.to_string(), ConfigFile::page_chain_from_json(v));
+                    }
+                }
+            }
+            if let Option::Some(Value::Object(fl)) = map.get("index_pages") {
+                for (k, v) in fl.iter() {
+                    if index_pages.contains_key(k.as_str()) {
+                        index_pages.insert(k.to_string(), ConfigFile::page_chain_from_json(v));
                     }
                 }
             }
@@ -82,41 +154,50 @@ impl ConfigFile {
                     nw.insert(k.to_string(), SelectSyntax::from_json(v));
                 }nw
             }else{panic!("Wrong config type.");};
+            //读索引配置。旧版本的配置文件中没有这一项。
+            let mut indexes:HashMap<String, IndexConfig> = HashMap::new();
+            if let Option::Some(Value::Object(fl)) = map.get("indexes") {
+                for (k, v) in fl.iter() {
+                    indexes.insert(k.to_string(), IndexConfig::from_json(v));
+                }
+            }
 
-            return ConfigFile {
-                name: if let Value::String(ref s) = map["name"] {s.to_string()}else{name},
-                filepath: filepath,
-                basepath: basepath,
-                pages: pages,
-                table_pages: table_pages,
-                tables: tables,
-                views: views
-            };
-        }else if let Value::Null = v {
-            return ConfigFile {
-                name: name,
-                filepath: filepath,
-                basepath: basepath,
-                pages: Vec::new(),
-                table_pages: HashMap::new(),
-                tables: HashMap::new(),
-                views: HashMap::new()
+            if let Value::String(ref s) = map["name"] {self.name = s.to_string();}
+            //旧版本的配置文件中没有版本号。
+            self.version = match map.get("version") {
+                Option::Some(Value::Number(n)) => n.as_u64().unwrap_or(0) as usize,
+                _ => 0
             };
+            self.pages = pages;
+            self.table_pages = table_pages;
+            self.tables = tables;
+            self.views = views;
+            self.indexes = indexes;
+            self.index_pages = index_pages;
         }else{
-            return ConfigFile {
-                name: name,
-                filepath: filepath,
-                basepath: basepath,
-                pages: Vec::new(),
-                table_pages: HashMap::new(),
-                tables: HashMap::new(),
-                views: HashMap::new()
-            };
+            self.version = DATA_VERSION;
+            self.pages = Vec::new();
+            self.table_pages = HashMap::new();
+            self.tables = HashMap::new();
+            self.views = HashMap::new();
+            self.indexes = HashMap::new();
+            self.index_pages = HashMap::new();
         }
+        self.index_trees = HashMap::new();
     }
     pub fn save(&self) {
+        let js = self.to_json_string();
+        self.save_json(&js);
+    }
+    pub fn save_json(&self, js:&str) {
+        let mut f = OpenOptions::new().create(true).truncate(true).write(true).open(&self.filepath).unwrap();
+        f.write_all(js.as_bytes()).unwrap();
+        f.sync_all().unwrap();
+    }
+    pub fn to_json_string(&self) -> String {
         let mut map:HashMap<String, Value> = HashMap::new();
         map.insert("name".to_string(), Value::String(self.name.to_string()));
+        map.insert("version".to_string(), Value::Number(Number::from(self.version as u64)));
         let mut arr = Vec::new();
         for i in self.pages.iter() {arr.push(Value::String(i.to_string()));}
         map.insert("pages".to_string(), Value::Array(arr));
@@ -126,20 +207,111 @@ impl ConfigFile {
         let mut views = Map::new();
         for (k, v) in self.views.iter() {views.insert(k.to_string(), v.to_json());}
         map.insert("views".to_string(), Value::Object(views));
+        let mut indexes = Map::new();
+        for (k, v) in self.indexes.iter() {indexes.insert(k.to_string(), v.to_json());}
+        map.insert("indexes".to_string(), Value::Object(indexes));
+        let mut table_pages = Map::new();
+        for (k, v) in self.table_pages.iter() {table_pages.insert(k.to_string(), ConfigFile::page_chain_to_json(v));}
+        map.insert("table_pages".to_string(), Value::Object(table_pages));
+        let mut index_pages = Map::new();
+        for (k, v) in self.index_pages.iter() {index_pages.insert(k.to_string(), ConfigFile::page_chain_to_json(v));}
+        map.insert("index_pages".to_string(), Value::Object(index_pages));
 
-        let js = serde_json::to_string(&map).unwrap();
-
-        let mut f = OpenOptions::new().create(true).truncate(true).write(true).open(self.filepath.to_string()).unwrap();
-        f.write_all(js.as_bytes()).unwrap();
+        serde_json::to_string(&map).unwrap()
+    }
+    fn page_chain_to_json(chain:&[usize]) -> Value {
+        Value::Array(chain.iter().map(|i|Value::Number(Number::from(*i as u64))).collect())
+    }
+    fn page_chain_from_json(v:&Value) -> Vec<usize> {
+        if let Value::Array(arr) = v {
+            arr.iter().map(|i|i.as_u64().unwrap_or(0) as usize).collect()
+        }else{panic!("Wrong config type.");}
+    }
+    pub fn alloc_page(&mut self, owner:PageType) -> usize {
+        //分配一个新页。优先复用页号最小的空闲页，没有空闲页时才在文件末尾追加。
+        let free = self.pages.iter().position(|p|if let &PageType::Free = p {true}else{false});
+        match free {
+            Option::Some(i) => {
+                self.pages[i] = owner;
+                i
+            },
+            Option::None => {
+                self.pages.push(owner);
+                self.pages.len() - 1
+            }
+        }
+    }
+    pub fn alloc_shared(&mut self, table:&str, len:usize) -> (usize, usize) {
+        //在表的共享溢出页中为长度为len的内容分配位置，返回页号与页内偏移。每个值之前有8字节的长度。
+        //共享溢出页只从已分配的位置往后分配，被释放的值占用的空间要等到整页都不再使用时才回收。
+        let need = len + 8;
+        for (i, p) in self.pages.iter_mut().enumerate() {
+            if let PageType::Shared(ref t, ref mut end, ref mut live) = *p {
+                if t == table && *end + need <= PAGE_SIZE {
+                    let offset = *end;
+                    *end += need;
+                    *live += 1;
+                    return (i, offset);
+                }
+            }
+        }
+        //页头的位置不使用，这样共享溢出页中的值的页内偏移都不为0。
+        let i = self.alloc_page(PageType::Shared(table.to_string(), OVERFLOW_HEAD + need, 1));
+        (i, OVERFLOW_HEAD)
+    }
+    pub fn release_shared(&mut self, page:usize) {
+        //释放共享溢出页中的一个值。页中不再有使用的值时归还到空闲页表。
+        let empty = match self.pages.get_mut(page) {
+            Option::Some(&mut PageType::Shared(_, _, ref mut live)) => {
+                if *live > 0 {*live -= 1;}
+                *live == 0
+            },
+            _ => false
+        };
+        if empty {self.pages[page] = PageType::Free;}
+    }
+    pub fn free_pages(&mut self, pages:&[usize]) {
+        //将页归还到空闲页表中。文件不会因此变短。
+        for &i in pages.iter() {
+            if i < self.pages.len() {self.pages[i] = PageType::Free;}
+        }
     }
     pub fn delete_file(&self) {
         remove_file(self.basepath.to_string() + self.name.as_str() + ".db").unwrap();
         remove_file(self.filepath.to_string()).unwrap();
+        self.wal().clear();
+        //.lock文件保留不删：仍持有锁时删除它，别的进程就能新建同名文件并加锁，两边会同时打开同一个数据库。
+    }
+    pub fn wal(&self) -> WriteAheadLog {
+        WriteAheadLog::new(self.basepath.to_string() + self.name.as_str() + ".wal")
     }
     pub fn session(&mut self) -> Database {
         Database{
             file: SaveFile::new(self.basepath.to_string() + self.name.as_str() + ".db"),
-            conf: self
+            conf: ConfRef::Write(self),
+            loaded: HashMap::new(),
+            subqueries: HashMap::new()
+        }
+    }
+    pub fn session_with(&mut self, dirty:HashMap<usize, Vec<u8>>) -> Database<'_> {
+        //带着之前尚未提交的脏页打开会话，用于事务中的后续语句。
+        Database{
+            file: SaveFile::with_dirty(self.basepath.to_string() + self.name.as_str() + ".db", dirty),
+            conf: ConfRef::Write(self),
+            loaded: HashMap::new(),
+            subqueries: HashMap::new()
+        }
+    }
+    pub fn reader(&self) -> Database<'_> {
+        //只读的会话，只能用于查询。多个只读的会话可以同时使用同一份配置。
+        self.reader_with(HashMap::new())
+    }
+    pub fn reader_with(&self, dirty:HashMap<usize, Vec<u8>>) -> Database<'_> {
+        Database{
+            file: SaveFile::with_dirty(self.basepath.to_string() + self.name.as_str() + ".db", dirty),
+            conf: ConfRef::Read(self),
+            loaded: HashMap::new(),
+            subqueries: HashMap::new()
         }
     }
 }
@@ -190,17 +362,48 @@ impl SelectSyntax {
             let wheres = if let Option::Some(some) = map.get("wheres") {
                 Expression::from_json(some)
             }else{Expression::empty()};
+            //旧版本的视图中没有连接和分组。
+            let mut joins = vec![];
+            if let Option::Some(Value::Array(arr)) = map.get("joins") {
+                for i in arr.iter() {
+                    if let Value::Object(j) = i {
+                        let get = |k:&str| if let Option::Some(Value::String(s)) = j.get(k) {s.to_string()}else{panic!("Wrong type.")};
+                        let mut join = JoinSyntax::new(get("kind").as_str());
+                        join.name = get("name");
+                        join.table = get("table");
+                        if let Option::Some(on) = j.get("on") {join.on = Expression::from_json(on);}
+                        joins.push(join);
+                    }
+                }
+            }
+            let groups = if let Option::Some(Value::Array(arr)) = map.get("groups") {
+                arr.iter().map(Expression::from_json).collect()
+            }else{vec![]};
+            let havings = if let Option::Some(some) = map.get("havings") {
+                Expression::from_json(some)
+            }else{Expression::empty()};
+            let limit = if let Option::Some(Value::Number(n)) = map.get("limit") {
+                Option::Some(n.as_u64().unwrap() as usize)
+            }else{Option::None};
+            let offset = if let Option::Some(Value::Number(n)) = map.get("offset") {
+                n.as_u64().unwrap() as usize
+            }else{0};
             SelectSyntax {
                 distinct: distinct,
                 froms: froms,
+                joins,
                 goals: goals,
                 orders: orders,
-                wheres: wheres
+                wheres,
+                groups,
+                havings,
+                limit,
+                offset
             }
         }else{panic!("Wrong config type.");}
         
     }
-    fn to_json(&self) -> Value {
+    pub fn to_json(&self) -> Value {
         let mut map = Map::new();
         map.insert("distinct".to_string(), Value::Bool(self.distinct));
 
@@ -213,18 +416,35 @@ impl SelectSyntax {
         }
         map.insert("froms".to_string(), Value::Object(froms));
 
+        let mut joins = Vec::new();
+        for j in self.joins.iter() {
+            let mut join = Map::new();
+            join.insert("kind".to_string(), Value::String(j.kind.to_string()));
+            join.insert("name".to_string(), Value::String(j.name.to_string()));
+            join.insert("table".to_string(), Value::String(j.table.to_string()));
+            join.insert("on".to_string(), j.on.to_json());
+            joins.push(Value::Object(join));
+        }
+        map.insert("joins".to_string(), Value::Array(joins));
+
         let mut goals = Vec::new();
         for &(ref k, ref v) in self.goals.iter() {
             goals.push(Value::Array(vec![Value::String(k.to_string()), v.to_json()]));
         }
         map.insert("goals".to_string(), Value::Array(goals));
         map.insert("wheres".to_string(), self.wheres.to_json());
+        map.insert("groups".to_string(), Value::Array(self.groups.iter().map(|g|g.to_json()).collect()));
+        map.insert("havings".to_string(), self.havings.to_json());
 
         let mut orders = Vec::new();
         for &(ref k, ref v) in self.orders.iter() {
             orders.push(Value::Array(vec![Value::String(k.to_string()), Value::Bool(*v)]));
         }
         map.insert("orders".to_string(), Value::Array(orders));
+        if let Option::Some(limit) = self.limit {
+            map.insert("limit".to_string(), Value::from(limit as u64));
+        }
+        map.insert("offset".to_string(), Value::from(self.offset as u64));
         Value::Object(map)
     }
 }
@@ -247,7 +467,20 @@ impl Expression {
                             "Integer" => ExpressionType::Integer(s[7..].parse().unwrap()),
                             "Float" => ExpressionType::Float(s[7..].parse().unwrap()),
                             "Str" => ExpressionType::Str(s[7..].to_string()),
+                            "Date" => ExpressionType::Date(s[7..].parse().unwrap()),
+                            "Time" => ExpressionType::Time(s[7..].parse().unwrap()),
+                            "Stamp" => ExpressionType::Timestamp(s[7..].parse().unwrap()),
+                            "Decimal" => {
+                                let u = s.rfind(':').unwrap();
+                                ExpressionType::Decimal(s[7..u].parse().unwrap(), s[u+1..].parse().unwrap())
+                            },
                             "Signal" => ExpressionType::Signal(s[7..].to_string()),
+                            "Null" => ExpressionType::Null,
+                            "Select" => ExpressionType::Select(Box::new(SelectSyntax::from_json(&serde_json::from_str(&s[7..]).unwrap()))),
+                            "Func" => {
+                                let u = s.rfind(':').unwrap();
+                                ExpressionType::Func(s[7..u].to_string(), s[u+1..].parse().unwrap())
+                            },
                             _ => {panic!(format!("Wrong type config."))}
                         });
                     }
@@ -271,7 +504,15 @@ impl Expression {
                 &ExpressionType::Integer(i) => format!("Integer{}", i),
                 &ExpressionType::Float(f) => format!("Float  {}", f),
                 &ExpressionType::Str(ref s) => format!("Str    {}", s),
-                &ExpressionType::Signal(ref s) => format!("Signal {}", s)
+                &ExpressionType::Date(d) => format!("Date   {}", d),
+                &ExpressionType::Time(t) => format!("Time   {}", t),
+                &ExpressionType::Timestamp(t) => format!("Stamp  {}", t),
+                &ExpressionType::Decimal(v, s) => format!("Decimal{}:{}", v, s),
+                ExpressionType::Signal(s) => format!("Signal {}", s),
+                &ExpressionType::Null => "Null   ".to_string(),
+                &ExpressionType::Func(ref name, argc) => format!("Func   {}:{}", name, argc),
+                ExpressionType::Select(sub) => format!("Select {}", serde_json::to_string(&sub.to_json()).unwrap()),
+                &ExpressionType::List(_) => {panic!("Wrong type: list cannot be saved.")}
             }));
         }
         let mut map = Map::new();
@@ -281,17 +522,43 @@ impl Expression {
     }
 }
 pub enum PageType {
-    Data(String)  //数据页
+    Data(String),  //数据页
+    Index(String),  //索引页
+    Overflow(String),  //溢出页，存放表中较长的text/blob字段的内容
+    Shared(String, usize, usize),  //共享溢出页，存放表中较短的text/blob字段的内容：表名, 已分配到的位置, 仍在使用的值的数目
+    Free  //空闲页，可以被新的数据页或索引页复用
 }
 impl PageType {
     pub fn to_string(&self) -> String {
         match self {
-            &PageType::Data(ref s) => format!("data:{}", s)
+            PageType::Data(s) => format!("data:{}", s),
+            PageType::Index(s) => format!("index:{}", s),
+            PageType::Overflow(s) => format!("overflow:{}", s),
+            PageType::Shared(s, end, live) => format!("shared:{}:{}:{}", end, live, s),
+            &PageType::Free => "free".to_string()
+        }
+    }
+    pub fn copy(&self) -> Self {
+        match self {
+            PageType::Data(s) => PageType::Data(s.to_string()),
+            PageType::Index(s) => PageType::Index(s.to_string()),
+            PageType::Overflow(s) => PageType::Overflow(s.to_string()),
+            &PageType::Shared(ref s, end, live) => PageType::Shared(s.to_string(), end, live),
+            &PageType::Free => PageType::Free
         }
     }
     pub fn from_string(s:String) -> Self {
         if s.starts_with("data:") {
             PageType::Data(s[5..].to_string())
+        }else if s.starts_with("index:") {
+            PageType::Index(s[6..].to_string())
+        }else if s.starts_with("overflow:") {
+            PageType::Overflow(s[9..].to_string())
+        }else if let Option::Some(rest) = s.strip_prefix("shared:") {
+            let parts:Vec<&str> = rest.splitn(3, ':').collect();
+            PageType::Shared(parts[2].to_string(), parts[0].parse().unwrap(), parts[1].parse().unwrap())
+        }else if s == "free" {
+            PageType::Free
         }else{
             panic!("WRONG EXTERNAL TYPE: PAGE TYPE ERROR.")
         }
@@ -301,13 +568,71 @@ impl PageType {
 
 
 
+//= 索引配置 ===========================================
+/*
+    索引建立在单个字段上。索引页与数据页一样存放在.db文件中，
+    页内依次存放(键, 行号)对，每一对就是一条两列的Data记录。
+    使用时整个索引被加载到内存中，组织为以键排序的B树(BTreeMap)，
+    等值与范围查找都在B树上进行。
+*/
+pub struct IndexConfig {
+    pub name: String,
+    pub table: String,
+    pub field: String,
+    pub count: usize  //索引页中(键, 行号)对的数目
+}
+impl IndexConfig {
+    pub fn to_json(&self) -> Value {
+        let mut map = Map::new();
+        map.insert("name".to_string(), Value::String(self.name.to_string()));
+        map.insert("table".to_string(), Value::String(self.table.to_string()));
+        map.insert("field".to_string(), Value::String(self.field.to_string()));
+        map.insert("count".to_string(), Value::Number(Number::from_f64(self.count as f64).unwrap()));
+        Value::Object(map)
+    }
+    pub fn from_json(v:&Value) -> Self {
+        if let Value::Object(map) = v {
+            Self {
+                name: if let Option::Some(Value::String(s)) = map.get("name") {s.to_string()}else{panic!("Wrong config type.");},
+                table: if let Option::Some(Value::String(s)) = map.get("table") {s.to_string()}else{panic!("Wrong config type.");},
+                field: if let Option::Some(Value::String(s)) = map.get("field") {s.to_string()}else{panic!("Wrong config type.");},
+                count: if let Option::Some(Value::Number(n)) = map.get("count") {n.as_f64().unwrap() as usize}else{0}
+            }
+        }else{panic!("Wrong config type.");}
+    }
+    pub fn get_template(&self, t:&FieldType) -> Data {
+        //索引页中一条记录的模板：(键, 行号)
+        Data{li: vec![t.get_dataitem(), DataItem::Integer(0)]}
+    }
+}
+pub type IndexTree = BTreeMap<IndexKey, Vec<usize>>;
+pub struct IndexKey(pub DataItem);
+impl PartialEq for IndexKey {
+    fn eq(&self, other:&IndexKey) -> bool {self.cmp(other) == Ordering::Equal}
+}
+impl Eq for IndexKey {}
+impl PartialOrd for IndexKey {
+    fn partial_cmp(&self, other:&IndexKey) -> Option<Ordering> {Option::Some(self.cmp(other))}
+}
+impl Ord for IndexKey {
+    fn cmp(&self, other:&IndexKey) -> Ordering {
+        //同一索引中的键类型一致。无法比较的情况按相等处理。
+        match self.0.cmp(&other.0) {
+            Result::Ok(o) => o,
+            Result::Err(_) => Ordering::Equal
+        }
+    }
+}
+
 //= 表配置 =============================================
 pub struct TableConfig {
     pub name: String,  //表名
     pub fields: Vec<FieldConfig>,  // 字段列表
-    pub primary: Vec<String>,  // 特别标记主键
+    pub primary: Vec<String>,  // 特别标记主键。有多个字段时按整个元组查重
+    pub uniques: Vec<Vec<String>>,  //多个字段组合的unique约束
     pub auto_config: HashMap<String, usize>,  //用来记录自增属性的自增位置
     pub foreign: HashMap<String, ForeignConfig>,  //记录外键列表
+    pub checks: Vec<(String, Expression)>,  //check约束的名称与条件
     pub count: usize  //记录数量
 }
 impl TableConfig {
@@ -333,6 +658,19 @@ impl TableConfig {
             auto.insert(k.to_string(), Value::Number(Number::from_f64(*v as f64).unwrap()));
         }
         map.insert("auto_config".to_string(), Value::Object(auto));
+        //insert primary & uniques
+        map.insert("primary".to_string(), Value::Array(self.primary.iter().map(|s|Value::String(s.to_string())).collect()));
+        let mut uniques = Vec::new();
+        for u in self.uniques.iter() {
+            uniques.push(Value::Array(u.iter().map(|s|Value::String(s.to_string())).collect()));
+        }
+        map.insert("uniques".to_string(), Value::Array(uniques));
+        //insert checks
+        let mut checks = Vec::new();
+        for (name, e) in self.checks.iter() {
+            checks.push(Value::Array(vec![Value::String(name.to_string()), e.to_json()]));
+        }
+        map.insert("checks".to_string(), Value::Array(checks));
         //insert count
         map.insert("count".to_string(), Value::Number(Number::from_f64(self.count as f64).unwrap()));
         //return result
@@ -359,8 +697,16 @@ impl TableConfig {
                     nw
                 }else{vec![]}
             }else{vec![]};
-            let mut primary = Vec::new();
-            for i in fields.iter() { if i.primary {primary.push(i.name.to_string());} }
+            //旧版本的表中没有记录主键与组合unique，主键按字段上的标记取得。
+            let names = |v:&Value| if let Value::Array(arr) = v {
+                arr.iter().filter_map(|s|if let Value::String(ss) = s {Option::Some(ss.to_string())}else{Option::None}).collect()
+            }else{vec![]};
+            let primary:Vec<String> = if let Option::Some(p) = map.get("primary") {names(p)}else{
+                fields.iter().filter(|i|i.primary).map(|i|i.name.to_string()).collect()
+            };
+            let uniques:Vec<Vec<String>> = if let Option::Some(Value::Array(arr)) = map.get("uniques") {
+                arr.iter().map(names).collect()
+            }else{vec![]};
             let foreign = if let Option::Some(ref oob) = map.get("foreign") {
                 if let &Value::Object(ref ob) = *oob {
                     let mut map = HashMap::new();
@@ -381,12 +727,25 @@ impl TableConfig {
                     map
                 }else{HashMap::new()}
             }else{HashMap::new()};
+            //旧版本的表中没有check约束。
+            let mut checks = Vec::new();
+            if let Option::Some(Value::Array(arr)) = map.get("checks") {
+                for i in arr.iter() {
+                    if let Value::Array(pair) = i {
+                        if let Value::String(s) = &pair[0] {
+                            checks.push((s.to_string(), Expression::from_json(&pair[1])));
+                        }
+                    }
+                }
+            }
             Self {
                 name: name,
                 fields: fields,
                 auto_config: auto_config,
                 foreign: foreign,
                 primary: primary,
+                uniques,
+                checks,
                 count: count
             }
         }else {panic!("Wrong config type.");}
@@ -458,7 +817,10 @@ impl FieldConfig {
                 &DataItem::Str(_, ref s) => Value::String(s.to_string()),
                 &DataItem::Bool(b) => Value::Bool(b),
                 &DataItem::Integer(i) => Value::Number(Number::from_f64(i as f64).unwrap()),
-                &DataItem::Float(f) => Value::Number(Number::from_f64(f).unwrap())
+                &DataItem::Float(f) => Value::Number(Number::from_f64(f).unwrap()),
+                d@&DataItem::Date(_) | d@&DataItem::Time(_) | d@&DataItem::Timestamp(_) |
+                d@&DataItem::Text(..) | d@&DataItem::Blob(..) | d@&DataItem::Decimal(..) => Value::String(d.to_string()),
+                &DataItem::Null(_) => Value::Null
             }
         });
         Value::Object(map)
@@ -488,10 +850,14 @@ impl FieldConfig {
                 default: if let Option::Some(some) = map.get("default") {
                     match some {
                         &Value::Bool(s) => Option::Some(DataItem::Bool(s)),
-                        &Value::String(ref s) => Option::Some(DataItem::Str(0, s.to_string())),
+                        Value::String(s) => match t {
+                            FieldType::Date | FieldType::Time | FieldType::Timestamp |
+                            FieldType::Text | FieldType::Blob | FieldType::Decimal(..) => t.parse_value(s),
+                            _ => Option::Some(DataItem::Str(0, s.to_string()))
+                        },
                         &Value::Number(ref n) => Option::Some(match t{
                             FieldType::Float => DataItem::Float(n.as_f64().unwrap()),
-                            FieldType::Integer => DataItem::Integer(n.as_i64().unwrap()),
+                            FieldType::Integer => DataItem::Integer(n.as_f64().unwrap() as i64),  //整数默认值按浮点数保存
                             _ => {panic!("Wrong config type.");}
                         }),
                         _ => {Option::None}
@@ -507,7 +873,13 @@ pub enum FieldType {
     Integer,
     Float,
     Bool,
-    Str(usize)
+    Str(usize),
+    Date,
+    Time,
+    Timestamp,
+    Text,  //不定长的字符串与二进制数据，较短的内容保存在记录中，其余的保存在溢出页中
+    Blob,
+    Decimal(usize, usize)  //定点小数：总位数, 小数位数
 }
 impl PartialEq for FieldType {
     fn eq(&self, other: &FieldType) -> bool {
@@ -516,6 +888,12 @@ impl PartialEq for FieldType {
             &FieldType::Float => if let &FieldType::Float = other {true}else{false},
             &FieldType::Bool => if let &FieldType::Bool = other {true}else{false},
             &FieldType::Str(_) => if let &FieldType::Str(_) = other {true}else{false},
+            &FieldType::Date => if let &FieldType::Date = other {true}else{false},
+            &FieldType::Time => if let &FieldType::Time = other {true}else{false},
+            &FieldType::Timestamp => if let &FieldType::Timestamp = other {true}else{false},
+            &FieldType::Text => if let &FieldType::Text = other {true}else{false},
+            &FieldType::Blob => if let &FieldType::Blob = other {true}else{false},
+            &FieldType::Decimal(..) => if let &FieldType::Decimal(..) = other {true}else{false}
         }
     }
 }
@@ -525,29 +903,83 @@ impl FieldType {
             &FieldType::Integer => "integer".to_string(),
             &FieldType::Float => "float".to_string(),
             &FieldType::Bool => "bool".to_string(),
-            &FieldType::Str(u) => format!("str:{}", u)
+            &FieldType::Str(u) => format!("str:{}", u),
+            &FieldType::Date => "date".to_string(),
+            &FieldType::Time => "time".to_string(),
+            &FieldType::Timestamp => "timestamp".to_string(),
+            &FieldType::Text => "text".to_string(),
+            &FieldType::Blob => "blob".to_string(),
+            &FieldType::Decimal(p, s) => format!("decimal:{}:{}", p, s)
         }
     }
     pub fn from_string(s:&str) -> Self {
-        match s {
+        match FieldType::parse(s) {
+            Option::Some(t) => t,
+            Option::None => {panic!("Wrong config value.");}
+        }
+    }
+    pub fn parse(s:&str) -> Option<Self> {
+        //从类型字符串中得到字段类型。类型或参数不正确时返回None。
+        Option::Some(match s {
             "integer" => FieldType::Integer,
             "float" => FieldType::Float,
             "bool" => FieldType::Bool,
+            "date" => FieldType::Date,
+            "time" => FieldType::Time,
+            "timestamp" => FieldType::Timestamp,
+            "text" => FieldType::Text,
+            "blob" => FieldType::Blob,
+            "decimal" => FieldType::Decimal(10, 0),  //没有给出参数时的默认精度
             _ => {
                 if s.starts_with("str:") {
-                    FieldType::Str(s[4..].parse().unwrap())
+                    match s[4..].parse() {
+                        Result::Ok(u) => FieldType::Str(u),
+                        Result::Err(_) => {return Option::None;}
+                    }
+                }else if s.starts_with("decimal:") {
+                    //总位数为1~MAX_PRECISION，小数位数不能超过总位数。
+                    let v:Vec<&str> = s[8..].split(':').collect();
+                    if v.len() > 2 {return Option::None;}
+                    match (v[0].parse(), v.get(1).unwrap_or(&"0").parse()) {
+                        (Result::Ok(p), Result::Ok(d)) if (1..=decimal::MAX_PRECISION).contains(&p) && d <= p => FieldType::Decimal(p, d),
+                        _ => {return Option::None;}
+                    }
                 }else{
-                    panic!("Wrong config value.");
+                    return Option::None;
                 }
             }
-        }
+        })
     }
     pub fn get_dataitem(&self) -> DataItem {
         match self {
             &FieldType::Integer => DataItem::Integer(0),
             &FieldType::Float => DataItem::Float(0.0),
             &FieldType::Bool => DataItem::Bool(false),
-            &FieldType::Str(u) => DataItem::Str(u, "".to_string())
+            &FieldType::Str(u) => DataItem::Str(u, "".to_string()),
+            &FieldType::Date => DataItem::Date(0),
+            &FieldType::Time => DataItem::Time(0),
+            &FieldType::Timestamp => DataItem::Timestamp(0),
+            &FieldType::Text => DataItem::Text(Option::None, "".to_string()),
+            &FieldType::Blob => DataItem::Blob(Option::None, vec![]),
+            &FieldType::Decimal(p, s) => DataItem::Decimal(p, s, 0)
+        }
+    }
+    pub fn parse_value(&self, s:&str) -> Option<DataItem> {
+        //把字符串形式的值(如default)转换为该类型的数据。无法转换时返回None。
+        match self {
+            &FieldType::Integer => s.parse().ok().map(DataItem::Integer),
+            &FieldType::Float => s.parse().ok().map(DataItem::Float),
+            &FieldType::Bool => s.parse().ok().map(DataItem::Bool),
+            &FieldType::Str(u) => Option::Some(DataItem::Str(u, s.to_string())),
+            &FieldType::Date => datetime::parse_date(s).map(DataItem::Date),
+            &FieldType::Time => datetime::parse_time(s).map(DataItem::Time),
+            &FieldType::Timestamp => datetime::parse_timestamp(s).map(DataItem::Timestamp),
+            &FieldType::Text => Option::Some(DataItem::Text(Option::None, s.to_string())),
+            &FieldType::Blob => from_hex(s).map(|b|DataItem::Blob(Option::None, b)),
+            &FieldType::Decimal(..) => match decimal::parse(s) {
+                Option::Some((v, d)) => DataItem::from_expt_typed(&ExpressionType::Decimal(v, d), *self),
+                Option::None => Option::None
+            }
         }
     }
 }
@@ -572,6 +1004,13 @@ impl ForeignType {
             _ => {panic!("Wrong config value.");}
         }
     }
+    pub fn copy(&self) -> Self {
+        match *self {
+            ForeignType::Cascade => ForeignType::Cascade,
+            ForeignType::SetNull => ForeignType::SetNull,
+            ForeignType::Restrict => ForeignType::Restrict
+        }
+    }
 }
 
 //= 存储数据库文件内容的结构 ==============================
@@ -581,14 +1020,21 @@ impl ForeignType {
     页的划分存储在config文件中。
 */
 pub struct SaveFile{
-    file:File
+    file:File,
+    dirty:HashMap<usize, Vec<u8>> // 尚未提交的脏页。页号 -> 整页内容。
 }
 impl SaveFile {
     pub fn new(filepath:String) -> Self {
         Self {
-            file: OpenOptions::new().write(true).read(true).create(true).open(filepath.to_string()).unwrap()
+            file: OpenOptions::new().write(true).read(true).create(true).open(&filepath).unwrap(),
+            dirty: HashMap::new()
         }
     }
+    pub fn with_dirty(filepath:String, dirty:HashMap<usize, Vec<u8>>) -> Self {
+        let mut file = SaveFile::new(filepath);
+        file.dirty = dirty;
+        file
+    }
     fn get_start_seek(page:&[usize], newpage:usize, seek:usize, len:usize) -> Vec<(usize, usize, usize, usize)> {
         //返回值：(页号, 起始文件指针, 数据字节位置, io长度)
         let get_page = |p|{
@@ -621,71 +1067,266 @@ impl SaveFile {
         }
         v
     }
+    fn read_page(&mut self, page:usize) -> Vec<u8> {
+        //从文件中读取一整页。文件长度不足的部分以0补齐。
+        const PART_SIZE:usize = 64 * 16;
+        let mut t:Vec<u8> = Vec::with_capacity(PAGE_SIZE);
+        self.file.seek(SeekFrom::Start((page * PAGE_SIZE) as u64)).unwrap();
+        while t.len() < PAGE_SIZE {
+            let mut part:[u8; PART_SIZE] = [0_u8; PART_SIZE];
+            match self.file.read(&mut part) {
+                Result::Ok(0) => break,
+                Result::Ok(n) => {
+                    let n = if t.len() + n > PAGE_SIZE {PAGE_SIZE - t.len()}else{n};
+                    t.extend_from_slice(&part[..n]);
+                },
+                Result::Err(_) => break
+            }
+        }
+        t.resize(PAGE_SIZE, 0);
+        t
+    }
     pub fn write(&mut self, page:&[usize], newpage:usize, seek: usize, d:&Data) -> Option<usize> {
         //给出的页列表会按照顺序依次往下io。后一个页号需要作为新页的标记，从这个标记开始可以随意创建新页。
         //seek代表的不是文件指针的字节位置，而是在当前Data的长度下，记录的条目位置。
         //返回的Some是在创建了新页的情况下，最后一个页的页号。
-        //println!("WRITE");
+        //写入只会修改内存中的脏页，直到commit时才会经过预写日志落到文件上。
         let mut t = Vec::new();
         d.to_bytes(&mut t);
         let plist = SaveFile::get_start_seek(page, newpage, seek, d.len());
         let mut ret:Option<usize> = Option::None;
         for &(i, pb, db, l) in &plist {
-            self.file.seek(SeekFrom::Start(pb as u64)).unwrap();
-            //println!("i={}, pb in [{}, {}], db in [{}, {}], len={}", i, pb, pb+l, db, db+l, t.len());
-            self.file.write(&t[db..db + l]).unwrap();
+            if !self.dirty.contains_key(&i) {
+                let content = self.read_page(i);
+                self.dirty.insert(i, content);
+            }
+            let offset = pb - i * PAGE_SIZE;
+            let content = self.dirty.get_mut(&i).unwrap();
+            content[offset..offset + l].copy_from_slice(&t[db..db + l]);
             if i >= newpage {ret = Option::Some(i);}
         }
-        //self.file.write(&t[..]);
         ret
     }
     pub fn read(&mut self, page:&[usize], newpage:usize, seek: usize, d:&mut Data) -> Option<usize> {
         //给出的页列表会按照顺序依次往下io。后一个页号需要作为新页的标记，从这个标记开始可以随意创建新页。
         //seek代表的不是文件指针的字节位置，而是在当前Data的长度下，记录的条目位置。
         //返回的Some是在创建了新页的情况下，最后一个页的页号。
-        //println!("read[{}]", seek);
-        //println!("READ");
-        const PART_SIZE:usize = 64;
+        //脏页优先于文件内容。
+        //text/blob字段保存在溢出页中的内容随记录一起载入。
         let len = d.len();
+        let (t, ret) = self.read_bytes(page, newpage, seek, len);
+        Data::from_bytes(&t[0..len], d);
+        for i in d.li.iter_mut() {
+            match *i {
+                DataItem::Text(Option::Some(p), ref mut s) => {
+                    *s = String::from_utf8_lossy(&self.read_overflow(p)).into_owned();
+                },
+                DataItem::Blob(Option::Some(p), ref mut b) => {
+                    *b = self.read_overflow(p);
+                },
+                _ => {}
+            }
+        }
+        ret
+    }
+    pub fn read_legacy(&mut self, page:&[usize], newpage:usize, seek: usize, d:&mut Data) -> Option<usize> {
+        //按照没有空值位图的旧格式读取一条记录，仅用于升级旧版本的数据文件。
+        let len = d.plain_len();
+        let (t, ret) = self.read_bytes(page, newpage, seek, len);
+        Data::from_plain_bytes(&t[0..len], d);
+        ret
+    }
+    fn read_bytes(&mut self, page:&[usize], newpage:usize, seek: usize, len:usize) -> (Vec<u8>, Option<usize>) {
         let mut t:Vec<u8> = Vec::new();
         let plist = SaveFile::get_start_seek(page, newpage, seek, len);
         let mut ret:Option<usize> = Option::None;
         for &(i, pb, _, l) in &plist {
-            //println!("  plist[i={}, pb={}, db={}, l={}]", i, pb, db, l);
-            let mut part_t:Vec<u8> = Vec::new();
-            self.file.seek(SeekFrom::Start(pb as u64)).unwrap();
-            while part_t.len() < l {
-                let mut part:[u8; PART_SIZE] = [0_u8; PART_SIZE];
-                match self.file.read(&mut part) {
-                    Result::Ok(n) => {
-                        //println!("      read {}", n);
-                        part_t.extend_from_slice(&part[..n]);
-                        if n < PART_SIZE {break;}
-                    },
-                    Result::Err(_) => {
-                        //println!("      error occured.");
-                        break;
+            let offset = pb - i * PAGE_SIZE;
+            if let Option::Some(content) = self.dirty.get(&i) {
+                t.extend_from_slice(&content[offset..offset + l]);
+            }else{
+                let mut part_t:Vec<u8> = vec![0_u8; l];
+                self.file.seek(SeekFrom::Start(pb as u64)).unwrap();
+                let mut n = 0;
+                while n < l {
+                    match self.file.read(&mut part_t[n..]) {
+                        Result::Ok(0) => break,
+                        Result::Ok(m) => n += m,
+                        Result::Err(_) => break
                     }
                 }
+                t.extend_from_slice(&part_t);
             }
-            t.extend_from_slice(&part_t[..l]);
             if i >= newpage {ret = Option::Some(i);}
-            //println!("i={}, pb in [{}, {}], db in [{}, {}], len={}", i, pb, pb+l, db, db+l, part_t.len());
         }
-        Data::from_bytes(&t[0..len], d);
+        (t, ret)
+    }
+    fn page_content(&mut self, page:usize) -> Vec<u8> {
+        //取得一整页的当前内容，脏页优先。
+        if let Option::Some(content) = self.dirty.get(&page) {
+            return content.to_vec();
+        }
+        self.read_page(page)
+    }
+    pub fn overflow_count(len:usize) -> usize {
+        //存放len字节的内容需要的溢出页数。
+        (len + PAGE_SIZE - OVERFLOW_HEAD - 1) / (PAGE_SIZE - OVERFLOW_HEAD)
+    }
+    pub fn write_overflow(&mut self, pages:&[usize], content:&[u8]) {
+        //将内容依次写入给出的溢出页，页数由overflow_count得到。整页写入脏页，不需要读出原来的内容。
+        let cap = PAGE_SIZE - OVERFLOW_HEAD;
+        for (n, &i) in pages.iter().enumerate() {
+            let begin = n * cap;
+            let end = if begin + cap < content.len() {begin + cap}else{content.len()};
+            let next:u64 = if n + 1 < pages.len() {pages[n + 1] as u64 + 1}else{0};
+            let used:u64 = (end - begin) as u64;
+            let mut page = vec![0_u8; PAGE_SIZE];
+            let nw:[u8; 8] = next.to_ne_bytes();
+            page[0..8].copy_from_slice(&nw);
+            let nw:[u8; 8] = used.to_ne_bytes();
+            page[8..16].copy_from_slice(&nw);
+            page[OVERFLOW_HEAD..OVERFLOW_HEAD + end - begin].copy_from_slice(&content[begin..end]);
+            self.dirty.insert(i, page);
+        }
+    }
+    fn overflow_head(page:&[u8]) -> (Option<usize>, usize) {
+        let mut od = [0_u8; 8];
+        od.copy_from_slice(&page[0..8]);
+        let next:u64 = u64::from_ne_bytes(od);
+        od.copy_from_slice(&page[8..16]);
+        let used:u64 = u64::from_ne_bytes(od);
+        (if next == 0 {Option::None}else{Option::Some(next as usize - 1)}, used as usize)
+    }
+    pub fn write_shared(&mut self, page:usize, offset:usize, content:&[u8]) {
+        //将内容连同8字节的长度写入共享溢出页的offset处。页中的第一个值写入新分配的页，不需要读出原来的内容。
+        if !self.dirty.contains_key(&page) {
+            let old = if offset == OVERFLOW_HEAD {vec![0_u8; PAGE_SIZE]}else{self.read_page(page)};
+            self.dirty.insert(page, old);
+        }
+        let buf = self.dirty.get_mut(&page).unwrap();
+        let nw:[u8; 8] = (content.len() as u64).to_ne_bytes();
+        buf[offset..offset + 8].copy_from_slice(&nw);
+        buf[offset + 8..offset + 8 + content.len()].copy_from_slice(content);
+    }
+    fn read_range(&mut self, pos:usize, len:usize) -> Vec<u8> {
+        //读取一页之内从文件位置pos开始的len字节，脏页优先。
+        let page = pos / PAGE_SIZE;
+        let offset = pos - page * PAGE_SIZE;
+        if let Option::Some(content) = self.dirty.get(&page) {
+            return content[offset..offset + len].to_vec();
+        }
+        let mut t:Vec<u8> = vec![0_u8; len];
+        self.file.seek(SeekFrom::Start(pos as u64)).unwrap();
+        let mut n = 0;
+        while n < len {
+            match self.file.read(&mut t[n..]) {
+                Result::Ok(0) => break,
+                Result::Ok(m) => n += m,
+                Result::Err(_) => break
+            }
+        }
+        t
+    }
+    pub fn read_overflow(&mut self, addr:usize) -> Vec<u8> {
+        //按地址读出溢出页中的内容。地址是内容在文件中的位置：页内偏移为0时是一条溢出页链的首页，否则是共享溢出页中的一个值。
+        if !addr.is_multiple_of(PAGE_SIZE) {
+            let mut od = [0_u8; 8];
+            od.copy_from_slice(&self.read_range(addr, 8));
+            let len = u64::from_ne_bytes(od) as usize;
+            return self.read_range(addr + 8, len);
+        }
+        let mut ret = Vec::new();
+        let mut page = Option::Some(addr / PAGE_SIZE);
+        while let Option::Some(i) = page {
+            let content = self.page_content(i);
+            let (next, used) = SaveFile::overflow_head(&content);
+            ret.extend_from_slice(&content[OVERFLOW_HEAD..OVERFLOW_HEAD + used]);
+            page = next;
+        }
         ret
     }
+    pub fn overflow_pages(&mut self, addr:usize) -> Vec<usize> {
+        //取得一个值占用的溢出页：溢出页链上的全部页，或者它所在的共享溢出页。
+        if !addr.is_multiple_of(PAGE_SIZE) {return vec![addr / PAGE_SIZE];}
+        let mut ret = Vec::new();
+        let mut page = Option::Some(addr / PAGE_SIZE);
+        while let Option::Some(i) = page {
+            ret.push(i);
+            page = SaveFile::overflow_head(&self.page_content(i)).0;
+        }
+        ret
+    }
+    pub fn has_dirty(&self) -> bool {
+        !self.dirty.is_empty()
+    }
+    pub fn take_dirty(&mut self) -> Vec<(usize, Vec<u8>)> {
+        //取出所有的脏页，按页号排序。
+        let mut v:Vec<(usize, Vec<u8>)> = self.dirty.drain().collect();
+        v.sort_by_key(|a| a.0);
+        v
+    }
+    pub fn copy_dirty(&self) -> HashMap<usize, Vec<u8>> {
+        let mut ret = HashMap::new();
+        for (k, v) in self.dirty.iter() {ret.insert(*k, v.to_vec());}
+        ret
+    }
+    pub fn restore_dirty(&mut self, dirty:HashMap<usize, Vec<u8>>) {
+        //丢弃当前的脏页，恢复为copy_dirty时的状态。
+        self.dirty = dirty;
+    }
+    pub fn detach_dirty(&mut self) -> HashMap<usize, Vec<u8>> {
+        //取走全部脏页而不写入文件，用于在事务的语句之间保存修改。
+        take(&mut self.dirty)
+    }
+    pub fn write_pages(&mut self, pages:&[(usize, Vec<u8>)]) {
+        //将整页内容覆写到文件中，并等待落盘。
+        for &(i, ref content) in pages {
+            self.file.seek(SeekFrom::Start((i * PAGE_SIZE) as u64)).unwrap();
+            self.file.write_all(content).unwrap();
+        }
+        self.file.sync_all().unwrap();
+    }
 }
 
 //= 单条记录对象 ================================================
+fn to_hex(b:&[u8]) -> String {
+    b.iter().map(|i|format!("{:02x}", i)).collect()
+}
+fn from_hex(s:&str) -> Option<Vec<u8>> {
+    //blob以十六进制的字符串读写，每两个字符表示一个字节。
+    let s = s.trim();
+    if !s.len().is_multiple_of(2) || !s.is_ascii() {return Option::None;}
+    let mut ret = Vec::with_capacity(s.len() / 2);
+    for i in 0..s.len() / 2 {
+        match u8::from_str_radix(&s[i * 2..i * 2 + 2], 16) {
+            Result::Ok(v) => ret.push(v),
+            Result::Err(_) => {return Option::None;}
+        }
+    }
+    Option::Some(ret)
+}
 pub enum DataItem {
     Integer(i64),
     Float(f64),
     Str(usize, String),
-    Bool(bool)
+    Bool(bool),
+    Date(i32),  //自1970-01-01起的天数
+    Time(i32),  //自零点起的秒数
+    Timestamp(i64),  //自1970-01-01 00:00:00起的秒数
+    Text(Option<usize>, String),  //内容在溢出页中的地址与内容。内容保存在记录中或新的值尚未写入溢出页时地址为None
+    Blob(Option<usize>, Vec<u8>),
+    Decimal(usize, usize, i64),  //定点小数：总位数, 小数位数, 不带小数点的整数值
+    Null(FieldType)  //空值。保留字段类型，以便按字段的定长存储。
 }
 impl DataItem {
     pub fn cmp(&self, d:&DataItem) -> Result<Ordering, ()> {
+        //null小于任何值，两个null视作相等。
+        match (self.is_null(), d.is_null()) {
+            (true, true) => {return Result::Ok(Ordering::Equal);},
+            (true, false) => {return Result::Ok(Ordering::Less);},
+            (false, true) => {return Result::Ok(Ordering::Greater);},
+            _ => {}
+        }
         match self {
             &DataItem::Integer(i) => if let &DataItem::Integer(j) = d {Result::Ok(i.cmp(&j))}else{Result::Err(())},
             &DataItem::Float(i) => if let &DataItem::Float(j) = d {Result::Ok(
@@ -694,7 +1335,14 @@ impl DataItem {
             &DataItem::Str(_, ref i) => if let &DataItem::Str(_, ref j) = d {Result::Ok(i.cmp(j))}else{Result::Err(())},
             &DataItem::Bool(i) => if let &DataItem::Bool(j) = d {Result::Ok(
                 if (i && j)||(!i&&!j) {Ordering::Equal}else if i&&!j {Ordering::Less}else{Ordering::Greater}
-            )}else{Result::Err(())}
+            )}else{Result::Err(())},
+            &DataItem::Date(i) => if let &DataItem::Date(j) = d {Result::Ok(i.cmp(&j))}else{Result::Err(())},
+            &DataItem::Time(i) => if let &DataItem::Time(j) = d {Result::Ok(i.cmp(&j))}else{Result::Err(())},
+            &DataItem::Timestamp(i) => if let &DataItem::Timestamp(j) = d {Result::Ok(i.cmp(&j))}else{Result::Err(())},
+            DataItem::Text(_, i) => if let DataItem::Text(_, j) = d {Result::Ok(i.cmp(j))}else{Result::Err(())},
+            DataItem::Blob(_, i) => if let DataItem::Blob(_, j) = d {Result::Ok(i.cmp(j))}else{Result::Err(())},
+            &DataItem::Decimal(_, s, i) => if let &DataItem::Decimal(_, t, j) = d {Result::Ok(decimal::cmp((i, s), (j, t)))}else{Result::Err(())},
+            &DataItem::Null(_) => Result::Err(())
         }
     }
     pub fn eq(&self, d:&DataItem) -> bool {
@@ -702,7 +1350,32 @@ impl DataItem {
             &DataItem::Integer(i) => if let &DataItem::Integer(j) = d {i==j}else{false},
             &DataItem::Float(i) => if let &DataItem::Float(j) = d {i==j}else{false},
             &DataItem::Str(_ ,ref i) => if let &DataItem::Str(_, ref j) = d {i.to_string()==j.to_string()}else{false},
-            &DataItem::Bool(i) => if let &DataItem::Bool(j) = d {i==j}else{false}
+            &DataItem::Bool(i) => if let &DataItem::Bool(j) = d {i==j}else{false},
+            &DataItem::Date(i) => if let &DataItem::Date(j) = d {i==j}else{false},
+            &DataItem::Time(i) => if let &DataItem::Time(j) = d {i==j}else{false},
+            &DataItem::Timestamp(i) => if let &DataItem::Timestamp(j) = d {i==j}else{false},
+            DataItem::Text(_, i) => if let DataItem::Text(_, j) = d {i==j}else{false},
+            DataItem::Blob(_, i) => if let DataItem::Blob(_, j) = d {i==j}else{false},
+            &DataItem::Decimal(_, s, i) => if let &DataItem::Decimal(_, t, j) = d {decimal::cmp((i, s), (j, t)) == Ordering::Equal}else{false},
+            &DataItem::Null(_) => d.is_null()
+        }
+    }
+    pub fn is_null(&self) -> bool {
+        if let &DataItem::Null(_) = self {true}else{false}
+    }
+    pub fn get_type(&self) -> FieldType {
+        match self {
+            &DataItem::Integer(_) => FieldType::Integer,
+            &DataItem::Float(_) => FieldType::Float,
+            &DataItem::Str(u, _) => FieldType::Str(u),
+            &DataItem::Bool(_) => FieldType::Bool,
+            &DataItem::Date(_) => FieldType::Date,
+            &DataItem::Time(_) => FieldType::Time,
+            &DataItem::Timestamp(_) => FieldType::Timestamp,
+            &DataItem::Text(..) => FieldType::Text,
+            &DataItem::Blob(..) => FieldType::Blob,
+            &DataItem::Decimal(p, s, _) => FieldType::Decimal(p, s),
+            &DataItem::Null(t) => t
         }
     }
     fn to_bytes(&self, ret:&mut Vec<u8>) {
@@ -730,6 +1403,24 @@ impl DataItem {
             &DataItem::Bool(b) => {
                 let nw:[u8; 1] = unsafe{transmute(b)};
                 *ret = nw.to_vec();
+            },
+            &DataItem::Date(i) | &DataItem::Time(i) => {
+                let nw:[u8; 4] = i.to_ne_bytes();
+                *ret = nw.to_vec();
+            },
+            &DataItem::Timestamp(i) | &DataItem::Decimal(_, _, i) => {
+                let nw:[u8; 8] = i.to_ne_bytes();
+                *ret = nw.to_vec();
+            },
+            &DataItem::Text(p, ref s) => {
+                *ret = DataItem::overflow_to_bytes(p, s.as_bytes());
+            },
+            &DataItem::Blob(p, ref b) => {
+                *ret = DataItem::overflow_to_bytes(p, b);
+            },
+            &DataItem::Null(_) => {
+                //空值只在空值位图中标记，数据区填0占位。
+                *ret = vec![0_u8; self.len()];
             }
         };
     }
@@ -762,7 +1453,58 @@ impl DataItem {
                 let mut od = [0_u8; 1];
                 for i in 0..1 {od[i] = from[i];}
                 *b = unsafe{transmute(od)};
+            },
+            DataItem::Date(ref mut i) | DataItem::Time(ref mut i) => {
+                let mut od = [0_u8; 4];
+                for i in 0..4 { od[i] = from[i];}
+                *i = i32::from_ne_bytes(od);
+            },
+            DataItem::Timestamp(ref mut i) | DataItem::Decimal(_, _, ref mut i) => {
+                let mut od = [0_u8; 8];
+                for i in 0..8 { od[i] = from[i];}
+                *i = i64::from_ne_bytes(od);
+            },
+            DataItem::Text(ref mut p, ref mut s) => {
+                //溢出页中的内容由SaveFile在读取记录之后载入。
+                let (addr, inline) = DataItem::overflow_from_bytes(from);
+                *p = addr;
+                *s = String::from_utf8_lossy(&inline).into_owned();
+            },
+            DataItem::Blob(ref mut p, ref mut b) => {
+                let (addr, inline) = DataItem::overflow_from_bytes(from);
+                *p = addr;
+                *b = inline;
+            },
+            DataItem::Null(_) => {}
+        }
+    }
+    fn overflow_to_bytes(p:Option<usize>, content:&[u8]) -> Vec<u8> {
+        //text/blob在记录中占8字节的头和INLINE_SIZE字节的内容。
+        //头的最高位为1时，其余各位是内容的长度，内容直接保存在头之后；否则头是内容在溢出页中的地址+1，0表示没有内容。
+        //较长的内容要先由Database写入溢出页。
+        let mut ret = vec![0_u8; 8 + INLINE_SIZE];
+        let head:u64 = match p {
+            Option::Some(p) => p as u64 + 1,
+            Option::None if content.is_empty() || content.len() > INLINE_SIZE => 0,
+            Option::None => {
+                ret[8..8 + content.len()].copy_from_slice(content);
+                content.len() as u64 | 1 << 63
             }
+        };
+        ret[0..8].copy_from_slice(&head.to_ne_bytes());
+        ret
+    }
+    fn overflow_from_bytes(from:&[u8]) -> (Option<usize>, Vec<u8>) {
+        let mut od = [0_u8; 8];
+        od.copy_from_slice(&from[0..8]);
+        let head:u64 = u64::from_ne_bytes(od);
+        if head & 1 << 63 != 0 {
+            let len = (head & !(1 << 63)) as usize;
+            (Option::None, from[8..8 + len].to_vec())
+        }else if head == 0 {
+            (Option::None, vec![])
+        }else{
+            (Option::Some(head as usize - 1), vec![])
         }
     }
     pub fn len(&self) -> usize {
@@ -770,7 +1512,14 @@ impl DataItem {
             &DataItem::Integer(..) => 8,
             &DataItem::Float(..) => 8,
             &DataItem::Str(l, _) => l * 4,
-            &DataItem::Bool(..) => 1
+            &DataItem::Bool(..) => 1,
+            &DataItem::Date(..) => 4,
+            &DataItem::Time(..) => 4,
+            &DataItem::Timestamp(..) => 8,
+            &DataItem::Text(..) => 8 + INLINE_SIZE,
+            &DataItem::Blob(..) => 8 + INLINE_SIZE,
+            &DataItem::Decimal(..) => 8,
+            &DataItem::Null(t) => t.get_dataitem().len()
         }
     }
     pub fn copy(&self) -> Self {
@@ -778,10 +1527,24 @@ impl DataItem {
             &DataItem::Integer(i) => DataItem::Integer(i),
             &DataItem::Float(f) => DataItem::Float(f),
             &DataItem::Bool(b) => DataItem::Bool(b),
-            &DataItem::Str(u, ref s) => DataItem::Str(u, s.to_string())
+            &DataItem::Str(u, ref s) => DataItem::Str(u, s.to_string()),
+            &DataItem::Date(d) => DataItem::Date(d),
+            &DataItem::Time(t) => DataItem::Time(t),
+            &DataItem::Timestamp(t) => DataItem::Timestamp(t),
+            &DataItem::Text(p, ref s) => DataItem::Text(p, s.to_string()),
+            &DataItem::Blob(p, ref b) => DataItem::Blob(p, b.to_vec()),
+            &DataItem::Decimal(p, s, v) => DataItem::Decimal(p, s, v),
+            &DataItem::Null(t) => DataItem::Null(t)
         }
     }
-    pub fn from_dfa(d:&DfaWord, goal:&mut DataItem){
+    pub fn from_dfa(d:&DfaWord, goal:&mut DataItem) -> bool {
+        //日期与时间的字符串无法解析时返回false。
+        if let DfaWord::Kword(k) = d {
+            if k == "null" {
+                *goal = DataItem::Null(goal.get_type());
+                return true;
+            }
+        }
         match goal {
             &mut DataItem::Integer(ref mut i) => {
                 if let &DfaWord::Integer(value) = d {*i=value;}
@@ -789,20 +1552,60 @@ impl DataItem {
             &mut DataItem::Float(ref mut f) => {
                 if let &DfaWord::Float(value) = d {*f=value;}
             },
-            &mut DataItem::Str(_, ref mut s) => {
+            &mut DataItem::Str(_, ref mut s) | &mut DataItem::Text(_, ref mut s) => {
                 if let &DfaWord::Str(ref value) = d {*s=value.to_string();}
             },
+            //定点小数可以用整数、小数或字符串给出，按字段的小数位数四舍五入，超出总位数时同样返回false。
+            &mut DataItem::Decimal(..) => {
+                let value = match d {
+                    &DfaWord::Integer(i) => ExpressionType::Integer(i),
+                    &DfaWord::Float(f) => ExpressionType::Float(f),
+                    DfaWord::Str(s) => match decimal::parse(s) {
+                        Option::Some((v, s)) => ExpressionType::Decimal(v, s),
+                        Option::None => {return false;}
+                    },
+                    _ => {return false;}
+                };
+                match DataItem::from_expt_typed(&value, goal.get_type()) {
+                    Option::Some(v) => {*goal = v;},
+                    Option::None => {return false;}
+                }
+            },
+            //二进制数据以十六进制的字符串给出，如"0a1bff"。
+            &mut DataItem::Blob(_, ref mut b) => {
+                match if let DfaWord::Str(value) = d {from_hex(value)}else{Option::None} {
+                    Option::Some(v) => {*b = v;},
+                    Option::None => {return false;}
+                }
+            },
             &mut DataItem::Bool(ref mut b) => {
                 if let &DfaWord::Bool(value) = d {*b=value;}
-            }
+            },
+            //日期与时间以字符串的形式给出，如"2026-10-18"、"12:30:00"、"2026-10-18 12:30:00"。
+            &mut DataItem::Date(_) | &mut DataItem::Time(_) | &mut DataItem::Timestamp(_) => {
+                let parsed = if let DfaWord::Str(value) = d {goal.get_type().parse_value(value)}else{Option::None};
+                match parsed {
+                    Option::Some(v) => {*goal = v;},
+                    Option::None => {return false;}
+                }
+            },
+            &mut DataItem::Null(_) => {}
         }
+        true
     }
     pub fn to_expt(&self) -> ExpressionType {
         match self {
             &DataItem::Integer(i) => ExpressionType::Integer(i),
             &DataItem::Float(f) => ExpressionType::Float(f),
             &DataItem::Bool(b) => ExpressionType::Bool(b),
-            &DataItem::Str(_, ref s) => ExpressionType::Str(s.to_string())
+            DataItem::Str(_, s) => ExpressionType::Str(s.to_string()),
+            &DataItem::Date(d) => ExpressionType::Date(d),
+            &DataItem::Time(t) => ExpressionType::Time(t),
+            &DataItem::Timestamp(t) => ExpressionType::Timestamp(t),
+            DataItem::Text(_, s) => ExpressionType::Str(s.to_string()),
+            DataItem::Blob(_, b) => ExpressionType::Str(to_hex(b)),
+            &DataItem::Decimal(_, s, v) => ExpressionType::Decimal(v, s),
+            &DataItem::Null(_) => ExpressionType::Null
         }
     }
     pub fn from_expt(e:&ExpressionType) -> Self {
@@ -811,15 +1614,60 @@ impl DataItem {
             &ExpressionType::Float(f) => DataItem::Float(f),
             &ExpressionType::Bool(b) => DataItem::Bool(b),
             &ExpressionType::Str(ref s) => DataItem::Str(0, s.to_string()),
+            &ExpressionType::Date(d) => DataItem::Date(d),
+            &ExpressionType::Time(t) => DataItem::Time(t),
+            &ExpressionType::Timestamp(t) => DataItem::Timestamp(t),
+            &ExpressionType::Decimal(v, s) => DataItem::Decimal(decimal::MAX_PRECISION, s, v),
+            &ExpressionType::Null => DataItem::Null(FieldType::Str(0)),
             _ => {panic!("Wrong type.")}
         }
     }
+    pub fn from_expt_typed(e:&ExpressionType, t:FieldType) -> Option<Self> {
+        //把表达式的值转换为字段类型的数据。整数可以放宽为浮点数；类型不匹配时返回None。
+        match (t, e) {
+            (_, &ExpressionType::Null) => Option::Some(DataItem::Null(t)),
+            (FieldType::Integer, &ExpressionType::Integer(i)) => Option::Some(DataItem::Integer(i)),
+            (FieldType::Float, &ExpressionType::Integer(i)) => Option::Some(DataItem::Float(i as f64)),
+            (FieldType::Float, &ExpressionType::Float(f)) => Option::Some(DataItem::Float(f)),
+            (FieldType::Bool, &ExpressionType::Bool(b)) => Option::Some(DataItem::Bool(b)),
+            (FieldType::Str(u), ExpressionType::Str(s)) => Option::Some(DataItem::Str(u, s.to_string())),
+            (FieldType::Text, ExpressionType::Str(s)) => Option::Some(DataItem::Text(Option::None, s.to_string())),
+            (FieldType::Blob, ExpressionType::Str(s)) => from_hex(s).map(|b|DataItem::Blob(Option::None, b)),
+            //定点小数按字段的小数位数四舍五入，超出总位数时返回None。浮点数按字面的值转换。
+            (FieldType::Decimal(p, d), &ExpressionType::Decimal(v, s)) => match decimal::rescale(v, s, d) {
+                Option::Some(v) if decimal::fits(v, p) => Option::Some(DataItem::Decimal(p, d, v)),
+                _ => Option::None
+            },
+            (FieldType::Decimal(..), &ExpressionType::Integer(i)) => DataItem::from_expt_typed(&ExpressionType::Decimal(i, 0), t),
+            (FieldType::Decimal(..), &ExpressionType::Float(f)) => match decimal::parse(&f.to_string()) {
+                Option::Some((v, s)) => DataItem::from_expt_typed(&ExpressionType::Decimal(v, s), t),
+                Option::None => Option::None
+            },
+            (FieldType::Float, &ExpressionType::Decimal(v, s)) => Option::Some(DataItem::Float(decimal::format(v, s).parse().unwrap())),
+            (FieldType::Date, &ExpressionType::Date(d)) => Option::Some(DataItem::Date(d)),
+            (FieldType::Time, &ExpressionType::Time(d)) => Option::Some(DataItem::Time(d)),
+            (FieldType::Timestamp, &ExpressionType::Timestamp(d)) => Option::Some(DataItem::Timestamp(d)),
+            (FieldType::Timestamp, &ExpressionType::Date(d)) => Option::Some(DataItem::Timestamp(d as i64 * datetime::DAY_SECONDS)),
+            //字符串按日期与时间的格式解析。
+            (FieldType::Date, &ExpressionType::Str(ref s)) |
+            (FieldType::Time, &ExpressionType::Str(ref s)) |
+            (FieldType::Timestamp, &ExpressionType::Str(ref s)) => t.parse_value(s),
+            _ => Option::None
+        }
+    }
     pub fn to_string(&self) -> String {
         match self {
             &DataItem::Integer(i) => i.to_string(),
             &DataItem::Float(f) => f.to_string(),
             &DataItem::Str(_, ref s) => s.to_string(),
-            &DataItem::Bool(b) => b.to_string()
+            &DataItem::Bool(b) => b.to_string(),
+            &DataItem::Date(d) => datetime::format_date(d),
+            &DataItem::Time(t) => datetime::format_time(t),
+            &DataItem::Timestamp(t) => datetime::format_timestamp(t),
+            DataItem::Text(_, s) => s.to_string(),
+            DataItem::Blob(_, b) => to_hex(b),
+            &DataItem::Decimal(_, s, v) => decimal::format(v, s),
+            &DataItem::Null(_) => "null".to_string()
         }
     }
 }
@@ -831,7 +1679,13 @@ impl Data {
         Self {li: li}
     }
     pub fn len(&self) -> usize {
-        //取得一条数据记录的定长。
+        //取得一条数据记录的定长。记录的开头是空值位图，每个字段占一位。
+        self.bitmap_len() + self.plain_len()
+    }
+    fn bitmap_len(&self) -> usize {
+        self.li.len().div_ceil(8)
+    }
+    fn plain_len(&self) -> usize {
         let mut ret:usize = 0;
         for i in &self.li {
             ret += i.len()
@@ -840,6 +1694,11 @@ impl Data {
     }
     pub fn to_bytes(&self, ret:&mut Vec<u8>) {
         ret.clear();
+        let mut bitmap = vec![0_u8; self.bitmap_len()];
+        for (index, i) in self.li.iter().enumerate() {
+            if i.is_null() {bitmap[index / 8] |= 1 << (index % 8);}
+        }
+        ret.extend_from_slice(&bitmap[..]);
         for i in &self.li {
             let mut part:Vec<u8> = Vec::new();
             //print!("[{}]", i.to_string());
@@ -849,16 +1708,32 @@ impl Data {
         }
     }
     pub fn from_bytes(from:&[u8], goal:&mut Data) {
-        let mut index = 0;
-        for i in &mut goal.li {
+        let mut index = goal.bitmap_len();
+        for (n, i) in goal.li.iter_mut().enumerate() {
+            //模板会被反复使用，上一条记录留下的空值需要先还原成字段类型。
+            if i.is_null() {*i = i.get_type().get_dataitem();}
             let l = i.len();
             let v = &from[index..index+l];
             //print!("[");for ii in v.iter() {print!("{}", ii);}print!("]");
             index += l;
-            DataItem::from_bytes(v, i);
+            if from[n / 8] & (1 << (n % 8)) != 0 {
+                *i = DataItem::Null(i.get_type());
+            }else{
+                DataItem::from_bytes(v, i);
+            }
             //print!("[{}]", i.to_string());
         }
     }
+    fn from_plain_bytes(from:&[u8], goal:&mut Data) {
+        //旧格式：没有空值位图，字段依次排列。
+        let mut index = 0;
+        for i in &mut goal.li {
+            if i.is_null() {*i = i.get_type().get_dataitem();}
+            let l = i.len();
+            DataItem::from_bytes(&from[index..index+l], i);
+            index += l;
+        }
+    }
     pub fn copy(&self) -> Self {
         let mut li = Vec::with_capacity(self.li.len());
         for i in self.li.iter(){li.push(i.copy());}
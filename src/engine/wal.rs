@@ -0,0 +1,121 @@
+use std::fs::{OpenOptions, remove_file};
+use std::io::{Read, Write, Seek, SeekFrom};
+use std::path::Path;
+use super::save::PAGE_SIZE;
+
+const WAL_MAGIC:&[u8] = b"DBAWAL01";
+const WAL_COMMIT:&[u8] = b"COMMITED";
+
+//= 预写日志 ==============================================
+/*
+    每个数据库在.db与.dba文件旁边有一个.wal文件。
+    提交时的流程：
+        1. 把本次所有的脏页与新的配置JSON写入日志，并等待日志落盘；
+        2. 将脏页覆写到.db文件中，将配置覆写到.dba文件中；
+        3. 删除日志。
+    日志格式：
+        WAL_MAGIC | 页数(u64) | [页号(u64) | 页内容(PAGE_SIZE)]* | 配置长度(u64) | 配置 | 校验和(u64) | WAL_COMMIT
+    启动时如果发现完整的日志，说明上一次提交在第2步中断了，重放日志即可；
+    如果日志不完整，说明上一次提交在第1步中断了，.db与.dba都还没有被改动，丢弃日志即可。
+*/
+pub struct WriteAheadLog {
+    filepath:String
+}
+impl WriteAheadLog {
+    pub fn new(filepath:String) -> Self {
+        WriteAheadLog{filepath}
+    }
+    pub fn write(&self, pages:&[(usize, Vec<u8>)], config:&str) {
+        let mut body:Vec<u8> = Vec::with_capacity(pages.len() * (PAGE_SIZE + 8) + config.len() + 16);
+        body.extend_from_slice(&(pages.len() as u64).to_le_bytes());
+        for &(i, ref content) in pages {
+            body.extend_from_slice(&(i as u64).to_le_bytes());
+            body.extend_from_slice(content);
+        }
+        body.extend_from_slice(&(config.len() as u64).to_le_bytes());
+        body.extend_from_slice(config.as_bytes());
+
+        let mut f = OpenOptions::new().create(true).truncate(true).write(true).open(&self.filepath).unwrap();
+        f.write_all(WAL_MAGIC).unwrap();
+        f.write_all(&body).unwrap();
+        f.write_all(&checksum(&body).to_le_bytes()).unwrap();
+        f.write_all(WAL_COMMIT).unwrap();
+        f.sync_all().unwrap();
+    }
+    pub fn clear(&self) {
+        if Path::new(&self.filepath).exists() {
+            remove_file(&self.filepath).unwrap();
+        }
+    }
+    pub fn recover(&self, db_path:&str, dba_path:&str) -> bool {
+        //如果存在完整的日志，将其重放到.db与.dba上。返回是否进行了重放。
+        //不完整的日志会被直接丢弃。
+        if !Path::new(&self.filepath).exists() {return false;}
+        let replayed = match self.read() {
+            Option::Some((pages, config)) => {
+                let mut fs = OpenOptions::new().create(true).write(true).open(db_path).unwrap();
+                for &(i, ref content) in &pages {
+                    fs.seek(SeekFrom::Start((i * PAGE_SIZE) as u64)).unwrap();
+                    fs.write_all(content).unwrap();
+                }
+                fs.sync_all().unwrap();
+                let mut f = OpenOptions::new().create(true).truncate(true).write(true).open(dba_path).unwrap();
+                f.write_all(config.as_bytes()).unwrap();
+                f.sync_all().unwrap();
+                true
+            },
+            Option::None => false
+        };
+        self.clear();
+        replayed
+    }
+    fn read(&self) -> Option<(Vec<(usize, Vec<u8>)>, String)> {
+        let mut t:Vec<u8> = Vec::new();
+        match OpenOptions::new().read(true).open(&self.filepath) {
+            Result::Ok(mut f) => {if f.read_to_end(&mut t).is_err() {return Option::None;}},
+            Result::Err(_) => return Option::None
+        }
+        let head = WAL_MAGIC.len();
+        let tail = 8 + WAL_COMMIT.len();
+        if t.len() < head + tail || &t[..head] != WAL_MAGIC || &t[t.len() - WAL_COMMIT.len()..] != WAL_COMMIT {
+            return Option::None;
+        }
+        let body = &t[head..t.len() - tail];
+        if read_u64(&t, t.len() - tail) != Option::Some(checksum(body)) {return Option::None;}
+
+        let mut p = 0;
+        let count = read_u64(body, p)? as usize;
+        p += 8;
+        let mut pages:Vec<(usize, Vec<u8>)> = Vec::with_capacity(count);
+        for _ in 0..count {
+            let i = read_u64(body, p)? as usize;
+            p += 8;
+            if p + PAGE_SIZE > body.len() {return Option::None;}
+            pages.push((i, body[p..p + PAGE_SIZE].to_vec()));
+            p += PAGE_SIZE;
+        }
+        let len = read_u64(body, p)? as usize;
+        p += 8;
+        if p + len != body.len() {return Option::None;}
+        match String::from_utf8(body[p..].to_vec()) {
+            Result::Ok(config) => Option::Some((pages, config)),
+            Result::Err(_) => Option::None
+        }
+    }
+}
+
+fn read_u64(t:&[u8], p:usize) -> Option<u64> {
+    if p + 8 > t.len() {return Option::None;}
+    let mut b = [0_u8; 8];
+    b.copy_from_slice(&t[p..p + 8]);
+    Option::Some(u64::from_le_bytes(b))
+}
+fn checksum(t:&[u8]) -> u64 {
+    //FNV-1a
+    let mut h:u64 = 0xcbf29ce484222325;
+    for b in t {
+        h ^= *b as u64;
+        h = h.wrapping_mul(0x100000001b3);
+    }
+    h
+}
@@ -1,3 +1,4 @@
 pub mod analyse;
 pub mod syntax;
-pub mod engine;
\ No newline at end of file
+pub mod engine;
+pub mod net;
\ No newline at end of file
@@ -52,12 +52,18 @@ fn get_user_runtime(user:&mut String, pw:&mut String) {
     }
 } 
 fn main() {
-    let mut engine = Engine::new();
+    let stdout = io::stdout();
+    let engine = match Engine::new() {
+        Result::Ok(ok) => ok,
+        Result::Err(e) => {
+            stdout.lock().write(format!("{}", e).as_bytes()).unwrap();
+            return;
+        }
+    };
     let (mut user, mut password, db_name) = get_env();
     if user == "" || password == "" {
         get_user_runtime(&mut user, &mut password);
     }
-    let stdout = io::stdout();
     let stdin = io::stdin();
     let mut session = match engine.session(&user, &password) {
         Result::Ok(ok) => ok,
@@ -69,6 +75,9 @@ fn main() {
     if db_name != "" {
         session.use_database(&UseSyntax::new(&db_name));
     }
+    if session.must_change_password() {
+        stdout.lock().write(format!("The password of {} must be changed before running other statements.", session.get_username()).as_bytes()).unwrap();
+    }
     loop {
         {
             let mut handle = stdout.lock();
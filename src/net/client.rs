@@ -0,0 +1,77 @@
+use std::io::{BufReader};
+use std::net::{TcpStream};
+use super::protocol::{Request, Response, read_frame, write_frame};
+use super::super::engine::database::{DResult};
+// 连接到数据库服务器的客户端
+
+pub struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>,
+    user: String,
+    using: Option<String>
+}
+impl Client {
+    pub fn connect(addr:&str, user:&str, password:&str, database:Option<&str>) -> Result<Self, String> {
+        //连接并登录。登录失败时返回服务器给出的错误。
+        let stream = match TcpStream::connect(addr) {
+            Result::Ok(s) => s,
+            Result::Err(e) => {return Result::Err(format!("Cannot connect to {}: {}.", addr, e));}
+        };
+        let reader = match stream.try_clone() {
+            Result::Ok(s) => BufReader::new(s),
+            Result::Err(e) => {return Result::Err(format!("Cannot connect to {}: {}.", addr, e));}
+        };
+        let mut client = Self {
+            stream,
+            reader,
+            user: user.to_string(),
+            using: Option::None
+        };
+        let login = Request::Login{
+            user: user.to_string(),
+            password: password.to_string(),
+            database: database.map(|s|s.to_string())
+        };
+        match client.request(&login) {
+            Result::Ok(Response::Login{user, using}) => {
+                client.user = user;
+                client.using = using;
+                Result::Ok(client)
+            },
+            Result::Ok(Response::Error(e)) => Result::Err(e),
+            Result::Ok(_) => Result::Err("Wrong response.".to_string()),
+            Result::Err(e) => Result::Err(e)
+        }
+    }
+    fn request(&mut self, request:&Request) -> Result<Response, String> {
+        if let Result::Err(e) = write_frame(&mut self.stream, &request.to_json()) {
+            return Result::Err(format!("Connection error: {}.", e));
+        }
+        match read_frame(&mut self.reader) {
+            Result::Ok(Option::Some(frame)) => Response::from_json(&frame),
+            Result::Ok(Option::None) => Result::Err("Connection closed by server.".to_string()),
+            Result::Err(e) => Result::Err(format!("Connection error: {}.", e))
+        }
+    }
+    pub fn execute(&mut self, sql:&str) -> Result<DResult, String> {
+        //执行一条语句。语句的执行结果(包括执行失败的信息)在DResult中，语法错误与连接错误在Err中。
+        match self.request(&Request::Execute(sql.to_string())) {
+            Result::Ok(Response::Result(result, using)) => {
+                self.using = using;
+                Result::Ok(result)
+            },
+            Result::Ok(Response::Error(e)) => Result::Err(e),
+            Result::Ok(_) => Result::Err("Wrong response.".to_string()),
+            Result::Err(e) => Result::Err(e)
+        }
+    }
+    pub fn close(mut self) {
+        self.request(&Request::Quit).ok();
+    }
+    pub fn get_username(&self) -> String {
+        self.user.to_string()
+    }
+    pub fn get_using_database(&self) -> Option<String> {
+        self.using.as_ref().map(|s| s.to_string())
+    }
+}
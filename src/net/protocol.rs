@@ -0,0 +1,273 @@
+extern crate serde_json;
+use std::io::{self, BufRead, Read, Write};
+use self::serde_json::{Value, Map};
+use super::super::engine::database::{DResult, DTable, MultiResult};
+use super::super::engine::save::{Data, DataItem, FieldType};
+// 服务器与客户端之间的通信协议
+// 注意：协议没有加密，登录请求中的密码以明文传输。服务器默认只监听127.0.0.1，
+// 需要从其他机器访问时应当通过ssh隧道等加密的通道转发，不要直接监听公开的地址。
+
+/*
+    每一帧是一行UTF-8编码的JSON，以\n结尾，不超过MAX_FRAME字节。JSON的字符串中的换行会被转义，所以一行就是一帧。
+    客户端发出的请求：
+        {"op": "login", "user": "root", "password": "root", "database": "d"}  database可以省略，不能使用时登录失败
+        {"op": "execute", "sql": "select * from t;"}
+        {"op": "quit"}
+    服务器的回应，除error与bye之外都带有当前使用的数据库"using"(没有时为null)：
+        {"type": "login", "user": "root", "using": null}
+        {"type": "none", "using": ...}
+        {"type": "string", "message": "...", "using": ...}
+        {"type": "table", "head": ["id", ...], "rows": [[单元格, ...], ...], "using": ...}
+        {"type": "multi", "results": [string或table的帧, ...], "using": ...}
+        {"type": "error", "message": "..."}  请求无法解析、登录失败、语句的词法或语法错误
+        {"type": "bye"}
+    单元格是[类型, 值]：类型与保存字段类型的写法相同(如"integer"、"str:20"、"decimal:10:2")，
+    值是该类型的字符串形式，null为JSON的null。
+*/
+pub const MAX_FRAME:usize = 64 * 1024 * 1024;  //一帧的最大字节数，包括结尾的\n
+
+pub enum Request {
+    Login{user:String, password:String, database:Option<String>},
+    Execute(String),
+    Quit
+}
+pub enum Response {
+    Login{user:String, using:Option<String>},
+    Result(DResult, Option<String>),
+    Error(String),
+    Bye
+}
+
+pub fn write_frame<W: Write>(out:&mut W, frame:&str) -> io::Result<()> {
+    let mut buf = frame.as_bytes().to_vec();
+    buf.push(b'\n');
+    match out.write_all(&buf) {
+        Result::Ok(_) => out.flush(),
+        e@Result::Err(_) => e
+    }
+}
+pub fn read_frame<R: BufRead>(input:&mut R) -> io::Result<Option<String>> {
+    //对方关闭连接时返回None。一帧最多读MAX_FRAME字节，超出时返回错误，不会无限制地占用内存。
+    let mut line = String::new();
+    match input.by_ref().take(MAX_FRAME as u64 + 1).read_line(&mut line) {
+        Result::Ok(0) => Result::Ok(Option::None),
+        Result::Ok(n) if n > MAX_FRAME => Result::Err(io::Error::new(io::ErrorKind::InvalidData, format!("Frame is larger than {} bytes", MAX_FRAME))),
+        Result::Ok(_) => Result::Ok(Option::Some(line.trim_end_matches(['\n', '\r']).to_string())),
+        Result::Err(e) => Result::Err(e)
+    }
+}
+
+fn get_str(map:&Map<String, Value>, key:&str) -> Option<String> {
+    if let Option::Some(Value::String(s)) = map.get(key) {Option::Some(s.to_string())}else{Option::None}
+}
+fn parse_object(s:&str) -> Result<Map<String, Value>, String> {
+    match serde_json::from_str(s) {
+        Result::Ok(Value::Object(map)) => Result::Ok(map),
+        _ => Result::Err("Wrong frame.".to_string())
+    }
+}
+fn using_to_json(using:&Option<String>) -> Value {
+    match using {
+        Option::Some(s) => Value::String(s.to_string()),
+        &Option::None => Value::Null
+    }
+}
+
+impl Request {
+    pub fn to_json(&self) -> String {
+        let mut map = Map::new();
+        match self {
+            Request::Login{user, password, database} => {
+                map.insert("op".to_string(), Value::String("login".to_string()));
+                map.insert("user".to_string(), Value::String(user.to_string()));
+                map.insert("password".to_string(), Value::String(password.to_string()));
+                map.insert("database".to_string(), using_to_json(database));
+            },
+            Request::Execute(sql) => {
+                map.insert("op".to_string(), Value::String("execute".to_string()));
+                map.insert("sql".to_string(), Value::String(sql.to_string()));
+            },
+            &Request::Quit => {
+                map.insert("op".to_string(), Value::String("quit".to_string()));
+            }
+        }
+        serde_json::to_string(&map).unwrap()
+    }
+    pub fn from_json(s:&str) -> Result<Self, String> {
+        let map = match parse_object(s) {
+            Result::Ok(map) => map,
+            Result::Err(e) => {return Result::Err(e);}
+        };
+        match get_str(&map, "op").as_deref() {
+            Option::Some("login") => match (get_str(&map, "user"), get_str(&map, "password")) {
+                (Option::Some(user), Option::Some(password)) => Result::Ok(Request::Login{
+                    user,
+                    password,
+                    database: get_str(&map, "database")
+                }),
+                _ => Result::Err("Login needs user and password.".to_string())
+            },
+            Option::Some("execute") => match get_str(&map, "sql") {
+                Option::Some(sql) => Result::Ok(Request::Execute(sql)),
+                Option::None => Result::Err("Execute needs sql.".to_string())
+            },
+            Option::Some("quit") => Result::Ok(Request::Quit),
+            Option::Some(op) => Result::Err(format!("Unknown op: {}.", op)),
+            Option::None => Result::Err("Wrong frame.".to_string())
+        }
+    }
+}
+
+impl Response {
+    pub fn to_json(&self) -> String {
+        let map = match self {
+            Response::Login{user, using} => {
+                let mut map = Map::new();
+                map.insert("type".to_string(), Value::String("login".to_string()));
+                map.insert("user".to_string(), Value::String(user.to_string()));
+                map.insert("using".to_string(), using_to_json(using));
+                map
+            },
+            Response::Result(result, using) => {
+                let mut map = Response::result_to_json(result);
+                map.insert("using".to_string(), using_to_json(using));
+                map
+            },
+            Response::Error(e) => {
+                let mut map = Map::new();
+                map.insert("type".to_string(), Value::String("error".to_string()));
+                map.insert("message".to_string(), Value::String(e.to_string()));
+                map
+            },
+            &Response::Bye => {
+                let mut map = Map::new();
+                map.insert("type".to_string(), Value::String("bye".to_string()));
+                map
+            }
+        };
+        serde_json::to_string(&map).unwrap()
+    }
+    pub fn from_json(s:&str) -> Result<Self, String> {
+        let map = match parse_object(s) {
+            Result::Ok(map) => map,
+            Result::Err(e) => {return Result::Err(e);}
+        };
+        let using = get_str(&map, "using");
+        match get_str(&map, "type").as_deref() {
+            Option::Some("login") => Result::Ok(Response::Login{
+                user: get_str(&map, "user").unwrap_or_default(),
+                using
+            }),
+            Option::Some("error") => Result::Ok(Response::Error(get_str(&map, "message").unwrap_or_default())),
+            Option::Some("bye") => Result::Ok(Response::Bye),
+            Option::Some("multi") => {
+                let mut results = Vec::new();
+                if let Option::Some(Value::Array(arr)) = map.get("results") {
+                    for i in arr.iter() {
+                        match i {
+                            Value::Object(m) => match Response::result_from_json(m) {
+                                Result::Ok(DResult::String(s)) => results.push(MultiResult::String(s)),
+                                Result::Ok(DResult::Table(dt)) => results.push(MultiResult::Table(dt)),
+                                Result::Ok(_) => {return Result::Err("Wrong frame.".to_string());},
+                                Result::Err(e) => {return Result::Err(e);}
+                            },
+                            _ => {return Result::Err("Wrong frame.".to_string());}
+                        }
+                    }
+                }
+                Result::Ok(Response::Result(DResult::Multi(results), using))
+            },
+            _ => match Response::result_from_json(&map) {
+                Result::Ok(result) => Result::Ok(Response::Result(result, using)),
+                Result::Err(e) => Result::Err(e)
+            }
+        }
+    }
+    fn result_to_json(result:&DResult) -> Map<String, Value> {
+        let mut map = Map::new();
+        match result {
+            &DResult::None => {
+                map.insert("type".to_string(), Value::String("none".to_string()));
+            },
+            DResult::String(s) => {
+                map.insert("type".to_string(), Value::String("string".to_string()));
+                map.insert("message".to_string(), Value::String(s.to_string()));
+            },
+            DResult::Table(dt) => {
+                map = Response::table_to_json(dt);
+            },
+            DResult::Multi(v) => {
+                map.insert("type".to_string(), Value::String("multi".to_string()));
+                map.insert("results".to_string(), Value::Array(v.iter().map(|i|Value::Object(match i {
+                    MultiResult::String(s) => Response::result_to_json(&DResult::String(s.to_string())),
+                    MultiResult::Table(dt) => Response::table_to_json(dt)
+                })).collect()));
+            }
+        }
+        map
+    }
+    fn table_to_json(dt:&DTable) -> Map<String, Value> {
+        let mut map = Map::new();
+        map.insert("type".to_string(), Value::String("table".to_string()));
+        map.insert("head".to_string(), Value::Array(dt.head.iter().map(|h|Value::String(h.to_string())).collect()));
+        let mut rows = Vec::new();
+        for row in dt.content.iter() {
+            rows.push(Value::Array(row.li.iter().map(|d|Value::Array(vec![
+                Value::String(d.get_type().to_string()),
+                if d.is_null() {Value::Null}else{Value::String(d.to_string())}
+            ])).collect()));
+        }
+        map.insert("rows".to_string(), Value::Array(rows));
+        map
+    }
+    fn result_from_json(map:&Map<String, Value>) -> Result<DResult, String> {
+        match get_str(map, "type").as_deref() {
+            Option::Some("none") => Result::Ok(DResult::None),
+            Option::Some("string") => Result::Ok(DResult::String(get_str(map, "message").unwrap_or_default())),
+            Option::Some("table") => {
+                let mut head = Vec::new();
+                if let Option::Some(Value::Array(arr)) = map.get("head") {
+                    for h in arr.iter() {
+                        if let Value::String(s) = h {head.push(s.to_string());}
+                    }
+                }
+                let mut content = Vec::new();
+                if let Option::Some(Value::Array(rows)) = map.get("rows") {
+                    for row in rows.iter() {
+                        let mut li = Vec::new();
+                        if let Value::Array(cells) = row {
+                            for cell in cells.iter() {
+                                match Response::cell_from_json(cell) {
+                                    Option::Some(d) => li.push(d),
+                                    Option::None => {return Result::Err(format!("Wrong value in frame: {}.", cell));}
+                                }
+                            }
+                        }
+                        content.push(Data::new(li));
+                    }
+                }
+                Result::Ok(DResult::Table(DTable{head, content}))
+            },
+            _ => Result::Err("Wrong frame.".to_string())
+        }
+    }
+    fn cell_from_json(cell:&Value) -> Option<DataItem> {
+        if let Value::Array(pair) = cell {
+            if pair.len() == 2 {
+                let t = match pair[0] {
+                    Value::String(ref t) => match FieldType::parse(t) {
+                        Option::Some(t) => t,
+                        Option::None => {return Option::None;}
+                    },
+                    _ => {return Option::None;}
+                };
+                return match pair[1] {
+                    Value::Null => Option::Some(DataItem::Null(t)),
+                    Value::String(ref v) => t.parse_value(v),
+                    _ => Option::None
+                };
+            }
+        }
+        Option::None
+    }
+}
@@ -0,0 +1,128 @@
+use std::io::{BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc};
+use std::thread;
+use super::protocol::{Request, Response, read_frame, write_frame};
+use super::super::analyse::dfa::{FiniteAutomaton};
+use super::super::syntax::toptree::{PublicTree};
+use super::super::syntax::structures::{ColSyntax, UseSyntax};
+use super::super::engine::engine::{Engine};
+use super::super::engine::database::{DResult};
+// 通过tcp提供数据库服务
+
+pub fn parse_statement(sql:&str) -> Result<ColSyntax, String> {
+    //把一条语句解析为语法树。词法或语法错误时返回错误信息。
+    let mut fa = FiniteAutomaton::new(sql.to_string());
+    let dfawords = fa.construct();
+    if let Option::Some(ref s) = fa.get_error_string() {
+        return Result::Err(format!("ERROR: {}", s));
+    }
+    let mut tree = PublicTree::new();
+    let result = tree.construct(&dfawords[..]);
+    if let Option::Some(ref s) = tree.get_error_string() {
+        return Result::Err(format!("ERROR: {}", s));
+    }
+    Result::Ok(result)
+}
+
+pub fn serve(engine:Arc<Engine>, listener:&TcpListener) {
+    //每个连接在单独的线程中拥有一个会话，会话之间通过数据库的锁协调。
+    for stream in listener.incoming() {
+        match stream {
+            Result::Ok(stream) => {
+                let engine = engine.clone();
+                thread::spawn(move || {
+                    let peer = match stream.peer_addr() {
+                        Result::Ok(addr) => addr.to_string(),
+                        Result::Err(_) => "unknown".to_string()
+                    };
+                    println!("Connection from {}.", peer);
+                    handle(&engine, stream);
+                    println!("Connection from {} closed.", peer);
+                });
+            },
+            Result::Err(e) => {println!("Accept failed: {}.", e);}
+        }
+    }
+}
+
+fn send(stream:&mut TcpStream, response:&Response) -> bool {
+    write_frame(stream, &response.to_json()).is_ok()
+}
+
+fn handle(engine:&Engine, mut stream:TcpStream) {
+    let mut reader = match stream.try_clone() {
+        Result::Ok(s) => BufReader::new(s),
+        Result::Err(_) => {return;}
+    };
+    //第一帧必须是登录请求。
+    let (user, password, database) = match read_frame(&mut reader) {
+        Result::Ok(Option::Some(frame)) => match Request::from_json(&frame) {
+            Result::Ok(Request::Login{user, password, database}) => (user, password, database),
+            Result::Ok(_) => {
+                send(&mut stream, &Response::Error("Please login first.".to_string()));
+                return;
+            },
+            Result::Err(e) => {
+                send(&mut stream, &Response::Error(e));
+                return;
+            }
+        },
+        Result::Ok(Option::None) => {return;},
+        Result::Err(e) => {
+            send(&mut stream, &Response::Error(e.to_string()));
+            return;
+        }
+    };
+    let mut session = match engine.session(&user, &password) {
+        Result::Ok(ok) => ok,
+        Result::Err(e) => {
+            send(&mut stream, &Response::Error(e));
+            return;
+        }
+    };
+    if let Option::Some(ref db) = database {
+        //指定的数据库不能使用时拒绝登录，不能让客户端以为已经连到了这个数据库。
+        let result = session.use_database(&UseSyntax::new(db));
+        if session.get_using_database().as_ref() != Option::Some(db) {
+            let message = match result {
+                DResult::String(s) => s,
+                _ => format!("Cannot use database {}.", db)
+            };
+            send(&mut stream, &Response::Error(message));
+            return;
+        }
+    }
+    if !send(&mut stream, &Response::Login{user: session.get_username(), using: session.get_using_database()}) {
+        return;
+    }
+    loop {
+        let frame = match read_frame(&mut reader) {
+            Result::Ok(Option::Some(frame)) => frame,
+            Result::Ok(Option::None) => {break;},
+            Result::Err(e) => {
+                //帧过长或无法读取时报告错误后断开，剩下的内容已经无法按帧解析。
+                send(&mut stream, &Response::Error(e.to_string()));
+                break;
+            }
+        };
+        let response = match Request::from_json(&frame) {
+            Result::Ok(Request::Execute(sql)) => match parse_statement(&sql) {
+                Result::Ok(syntax) => {
+                    let result = session.execute(&syntax);
+                    Response::Result(result, session.get_using_database())
+                },
+                Result::Err(e) => Response::Error(e)
+            },
+            Result::Ok(Request::Quit) => {
+                send(&mut stream, &Response::Bye);
+                break;
+            },
+            Result::Ok(Request::Login{..}) => Response::Error("Already logged in.".to_string()),
+            Result::Err(e) => Response::Error(e)
+        };
+        if !send(&mut stream, &response) {
+            break;
+        }
+    }
+}
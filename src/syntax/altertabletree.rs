@@ -6,7 +6,7 @@ use super::structures::{
     Switch, Syntax, EmptySyntax, AlterTableSyntax, Expression,
     TableFieldSyntax, TableForeignSyntax
 };
-use super::trees::{DfaNode, AResult, EnumError, EnumResult, Tree};
+use super::trees::{DfaNode, AResult, EnumError, EnumResult, type_name};
 
 macro_rules! hmap {
 ( $( $x:expr => $y:expr ),* ) => {
@@ -52,7 +52,13 @@ impl AlterTableTree {
                 "SetPrimary" => NodeSetPrimary{},
                 "SetDefault" => NodeSetDefault{},
                 "DropField" => NodeDropField{},
-                "SetField2" => NodeSetField2{}
+                "SetField2" => NodeSetField2{},
+                "FieldCheck" => NodeSetCheck{action: "fieldcheck", next: "SetType"},
+                "ConstraintName" => NodeConstraintName{},
+                "ConstraintCheck" => NodeConstraintCheck{},
+                "TableCheck" => NodeSetCheck{action: "check", next: "SetField2"},
+                "DropConstraint" => NodeDropConstraint{},
+                "UniqueList" => NodeKeyList{action: "uniquekey", next: "SetField2"}
             ],
             error: (0, EnumError::None)
         }
@@ -62,6 +68,10 @@ impl AlterTableTree {
         let mut adds = Vec::new();
         let mut alters = Vec::new();
         let mut drops = Vec::new();
+        let mut checks:Vec<(String, Expression)> = Vec::new();
+        let mut drop_checks = Vec::new();
+        let mut uniques = Vec::new();
+        let mut check_name = "".to_string(); //constraint给出的约束名
 
         let mut new = TableFieldSyntax::empty();
         let mut last = "";
@@ -114,6 +124,8 @@ impl AlterTableTree {
                            let v = res.action[1].to_string();
                            if new.t.starts_with("str") {
                                new.t = format!("str:{}", v);
+                           }else if new.t.starts_with("decimal") {
+                               new.t = format!("{}:{}", new.t, v);  //依次是总位数与小数位数
                            }
                        },
                        "complete" => {
@@ -127,6 +139,26 @@ impl AlterTableTree {
                        "notnull" => {new.not_null = true;},
                        "auto_inc" => {new.auto_inc = true;},
                        "default" => {new.default = Option::Some(res.action[1].to_string());},
+                       "checkname" => {check_name = res.action[1].to_string();},
+                       "fieldcheck" | "check" => {
+                           //没有给出约束名时留空，由数据库按引用的字段与已有的约束命名。
+                           let begin_i:usize = res.action[1].parse().unwrap();
+                           let end_i:usize = res.action[2].parse().unwrap();
+                           let check = check_name.to_string();
+                           let expression = match Expression::parse(&li[begin_i..end_i]) {
+                               Result::Ok(e) => e,
+                               Result::Err(pos) => {
+                                   self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                   break;
+                               }
+                           };
+                           checks.push((check, expression));
+                           check_name = "".to_string();
+                       },
+                       "dropcheck" => {
+                           drop_checks.push(res.action[1].to_string());
+                       },
+                       "uniquekey" => {uniques.push(res.action[1..].to_vec());},
                        _ => {}
                     }
                 }
@@ -141,7 +173,10 @@ impl AlterTableTree {
             name: name,
             adds: adds,
             alters: alters,
-            drops: drops
+            drops,
+            checks,
+            drop_checks,
+            uniques
         }
     }
     pub fn get_error(&self) -> &(i32, EnumError) {
@@ -219,6 +254,15 @@ impl DfaNode for NodeAddField {
                 guide = "SetField";
                 action = vec!["addfield".to_string(), var.to_string()];
             },
+            DfaWord::Kword(k) if k == "check" => {
+                guide = "TableCheck";
+            },
+            DfaWord::Kword(k) if k == "constraint" => {
+                guide = "ConstraintName";
+            },
+            DfaWord::Kword(k) if k == "unique" => {
+                guide = "UniqueList";
+            },
             _ => {
                 error = EnumError::SyntaxError;
             }
@@ -267,9 +311,9 @@ impl DfaNode for NodeSetField {
         let mut guide = "";
         let mut error = EnumError::None;
         match w {
-            &DfaWord::Kword(ref k) if k == "integer" || k == "float" || k == "str" || k == "bool" => {
+            w if type_name(w).is_some() => {
                 guide = "SetType";
-                action = vec!["fieldtype".to_string(), k.to_string()];
+                action = vec!["fieldtype".to_string(), type_name(w).unwrap()];
             },
             _ => {
                 error = EnumError::SyntaxError;
@@ -299,6 +343,7 @@ impl DfaNode for NodeSetType {
                 "auto_increment" => {action = vec!["auto_inc".to_string()]},
                 "primary" => {guide = "SetPrimary"},
                 "default" => {guide = "SetDefault"},
+                "check" => {guide = "FieldCheck"},
                 _ => {
                     guide = "SetTableName";
                     action = vec!["complete".to_string()];
@@ -468,6 +513,9 @@ impl DfaNode for NodeDropField {
                 guide = "SetField2";
                 action = vec!["dropfield".to_string(), var.to_string()];
             },
+            DfaWord::Kword(k) if k == "constraint" => {
+                guide = "DropConstraint";
+            },
             _ => {
                 error = EnumError::SyntaxError;
             }
@@ -506,4 +554,155 @@ impl DfaNode for NodeSetField2 {
         panic!("Not Allowed");
     }
     fn allow_array(&self) -> bool {false}
+}
+
+struct NodeSetCheck {
+    action: &'static str,
+    next: &'static str
+}
+impl DfaNode for NodeSetCheck {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //check之后是括号括起的条件表达式。
+        let begin = begin as usize;
+        let is_left = if let Option::Some(DfaWord::Signal(s)) = w.get(begin) {s == "("}else{false};
+        let close = if is_left {Expression::find_close(w, begin)}else{w.len()};
+        if close >= w.len() || close == begin + 1 {
+            return AResult {
+                result: EnumResult::Accept,
+                action: vec![],
+                guide: "".to_string(),
+                error: EnumError::SyntaxError
+            }
+        }
+        *end = close as i32 + 1;
+        AResult {
+            result: EnumResult::Accept,
+            action: vec![self.action.to_string(), (begin + 1).to_string(), close.to_string()],
+            guide: self.next.to_string(),
+            error: EnumError::None
+        }
+    }
+    fn allow_array(&self) -> bool {true}
+}
+
+struct NodeConstraintName;
+impl DfaNode for NodeConstraintName {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Var(v) => {
+                guide = "ConstraintCheck";
+                action = vec!["checkname".to_string(), v.to_string()];
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeConstraintCheck;
+impl DfaNode for NodeConstraintCheck {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(k) if k == "check" => {
+                guide = "TableCheck";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeKeyList {
+    action: &'static str,
+    next: &'static str
+}
+impl DfaNode for NodeKeyList {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //括号括起的字段名列表，字段名之间用逗号分隔。
+        let mut action = vec![self.action.to_string()];
+        let mut i = begin as usize;
+        let mut error = EnumError::SyntaxError;
+        if let Option::Some(DfaWord::Signal(s)) = w.get(i) {
+            if s == "(" {
+                i += 1;
+                while let Option::Some(DfaWord::Var(v)) = w.get(i) {
+                    action.push(v.to_string());
+                    i += 1;
+                    match w.get(i) {
+                        Option::Some(DfaWord::Signal(s)) if s == "," => {i += 1;},
+                        Option::Some(DfaWord::Signal(s)) if s == ")" => {
+                            error = EnumError::None;
+                            i += 1;
+                            break;
+                        },
+                        _ => {break;}
+                    }
+                }
+            }
+        }
+        *end = i as i32;
+        AResult {
+            result: EnumResult::Accept,
+            action,
+            guide: self.next.to_string(),
+            error
+        }
+    }
+    fn allow_array(&self) -> bool {true}
+}
+
+struct NodeDropConstraint;
+impl DfaNode for NodeDropConstraint {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Var(var) => {
+                guide = "SetField2";
+                action = vec!["dropcheck".to_string(), var.to_string()];
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
 }
\ No newline at end of file
@@ -6,7 +6,7 @@ use super::structures::{
     Switch, Syntax, EmptySyntax, CreateTableSyntax, Expression,
     TableFieldSyntax, TableForeignSyntax
 };
-use super::trees::{DfaNode, AResult, EnumError, EnumResult, Tree};
+use super::trees::{DfaNode, AResult, EnumError, EnumResult, type_name};
 
 macro_rules! hmap {
 ( $( $x:expr => $y:expr ),* ) => {
@@ -60,7 +60,18 @@ impl CreateTableTree {
                 "ForeignField" => NodeForeignField{},
                 "ForeignReference" => NodeForeignReference{},
                 "ForeignTable" => NodeForeignTable{},
-                "ForeignKey" => NodeForeignKey{}
+                "ForeignKey" => NodeForeignKey{},
+                "ForeignOn" => NodeForeignOn{},
+                "ForeignDelete" => NodeForeignDelete{},
+                "ForeignSet" => NodeForeignSet{},
+                "FieldCheck" => NodeSetCheck{action: "fieldcheck", next: "SetType"},
+                "ConstraintName" => NodeConstraintName{},
+                "ConstraintCheck" => NodeConstraintCheck{},
+                "TableCheck" => NodeSetCheck{action: "check", next: "ConstraintEnd"},
+                "TablePrimary" => NodeTablePrimary{},
+                "PrimaryList" => NodeKeyList{action: "primarykey", next: "ConstraintEnd"},
+                "UniqueList" => NodeKeyList{action: "uniquekey", next: "ConstraintEnd"},
+                "ConstraintEnd" => NodeConstraintEnd{}
             ],
             error: (0, EnumError::None)
         }
@@ -69,6 +80,10 @@ impl CreateTableTree {
         let mut table_name = "".to_string();
         let mut fields = Vec::new();
         let mut foreigns = Vec::new();
+        let mut checks:Vec<(String, Expression)> = Vec::new();
+        let mut check_name = "".to_string(); //constraint给出的约束名
+        let mut primary = Vec::new();
+        let mut uniques = Vec::new();
 
         let mut new_field = TableFieldSyntax::empty();
         let mut new_foreign = TableForeignSyntax::empty();
@@ -113,6 +128,8 @@ impl CreateTableTree {
                            let v = res.action[1].to_string();
                            if new_field.t.starts_with("str") {
                                new_field.t = format!("str:{}", v);
+                           }else if new_field.t.starts_with("decimal") {
+                               new_field.t = format!("{}:{}", new_field.t, v);  //依次是总位数与小数位数
                            }
                        },
                        "completefield" => {
@@ -128,10 +145,36 @@ impl CreateTableTree {
                        "foreign" => {new_foreign.field = res.action[1].to_string();},
                        "foreigntable" => {new_foreign.foreign_table = res.action[1].to_string();},
                        "foreignfield" => {new_foreign.foreign_field = res.action[1].to_string();},
+                       "foreignaction" => {new_foreign.delete_action = res.action[1].to_string();},
                        "completeforeign" => {
                            foreigns.push(new_foreign);
                            new_foreign = TableForeignSyntax::empty();
-                       }
+                       },
+                       "checkname" => {check_name = res.action[1].to_string();},
+                       "fieldcheck" | "check" => {
+                           //没有给出约束名时留空，由数据库按引用的字段命名。
+                           let begin_i:usize = res.action[1].parse().unwrap();
+                           let end_i:usize = res.action[2].parse().unwrap();
+                           let name = check_name.to_string();
+                           let expression = match Expression::parse(&li[begin_i..end_i]) {
+                               Result::Ok(e) => e,
+                               Result::Err(pos) => {
+                                   self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                   break;
+                               }
+                           };
+                           checks.push((name, expression));
+                           check_name = "".to_string();
+                       },
+                       "primarykey" => {
+                           if !primary.is_empty() {
+                               //表上只能声明一次primary key。
+                               self.error = (i as i32, EnumError::SyntaxError);
+                               break;
+                           }
+                           primary = res.action[1..].to_vec();
+                       },
+                       "uniquekey" => {uniques.push(res.action[1..].to_vec());},
                        _ => {}
                     }
                 }
@@ -143,7 +186,10 @@ impl CreateTableTree {
         CreateTableSyntax {
             name: table_name,
             fields: fields,
-            foreigns: foreigns
+            foreigns,
+            checks,
+            primary,
+            uniques
         }
     }
     pub fn get_error(&self) -> &(i32, EnumError) {
@@ -218,6 +264,18 @@ impl DfaNode for NodeFields {
             &DfaWord::Kword(ref k) if k == "foreign" => {
                 guide = "ForeignCheck";
             },
+            DfaWord::Kword(k) if k == "check" => {
+                guide = "TableCheck";
+            },
+            DfaWord::Kword(k) if k == "constraint" => {
+                guide = "ConstraintName";
+            },
+            DfaWord::Kword(k) if k == "primary" => {
+                guide = "TablePrimary";
+            },
+            DfaWord::Kword(k) if k == "unique" => {
+                guide = "UniqueList";
+            },
             &DfaWord::Signal(ref s) if s == ")" => {
                 guide = "EndField";
             }
@@ -260,9 +318,9 @@ impl DfaNode for NodeSetField {
         let mut guide = "";
         let mut error = EnumError::None;
         match w {
-            &DfaWord::Kword(ref k) if k == "integer" || k == "float" || k == "str" || k == "bool" => {
+            w if type_name(w).is_some() => {
                 guide = "SetType";
-                action = vec!["fieldtype".to_string(), k.to_string()];
+                action = vec!["fieldtype".to_string(), type_name(w).unwrap()];
             },
             _ => {
                 error = EnumError::SyntaxError;
@@ -292,6 +350,7 @@ impl DfaNode for NodeSetType {
                 "auto_increment" => {action = vec!["auto_inc".to_string()]},
                 "primary" => {guide = "SetPrimary"},
                 "default" => {guide = "SetDefault"},
+                "check" => {guide = "FieldCheck"},
                 _ => {error = EnumError::SyntaxError;}
             },
             &DfaWord::Signal(ref s) if s == "(" => {
@@ -454,6 +513,184 @@ impl DfaNode for NodeSetDefault {
     fn allow_array(&self) -> bool {false}
 }
 
+struct NodeSetCheck {
+    action: &'static str,
+    next: &'static str
+}
+impl DfaNode for NodeSetCheck {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //check之后是括号括起的条件表达式。
+        let begin = begin as usize;
+        let is_left = if let Option::Some(DfaWord::Signal(s)) = w.get(begin) {s == "("}else{false};
+        let close = if is_left {Expression::find_close(w, begin)}else{w.len()};
+        if close >= w.len() || close == begin + 1 {
+            return AResult {
+                result: EnumResult::Accept,
+                action: vec![],
+                guide: "".to_string(),
+                error: EnumError::SyntaxError
+            }
+        }
+        *end = close as i32 + 1;
+        AResult {
+            result: EnumResult::Accept,
+            action: vec![self.action.to_string(), (begin + 1).to_string(), close.to_string()],
+            guide: self.next.to_string(),
+            error: EnumError::None
+        }
+    }
+    fn allow_array(&self) -> bool {true}
+}
+
+struct NodeConstraintName;
+impl DfaNode for NodeConstraintName {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Var(v) => {
+                guide = "ConstraintCheck";
+                action = vec!["checkname".to_string(), v.to_string()];
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeConstraintCheck;
+impl DfaNode for NodeConstraintCheck {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(k) if k == "check" => {
+                guide = "TableCheck";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeTablePrimary;
+impl DfaNode for NodeTablePrimary {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(k) if k == "key" => {
+                guide = "PrimaryList";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeKeyList {
+    action: &'static str,
+    next: &'static str
+}
+impl DfaNode for NodeKeyList {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //括号括起的字段名列表，字段名之间用逗号分隔。
+        let mut action = vec![self.action.to_string()];
+        let mut i = begin as usize;
+        let mut error = EnumError::SyntaxError;
+        if let Option::Some(DfaWord::Signal(s)) = w.get(i) {
+            if s == "(" {
+                i += 1;
+                while let Option::Some(DfaWord::Var(v)) = w.get(i) {
+                    action.push(v.to_string());
+                    i += 1;
+                    match w.get(i) {
+                        Option::Some(DfaWord::Signal(s)) if s == "," => {i += 1;},
+                        Option::Some(DfaWord::Signal(s)) if s == ")" => {
+                            error = EnumError::None;
+                            i += 1;
+                            break;
+                        },
+                        _ => {break;}
+                    }
+                }
+            }
+        }
+        *end = i as i32;
+        AResult {
+            result: EnumResult::Accept,
+            action,
+            guide: self.next.to_string(),
+            error
+        }
+    }
+    fn allow_array(&self) -> bool {true}
+}
+
+struct NodeConstraintEnd;
+impl DfaNode for NodeConstraintEnd {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Signal(s) if s == "," => {
+                guide = "Fields";
+            },
+            DfaWord::Signal(s) if s == ")" => {
+                guide = "EndField";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
 struct NodeForeignCheck;
 impl DfaNode for NodeForeignCheck {
     fn analysis(&self, w:&DfaWord) -> AResult {
@@ -669,6 +906,72 @@ impl DfaNode for NodeForeignSignal4 {
                 action = vec!["completeforeign".to_string()];
                 result = EnumResult::Return;
             },
+            DfaWord::Kword(k) if k == "on" => {
+                guide = "ForeignOn";
+            },
+            _ => {error = EnumError::SyntaxError;}
+        }
+        AResult {result: result, action: action, guide: guide.to_string(), error: error}
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{panic!("Not Allowed");}
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeForeignOn;
+impl DfaNode for NodeForeignOn {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(k) if k == "delete" => {
+                guide = "ForeignDelete";
+            },
+            _ => {error = EnumError::SyntaxError;}
+        }
+        AResult {result: result, action: action, guide: guide.to_string(), error: error}
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{panic!("Not Allowed");}
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeForeignDelete;
+impl DfaNode for NodeForeignDelete {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        //on delete cascade | set null | restrict
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Var(k) if k == "cascade" || k == "restrict" => {
+                guide = "ForeignSignal4";
+                action = vec!["foreignaction".to_string(), k.to_string()];
+            },
+            DfaWord::Kword(k) if k == "set" => {
+                guide = "ForeignSet";
+            },
+            _ => {error = EnumError::SyntaxError;}
+        }
+        AResult {result: result, action: action, guide: guide.to_string(), error: error}
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{panic!("Not Allowed");}
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeForeignSet;
+impl DfaNode for NodeForeignSet {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(k) if k == "null" => {
+                guide = "ForeignSignal4";
+                action = vec!["foreignaction".to_string(), "setnull".to_string()];
+            },
             _ => {error = EnumError::SyntaxError;}
         }
         return AResult {result: result, action: action, guide: guide.to_string(), error: error}
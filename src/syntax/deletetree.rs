@@ -83,7 +83,13 @@ impl DeleteTree {
                             let begin_i:usize = res.action[1].parse().unwrap();
                             let end_i:usize = res.action[2].parse().unwrap();
                             // 调用expression的构造。
-                            wheres = Expression::new(&li[begin_i..end_i]);
+                            wheres = match Expression::parse(&li[begin_i..end_i]) {
+                                Result::Ok(e) => e,
+                                Result::Err(pos) => {
+                                    self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                    break;
+                                }
+                            };
                         },
                        _ =>{}
                     }
@@ -186,18 +192,15 @@ impl DfaNode for NodeWhere {
         panic!("Not Allowed");
     }
     fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
-        let mut i = begin as usize;
-        while i < w.len() {
-            if let DfaWord::Kword(_) = w[i] {
-                *end = i as i32;
-                return AResult {
-                    result: EnumResult::Return,
-                    guide: "SetName".to_string(),
-                    error: EnumError::None,
-                    action: vec!["where".to_string(), begin.to_string(), i.to_string()]
-                }
-            }else{
-                i+=1;
+        //where的条件在下一个子句的关键字处结束。
+        let i = Expression::find_end(w, begin as usize, false);
+        if i < w.len() {
+            *end = i as i32;
+            return AResult {
+                result: EnumResult::Return,
+                guide: "SetName".to_string(),
+                error: EnumError::None,
+                action: vec!["where".to_string(), begin.to_string(), i.to_string()]
             }
         }
         *end = w.len() as i32;
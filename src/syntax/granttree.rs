@@ -52,7 +52,12 @@ impl GrantTree {
                 "OnView" => NodeOnView{},
                 "SetObject" => NodeSetObject{},
                 "User" => NodeUser{},
-                "SetUser" => NodeSetUser{}
+                "SetUser" => NodeSetUser{},
+                "Role" => NodeRole{},
+                "SetRole" => NodeSetRole{},
+                "With" => NodeWith{},
+                "WithGrant" => NodeWithGrant{},
+                "End" => NodeEnd{}
             ],
             error: (0, EnumError::None),
             is_grant: is_grant
@@ -63,6 +68,9 @@ impl GrantTree {
         let mut grantall = false;
         let mut grants = Vec::new();
         let mut objects = Vec::new();
+        let mut roles = Vec::new();
+        let mut grant_option = false;
+        let mut cascade = false;
 
         let mut node:&DfaNode = self.nodeset["Start"].borrow();
         let mut i = 0;
@@ -108,6 +116,15 @@ impl GrantTree {
                        "user" => {
                            users.push(res.action[1].to_string());
                        },
+                       "role" => {
+                           roles.push(res.action[1].to_string());
+                       },
+                       "grantoption" => {
+                           grant_option = true;
+                       },
+                       "cascade" => {
+                           cascade = true;
+                       },
                        _ =>{}
                     }
                 }
@@ -116,11 +133,22 @@ impl GrantTree {
                 }
             }
         }
+        //授予角色时不能同时授予权限；with grant option只用于grant，cascade只用于revoke。
+        if self.error.0 == 0
+            && (users.is_empty() ||
+                (!roles.is_empty() && (grantall || !grants.is_empty() || !objects.is_empty() || grant_option)) ||
+                (roles.is_empty() && objects.is_empty()) ||
+                (grant_option && !self.is_grant) || (cascade && self.is_grant)) {
+                self.error = (li.len().max(1) as i32, EnumError::SyntaxError);
+            }
         GrantSyntax {
             all: grantall,
             grants: grants,
             users: users,
             objects: objects,
+            roles,
+            grant_option,
+            cascade,
             is_grant: self.is_grant
         }
     }
@@ -156,6 +184,11 @@ impl DfaNode for NodeStart {
             &DfaWord::Kword(ref k) if k == "on" => {
                 guide = "On";
             }
+            DfaWord::Var(k) => {
+                //不是权限的名字时是角色名。
+                guide = "SetRole";
+                action = vec!["role".to_string(), k.to_string()];
+            }
             _ => {
                 error = EnumError::SyntaxError;
             }
@@ -506,6 +539,13 @@ impl DfaNode for NodeSetUser {
             &DfaWord::Signal(ref k) if k == "," => {
                 guide = "User";
             },
+            DfaWord::Kword(k) if k == "with" => {
+                guide = "With";
+            },
+            DfaWord::Var(k) if k == "cascade" => {
+                guide = "End";
+                action = vec!["cascade".to_string()];
+            },
             _ => {
                 error = EnumError::SyntaxError;
             }
@@ -518,4 +558,123 @@ impl DfaNode for NodeSetUser {
         panic!("Not Allowed");
     }
     fn allow_array(&self) -> bool {false}
-}
\ No newline at end of file
+}
+
+struct NodeRole;
+impl DfaNode for NodeRole {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Var(k) => {
+                guide = "SetRole";
+                action = vec!["role".to_string(), k.to_string()];
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeSetRole;
+impl DfaNode for NodeSetRole {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Signal(k) if k == "," => {
+                guide = "Role";
+            },
+            DfaWord::Kword(k) if k=="to"||k=="from" => {
+                guide = "User";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeWith;
+impl DfaNode for NodeWith {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(k) if k == "grant" => {
+                guide = "WithGrant";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeWithGrant;
+impl DfaNode for NodeWithGrant {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Var(k) if k == "option" => {
+                guide = "End";
+                action = vec!["grantoption".to_string()];
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeEnd;
+impl DfaNode for NodeEnd {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        //语句已经结束，后面不能再有任何内容。
+        AResult {
+            result: EnumResult::Accept, action: vec![], guide: "".to_string(), error: EnumError::SyntaxError
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
@@ -2,7 +2,8 @@ use std::collections::HashMap;
 use std::borrow::Borrow;
 use std::iter::Iterator;
 use super::super::analyse::dfa::{DfaWord};
-use super::structures::{Switch, Syntax, EmptySyntax, InsertSyntax, Expression};
+use super::structures::{Syntax, InsertSyntax, SelectSyntax};
+use super::selecttree::{SelectTree};
 use super::trees::{DfaNode, AResult, EnumError, EnumResult, Tree};
 
 macro_rules! hmap {
@@ -43,7 +44,8 @@ impl InsertTree {
             "ColumnName" => NodeColumnName{},
             "Values" => NodeValues{},
             "SetValue" => NodeSetValue{},
-            "Value" => NodeValue{}
+            "Value" => NodeValue{},
+            "Select" => NodeSelect{}
         ];
         Self {
             nodeset: map,
@@ -57,6 +59,7 @@ impl InsertTree {
         let mut columns = Vec::new();
         let mut values = Vec::new();
         let mut has_head = true;
+        let mut select:Option<SelectSyntax> = Option::None;
 
         let mut node:&DfaNode = self.nodeset["Start"].borrow();
         let mut i = 0;
@@ -97,9 +100,21 @@ impl InsertTree {
                                "integer" => values.push(DfaWord::Integer(v.parse().unwrap())),
                                "float" => values.push(DfaWord::Float(v.parse().unwrap())),
                                "bool" => values.push(DfaWord::Bool(v.parse().unwrap())),
+                               "null" => values.push(DfaWord::Kword("null".to_string())),
                                _ => panic!("Wrong DfaWord Type.")
                            }
                        },
+                       "select" => {
+                           let begin_i:usize = res.action[1].parse().unwrap();
+                           let end_i:usize = res.action[2].parse().unwrap();
+                           let mut tree = SelectTree::new();
+                           let sub = tree.construct(&li[begin_i + 1..end_i]);
+                           if tree.get_error().1 != EnumError::None {
+                               self.error = (begin_i as i32 + 1 + tree.get_error().0, tree.get_error().1);
+                               break;
+                           }
+                           select = Option::Some(sub);
+                       },
                        _ =>{}
                     }
                 }
@@ -108,6 +123,16 @@ impl InsertTree {
                 }
             }
         }
+        if select.is_some() {
+            //insert ... select的数据在执行时才从查询结果中取得。
+            return InsertSyntax {
+                table_name: table_name.to_string(),
+                has_head: !columns.is_empty(),
+                values: vec![],
+                columns,
+                select
+            }
+        }
         let map:HashMap<String, DfaWord> = if columns.len() > 0 && columns.len() != values.len() {
             self.error = (1, EnumError::SyntaxError);
             HashMap::new()
@@ -131,7 +156,9 @@ impl InsertTree {
         InsertSyntax {
             table_name: table_name.to_string(),
             has_head: has_head,
-            values: vec![map]
+            values: vec![map],
+            columns,
+            select: Option::None
         }
     }
     pub fn get_error(&self) -> &(i32, EnumError) {
@@ -180,6 +207,10 @@ impl DfaNode for NodeSetName {
             &DfaWord::Signal(ref s) if s == "(" => {
                 guide = "SetColumn";
             },
+            DfaWord::Kword(kword) if kword == "select" => {
+                guide = "Select";
+                result = EnumResult::Return;
+            },
             _ => {
                 error = EnumError::SyntaxError;
             }
@@ -296,6 +327,10 @@ impl DfaNode for NodeSetValue {
                guide = "Value";
                action = vec!["setvalue".to_string(), "bool".to_string(), b.to_string()];
            },
+           DfaWord::Kword(k) if k == "null" => {
+               guide = "Value";
+               action = vec!["setvalue".to_string(), "null".to_string(), k.to_string()];
+           },
            &DfaWord::Signal(ref s) if s == ")" => {
                guide = "Values";
            }
@@ -340,3 +375,21 @@ impl DfaNode for NodeValue {
     }
     fn allow_array(&self) -> bool {false}
 }
+
+struct NodeSelect;
+impl DfaNode for NodeSelect {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //从select开始直到语句结束都是查询。
+        *end = w.len() as i32;
+        AResult {
+            result: EnumResult::Accept,
+            guide: "".to_string(),
+            action: vec!["select".to_string(), begin.to_string(), w.len().to_string()],
+            error: EnumError::None
+        }
+    }
+    fn allow_array(&self) -> bool {true}
+}
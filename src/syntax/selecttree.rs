@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::borrow::Borrow;
 use std::iter::Iterator;
 use super::super::analyse::dfa::{DfaWord};
-use super::structures::{Switch, Syntax, EmptySyntax, SelectSyntax, Expression};
+use super::structures::{Switch, Syntax, SelectSyntax, JoinSyntax, Expression};
 use super::trees::{DfaNode, AResult, EnumError, EnumResult, Tree};
 fn dfa_name(d:&DfaWord) -> String {
     match d {
@@ -37,6 +37,16 @@ impl SelectTree {
         map.insert("OrderColumn".to_string(), Box::new(NodeOrderColumn{}));
         map.insert("OrderTable".to_string(), Box::new(NodeOrderTable{}));
         map.insert("Where".to_string(), Box::new(NodeWhere{}));
+        map.insert("JoinCheck".to_string(), Box::new(NodeJoinCheck{}));
+        map.insert("Limit".to_string(), Box::new(NodeLimit{kind: "limit"}));
+        map.insert("Offset".to_string(), Box::new(NodeLimit{kind: "offset"}));
+        map.insert("JoinTable".to_string(), Box::new(NodeJoinTable{}));
+        map.insert("JoinName".to_string(), Box::new(NodeJoinName{}));
+        map.insert("JoinAlias".to_string(), Box::new(NodeJoinAlias{}));
+        map.insert("JoinOn".to_string(), Box::new(NodeJoinOn{}));
+        map.insert("GroupCheck".to_string(), Box::new(NodeGroupCheck{}));
+        map.insert("Group".to_string(), Box::new(NodeGroup{}));
+        map.insert("Having".to_string(), Box::new(NodeHaving{}));
         map.insert("Othername3".to_string(), Box::new(NodeOthername3{}));
         map.insert("Othername4".to_string(), Box::new(NodeOthername4{}));
         Self {
@@ -55,6 +65,11 @@ impl SelectTree {
         let mut froms:HashMap<String, Switch<String, SelectSyntax>> = HashMap::new();
         let mut goals:Vec<(String, Expression)> = Vec::new();
         let mut wheres = Expression::empty();// todo 需要重写。
+        let mut joins:Vec<JoinSyntax> = Vec::new();
+        let mut limit:Option<usize> = Option::None;
+        let mut offset:usize = 0;
+        let mut groups:Vec<Expression> = Vec::new();
+        let mut havings = Expression::empty();
         let mut orders:Vec<(String, bool)> = Vec::new();
         let mut last_from = "".to_string();
         let mut last_goal = "".to_string();
@@ -67,6 +82,17 @@ impl SelectTree {
                 from [name] 新的标准来源表名。
                 fromsub [begin] [end] 标记始末，处理为一个序列表达式构成子查询。
                 as [name] 将上一个加入的来源重命名。
+            Join:
+                join [kind] 开始一个新的连接。
+                jointable [name] 连接的表名。
+                joinas [name] 连接的表的别名。
+                on [begin] [end] 标记始末，构造连接条件。
+            Group:
+                group [begin] [end] 标记始末，构造一个分组表达式。
+                having [begin] [end] 标记始末，构造分组的条件表达式。
+            Limit:
+                limit [n] 最多返回的行数。
+                offset [n] 跳过的行数。
         */
         let mut node:&DfaNode = self.nodeset["Start"].borrow();
         let mut i = 0;
@@ -101,7 +127,13 @@ impl SelectTree {
                             let begin_i:usize = res.action[1].parse().unwrap();
                             let end_i:usize = res.action[2].parse().unwrap();
                             // 调用expression的构造。
-                            let goal = Expression::new(&li[begin_i..end_i]);
+                            let goal = match Expression::parse(&li[begin_i..end_i]) {
+                                Result::Ok(e) => e,
+                                Result::Err(pos) => {
+                                    self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                    break;
+                                }
+                            };
                             let mut goal_name = String::new(); //合成默认的goalname
                             for i in &li[begin_i..end_i] {
                                 goal_name += i.to_code_string().as_str();
@@ -123,7 +155,12 @@ impl SelectTree {
                             else {
                                 if let DfaWord::Kword(ref word) = li[begin_i+1] {
                                     let mut sub = SelectTree::new();
-                                    froms.insert("SubSelect".to_string(), Switch::Two(sub.construct(&li[begin_i+2..end_i])));
+                                    let select = sub.construct(&li[begin_i+2..end_i]);
+                                    if sub.get_error().1 != EnumError::None {
+                                        self.error = ((begin_i + 2) as i32 + sub.get_error().0, EnumError::SyntaxError);
+                                        break;
+                                    }
+                                    froms.insert("SubSelect".to_string(), Switch::Two(select));
                                     last_from = "SubSelect".to_string();
                                 }else {err = true;}
                             }
@@ -159,7 +196,64 @@ impl SelectTree {
                             let begin_i:usize = res.action[1].parse().unwrap();
                             let end_i:usize = res.action[2].parse().unwrap();
                             // 调用expression的构造。
-                            wheres = Expression::new(&li[begin_i..end_i]);
+                            wheres = match Expression::parse(&li[begin_i..end_i]) {
+                                Result::Ok(e) => e,
+                                Result::Err(pos) => {
+                                    self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                    break;
+                                }
+                            };
+                        },
+                        "limit" => {
+                            limit = Option::Some(res.action[1].parse().unwrap());
+                        },
+                        "offset" => {
+                            offset = res.action[1].parse().unwrap();
+                        },
+                        "join" => {
+                            joins.push(JoinSyntax::new(res.action[1].as_str()));
+                        },
+                        "jointable" | "joinas" => {
+                            if let Option::Some(join) = joins.last_mut() {
+                                if res.action[0] == "jointable" {join.table = res.action[1].to_string();}
+                                join.name = res.action[1].to_string();
+                            }
+                        },
+                        "on" => {
+                            let begin_i:usize = res.action[1].parse().unwrap();
+                            let end_i:usize = res.action[2].parse().unwrap();
+                            if let Option::Some(join) = joins.last_mut() {
+                                join.on = match Expression::parse(&li[begin_i..end_i]) {
+                                    Result::Ok(e) => e,
+                                    Result::Err(pos) => {
+                                        self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                        break;
+                                    }
+                                };
+                            }
+                        },
+                        "group" => {
+                            let begin_i:usize = res.action[1].parse().unwrap();
+                            let end_i:usize = res.action[2].parse().unwrap();
+                            let expression = match Expression::parse(&li[begin_i..end_i]) {
+                                Result::Ok(e) => e,
+                                Result::Err(pos) => {
+                                    self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                    break;
+                                }
+                            };
+                            groups.push(expression);
+                        },
+                        "having" => {
+                            let begin_i:usize = res.action[1].parse().unwrap();
+                            let end_i:usize = res.action[2].parse().unwrap();
+                            havings = match Expression::parse(&li[begin_i..end_i]) {
+                                Result::Ok(e) => e,
+                                Result::Err(pos) => {
+                                    self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                    break;
+                                }
+                            };
                         },
                         "order" => {
                             let name = &res.action[1];
@@ -172,6 +266,7 @@ impl SelectTree {
                                 if orders[index].0 == last_order.as_str() {
                                     break;
                                 }
+                                index += 1;
                             }
                             if index < orders.len() {
                                 let (name, _) = orders.remove(index);
@@ -185,6 +280,7 @@ impl SelectTree {
                                 if orders[index].0 == last_order.as_str() {
                                     break;
                                 }
+                                index += 1;
                             }
                             if index < orders.len() {
                                 let v = &res.action[1];
@@ -207,9 +303,14 @@ impl SelectTree {
         SelectSyntax{
             distinct: distinct,
             froms: froms,
+            joins,
             goals: goals,
             wheres: wheres,
-            orders: orders
+            groups,
+            havings,
+            orders,
+            limit,
+            offset
         }
     }
     pub fn get_error(&self) -> &(i32, EnumError){
@@ -265,8 +366,8 @@ impl DfaNode for NodeGoal {
     }
     fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
         // 构造目标列.方案是在这里通过括号配对找到终点，然后输出终点位置。
-        let mut i = begin as usize;
-        while i < w.len() {
+        let i = Expression::find_end(w, begin as usize, true);
+        if i < w.len() {
             if let DfaWord::Kword(ref k) = w[i] {
                 *end = (if k == "as" {i+1}else{i}) as i32;
                 return AResult {
@@ -284,11 +385,7 @@ impl DfaNode for NodeGoal {
                         error: EnumError::None,
                         action: vec!["goal".to_string(), begin.to_string(), i.to_string()]
                     }
-                }else {
-                    i+=1;
                 }
-            }else{
-                i+=1;
             }
         }
         *end = w.len() as i32;
@@ -311,7 +408,26 @@ impl DfaNode for NodeBehind {
                 "from" => {guide = "From";},
                 "where" => {guide = "Where";},
                 "group" => {guide = "GroupCheck";},
+                "having" => {guide = "Having";},
+                "join" => {
+                    return AResult {
+                        result: EnumResult::Accept,
+                        action: vec!["join".to_string(), "inner".to_string()],
+                        guide: "JoinTable".to_string(),
+                        error: EnumError::None
+                    }
+                },
+                "inner" | "left" | "right" => {
+                    return AResult {
+                        result: EnumResult::Accept,
+                        action: vec!["join".to_string(), word.to_string()],
+                        guide: "JoinCheck".to_string(),
+                        error: EnumError::None
+                    }
+                },
                 "order" => {guide = "OrderCheck";},
+                "limit" => {guide = "Limit";},
+                "offset" => {guide = "Offset";},
                 _ => {return AResult{
                     result: EnumResult::Accept,
                     action: vec![],
@@ -590,25 +706,289 @@ impl DfaNode for NodeWhere {
         panic!("Not Allowed");
     }
     fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
-        let mut i = begin as usize;
-        while i < w.len() {
-            if let DfaWord::Kword(_) = w[i] {
-                *end = i as i32;
+        //where的条件在下一个子句的关键字处结束。
+        let i = Expression::find_end(w, begin as usize, false);
+        if i < w.len() {
+            *end = i as i32;
+            return AResult {
+                result: EnumResult::Return,
+                guide: "Behind".to_string(),
+                error: EnumError::None,
+                action: vec!["where".to_string(), begin.to_string(), i.to_string()]
+            }
+        }
+        *end = w.len() as i32;
+        AResult {
+            result: EnumResult::Accept,
+            guide: "Behind".to_string(),
+            action: vec!["where".to_string(), begin.to_string(), w.len().to_string()],
+            error: EnumError::None
+        }
+    }
+    fn allow_array(&self) -> bool {true}
+}
+
+struct NodeLimit {
+    kind: &'static str
+}
+impl DfaNode for NodeLimit {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        //limit/offset之后是一个非负整数。
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            &DfaWord::Integer(i) if i >= 0 => {
+                action = vec![self.kind.to_string(), i.to_string()];
+                guide = "Behind";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: EnumResult::Accept, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeJoinCheck;
+impl DfaNode for NodeJoinCheck {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        //inner/left/right之后是[outer] join。
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(word) if word == "outer" => {},
+            DfaWord::Kword(word) if word == "join" => {
+                guide = "JoinTable";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeJoinTable;
+impl DfaNode for NodeJoinTable {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Var(v) => {
+                action = vec!["jointable".to_string(), v.to_string()];
+                guide = "JoinName";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeJoinName;
+impl DfaNode for NodeJoinName {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let mut result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(word) if word == "as" => {
+                guide = "JoinAlias";
+            },
+            DfaWord::Kword(word) if word == "on" => {
+                guide = "JoinOn";
+            },
+            DfaWord::Kword(_) => {
+                //没有on的连接是笛卡儿积。
+                guide = "Behind";
+                result = EnumResult::Return;
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeJoinAlias;
+impl DfaNode for NodeJoinAlias {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let mut action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Var(v) => {
+                action = vec!["joinas".to_string(), v.to_string()];
+                guide = "JoinName";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeJoinOn;
+impl DfaNode for NodeJoinOn {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //连接条件在下一个子句或下一个连接的关键字处结束。
+        let i = Expression::find_end(w, begin as usize, false);
+        if i < w.len() {
+            *end = i as i32;
+            return AResult {
+                result: EnumResult::Return,
+                guide: "Behind".to_string(),
+                error: EnumError::None,
+                action: vec!["on".to_string(), begin.to_string(), i.to_string()]
+            }
+        }
+        *end = w.len() as i32;
+        AResult {
+            result: EnumResult::Accept,
+            guide: "Behind".to_string(),
+            action: vec!["on".to_string(), begin.to_string(), w.len().to_string()],
+            error: EnumError::None
+        }
+    }
+    fn allow_array(&self) -> bool {true}
+}
+
+struct NodeGroupCheck;
+impl DfaNode for NodeGroupCheck {
+    fn analysis(&self, w:&DfaWord) -> AResult {
+        let result = EnumResult::Accept;
+        let action = vec![];
+        let mut guide = "";
+        let mut error = EnumError::None;
+        match w {
+            DfaWord::Kword(word) if word == "by" => {
+                guide = "Group";
+            },
+            _ => {
+                error = EnumError::SyntaxError;
+            }
+        }
+        AResult {
+            result: result, action: action, guide: guide.to_string(), error: error
+        }
+    }
+    fn analysis_array(&self, _w:&[DfaWord], _begin:i32, _end:&mut i32) -> AResult{
+        panic!("Not Allowed");
+    }
+    fn allow_array(&self) -> bool {false}
+}
+
+struct NodeGroup;
+impl DfaNode for NodeGroup {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //分组表达式之间用逗号分割，在下一个子句的关键字处结束。
+        let i = Expression::find_end(w, begin as usize, true);
+        if i <= begin as usize {
+            *end = begin;
+            return AResult {
+                result: EnumResult::Accept,
+                guide: "".to_string(),
+                error: EnumError::SyntaxError,
+                action: vec![]
+            }
+        }
+        if i < w.len() {
+            if let DfaWord::Signal(_) = w[i] {
+                *end = (i + 1) as i32;
                 return AResult {
-                    result: EnumResult::Return,
-                    guide: "Behind".to_string(),
+                    result: EnumResult::Accept,
+                    guide: "".to_string(),
                     error: EnumError::None,
-                    action: vec!["where".to_string(), begin.to_string(), i.to_string()]
+                    action: vec!["group".to_string(), begin.to_string(), i.to_string()]
                 }
-            }else{
-                i+=1;
+            }
+            *end = i as i32;
+            return AResult {
+                result: EnumResult::Return,
+                guide: "Behind".to_string(),
+                error: EnumError::None,
+                action: vec!["group".to_string(), begin.to_string(), i.to_string()]
             }
         }
         *end = w.len() as i32;
         AResult {
             result: EnumResult::Accept,
             guide: "Behind".to_string(),
-            action: vec!["where".to_string(), begin.to_string(), w.len().to_string()],
+            action: vec!["group".to_string(), begin.to_string(), w.len().to_string()],
+            error: EnumError::None
+        }
+    }
+    fn allow_array(&self) -> bool {true}
+}
+
+struct NodeHaving;
+impl DfaNode for NodeHaving {
+    fn analysis(&self, _w:&DfaWord) -> AResult {
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //having的条件与where一样在下一个子句的关键字处结束。
+        let i = Expression::find_end(w, begin as usize, false);
+        if i < w.len() {
+            *end = i as i32;
+            return AResult {
+                result: EnumResult::Return,
+                guide: "Behind".to_string(),
+                error: EnumError::None,
+                action: vec!["having".to_string(), begin.to_string(), i.to_string()]
+            }
+        }
+        *end = w.len() as i32;
+        AResult {
+            result: EnumResult::Accept,
+            guide: "Behind".to_string(),
+            action: vec!["having".to_string(), begin.to_string(), w.len().to_string()],
             error: EnumError::None
         }
     }
@@ -1,7 +1,12 @@
 use std::collections::HashMap;
+use std::cmp::Ordering;
 use std::convert::From;
 use std::clone::Clone;
 use super::super::analyse::dfa::{DfaWord};
+use super::super::analyse::datetime;
+use super::super::analyse::decimal;
+use super::selecttree::{SelectTree};
+use super::trees::{EnumError, type_name};
 
 pub enum Switch<A, B> {
     One(A),
@@ -15,11 +20,20 @@ pub enum ExpressionType {
     Integer(i64),
     Float(f64),
     Str(String),
-    Signal(String)
+    Date(i32),  //日期与时间，取值与DataItem相同
+    Time(i32),
+    Timestamp(i64),
+    Decimal(i64, usize),  //定点小数：不带小数点的整数值, 小数位数
+    Signal(String),
+    Null,
+    Func(String, usize),  //函数调用：函数名, 参数数目。参数在后缀式中位于它之前。
+    Select(Box<SelectSyntax>),  //子查询
+    List(Vec<ExpressionType>)  //子查询的结果列，只在计算过程中出现
 }
 impl ExpressionType {
     pub fn new(d:&DfaWord) -> Self {
         match d {
+            DfaWord::Kword(word) if word == "null" => ExpressionType::Null,
             &DfaWord::Kword(ref word) => ExpressionType::Kword(word.to_string()),
             &DfaWord::Integer(ref i) => ExpressionType::Integer(*i),
             &DfaWord::Float(ref f) => ExpressionType::Float(*f),
@@ -57,8 +71,19 @@ impl ExpressionType {
             &ExpressionType::Integer(ref i) => i.to_string(),
             &ExpressionType::Float(ref f) => f.to_string(),
             &ExpressionType::Str(ref s) => s.to_string(),
+            &ExpressionType::Date(d) => datetime::format_date(d),
+            &ExpressionType::Time(t) => datetime::format_time(t),
+            &ExpressionType::Timestamp(t) => datetime::format_timestamp(t),
+            &ExpressionType::Decimal(v, s) => decimal::format(v, s),
             &ExpressionType::Signal(ref s) => s.to_string(),
-            &ExpressionType::Bool(ref b) => b.to_string()
+            ExpressionType::Bool(b) => b.to_string(),
+            &ExpressionType::Null => "null".to_string(),
+            &ExpressionType::Func(ref name, argc) => format!("{}/{}", name, argc),
+            ExpressionType::Select(sub) => format!("({})", sub.get_setence()),
+            ExpressionType::List(li) => {
+                let v:Vec<String> = li.iter().map(|i|i.to_string()).collect();
+                format!("({})", v.join(", "))
+            }
         }
     }
     pub fn copy(&self) -> Self {
@@ -72,14 +97,165 @@ impl ExpressionType {
             &ExpressionType::Integer(i) => ExpressionType::Integer(i),
             &ExpressionType::Float(f) => ExpressionType::Float(f),
             &ExpressionType::Str(ref s) => ExpressionType::Str(s.to_string()),
+            &ExpressionType::Date(d) => ExpressionType::Date(d),
+            &ExpressionType::Time(t) => ExpressionType::Time(t),
+            &ExpressionType::Timestamp(t) => ExpressionType::Timestamp(t),
+            &ExpressionType::Decimal(v, s) => ExpressionType::Decimal(v, s),
             &ExpressionType::Signal(ref s) => ExpressionType::Signal(s.to_string()),
-            &ExpressionType::Bool(b) => ExpressionType::Bool(b)
+            &ExpressionType::Bool(b) => ExpressionType::Bool(b),
+            &ExpressionType::Null => ExpressionType::Null,
+            &ExpressionType::Func(ref name, argc) => ExpressionType::Func(name.to_string(), argc),
+            ExpressionType::Select(sub) => ExpressionType::Select(Box::new(sub.copy())),
+            ExpressionType::List(li) => ExpressionType::List(li.iter().map(|i|i.copy()).collect())
+        }
+    }
+    pub fn is_aggregate(&self) -> bool {
+        if let ExpressionType::Func(name, _) = self {
+            Expression::is_aggregate_name(name)
+        }else{false}
+    }
+    pub fn is_null(&self) -> bool {
+        if let &ExpressionType::Null = self {true}else{false}
+    }
+    pub fn is_temporal(&self) -> bool {
+        match self {
+            &ExpressionType::Date(_) | &ExpressionType::Time(_) | &ExpressionType::Timestamp(_) => true,
+            _ => false
+        }
+    }
+    fn to_temporal(&self, like:&ExpressionType) -> Result<ExpressionType, String> {
+        //与日期时间运算的字符串按另一侧的类型解析。
+        if let ExpressionType::Str(s) = self {
+            let ret = match like {
+                &ExpressionType::Date(_) => datetime::parse_date(s).map(ExpressionType::Date),
+                &ExpressionType::Time(_) => datetime::parse_time(s).map(ExpressionType::Time),
+                &ExpressionType::Timestamp(_) => datetime::parse_timestamp(s).map(ExpressionType::Timestamp),
+                _ => {return Result::Ok(self.copy());}
+            };
+            return ret.ok_or(format!("Wrong date/time value: {}.", s));
+        }
+        Result::Ok(self.copy())
+    }
+    fn make_temporal(p1:&ExpressionType, p2:&ExpressionType, oper:&str) -> Result<ExpressionType, String> {
+        /*  date ± 整数(天) = date，time ± 整数(秒) = time，timestamp ± 整数(秒) = timestamp，date + time = timestamp。
+            同类的日期时间相减得到相差的天数(date)或秒数(time/timestamp)，也可以互相比较。
+            date与timestamp混合时，date按当天零点处理。
+        */
+        let a = match p1.to_temporal(p2) {Result::Ok(a) => a, Result::Err(e) => {return Result::Err(e);}};
+        let b = match p2.to_temporal(p1) {Result::Ok(b) => b, Result::Err(e) => {return Result::Err(e);}};
+        let day = datetime::DAY_SECONDS;
+        //超出0001-01-01到9999-12-31的结果是错误，不能回绕成别的日期。
+        let date = |v:Option<i64>| match v.and_then(datetime::to_date) {
+            Option::Some(d) => Result::Ok(ExpressionType::Date(d)),
+            Option::None => Result::Err("Date/time value is out of range.".to_string())
+        };
+        let timestamp = |v:Option<i64>| match v.and_then(datetime::to_timestamp) {
+            Option::Some(t) => Result::Ok(ExpressionType::Timestamp(t)),
+            Option::None => Result::Err("Date/time value is out of range.".to_string())
+        };
+        match (oper, &a, &b) {
+            ("+", &ExpressionType::Date(d), &ExpressionType::Integer(i)) |
+            ("+", &ExpressionType::Integer(i), &ExpressionType::Date(d)) => date((d as i64).checked_add(i)),
+            ("-", &ExpressionType::Date(d), &ExpressionType::Integer(i)) => date((d as i64).checked_sub(i)),
+            ("+", &ExpressionType::Time(t), &ExpressionType::Integer(i)) |
+            ("+", &ExpressionType::Integer(i), &ExpressionType::Time(t)) => Result::Ok(ExpressionType::Time((t as i64 + i.rem_euclid(day)).rem_euclid(day) as i32)),
+            ("-", &ExpressionType::Time(t), &ExpressionType::Integer(i)) => Result::Ok(ExpressionType::Time((t as i64 - i.rem_euclid(day)).rem_euclid(day) as i32)),
+            ("+", &ExpressionType::Timestamp(t), &ExpressionType::Integer(i)) |
+            ("+", &ExpressionType::Integer(i), &ExpressionType::Timestamp(t)) => timestamp(t.checked_add(i)),
+            ("-", &ExpressionType::Timestamp(t), &ExpressionType::Integer(i)) => timestamp(t.checked_sub(i)),
+            ("+", &ExpressionType::Date(d), &ExpressionType::Time(t)) |
+            ("+", &ExpressionType::Time(t), &ExpressionType::Date(d)) => Result::Ok(ExpressionType::Timestamp(d as i64 * day + t as i64)),
+            ("-", &ExpressionType::Date(x), &ExpressionType::Date(y)) => Result::Ok(ExpressionType::Integer(x as i64 - y as i64)),
+            _ => {
+                //其余的运算都在同一条时间轴上按数值进行。
+                let axis = |e:&ExpressionType| match e {
+                    &ExpressionType::Date(d) => Option::Some((0, d as i64 * day)),
+                    &ExpressionType::Timestamp(t) => Option::Some((0, t)),
+                    &ExpressionType::Time(t) => Option::Some((1, t as i64)),
+                    _ => Option::None
+                };
+                match (axis(&a), axis(&b)) {
+                    (Option::Some((k1, x)), Option::Some((k2, y))) if k1 == k2 => match oper {
+                        "-" | ">=" | "<=" | ">" | "<" | "=" | "!=" => {
+                            ExpressionType::make_two(&ExpressionType::Integer(x), &ExpressionType::Integer(y), oper)
+                        },
+                        _ => Result::Err("Wrong operator param type.".to_string())
+                    },
+                    _ => Result::Err("Wrong operator param type.".to_string())
+                }
+            }
+        }
+    }
+    pub fn is_decimal(&self) -> bool {
+        if let &ExpressionType::Decimal(..) = self {true}else{false}
+    }
+    fn make_decimal(p1:&ExpressionType, p2:&ExpressionType, oper:&str) -> Result<ExpressionType, String> {
+        /*  定点小数与整数、浮点数运算时，整数与浮点数按字面的值转换为定点小数，结果仍是精确的定点小数。
+            ^运算以及无法转换的值按原来的规则计算：与字符串相加时连接为字符串，其余的按浮点数计算。
+        */
+        let dec = |e:&ExpressionType| match e {
+            &ExpressionType::Decimal(v, s) => Option::Some((v, s)),
+            &ExpressionType::Integer(i) => Option::Some((i, 0)),
+            &ExpressionType::Float(f) => decimal::parse(&f.to_string()),
+            _ => Option::None
+        };
+        match (dec(p1), dec(p2)) {
+            (Option::Some(a), Option::Some(b)) if oper != "^" => match oper {
+                "+" | "-" | "*" | "/" | "%" => match decimal::operate(a, b, oper) {
+                    Option::Some((v, s)) => Result::Ok(ExpressionType::Decimal(v, s)),
+                    Option::None if b.0 == 0 && (oper == "/" || oper == "%") => Result::Err("Division by zero.".to_string()),
+                    Option::None => Result::Err("Decimal value is out of range.".to_string())
+                },
+                ">=" | "<=" | ">" | "<" | "=" | "!=" => {
+                    let o = decimal::cmp(a, b);
+                    Result::Ok(ExpressionType::Bool(match oper {
+                        ">=" => o != Ordering::Less,
+                        "<=" => o != Ordering::Greater,
+                        ">" => o == Ordering::Greater,
+                        "<" => o == Ordering::Less,
+                        "=" => o == Ordering::Equal,
+                        _ => o != Ordering::Equal
+                    }))
+                },
+                _ => Result::Err("Wrong operator param type.".to_string())
+            },
+            _ => {
+                let text = if let (&ExpressionType::Str(_), _) | (_, &ExpressionType::Str(_)) = (p1, p2) {true}else{false};
+                let plain = |e:&ExpressionType| match e {
+                    &ExpressionType::Decimal(v, s) if text => ExpressionType::Str(decimal::format(v, s)),
+                    &ExpressionType::Decimal(v, s) => ExpressionType::Float(decimal::format(v, s).parse().unwrap()),
+                    _ => e.copy()
+                };
+                ExpressionType::make_two(&plain(p1), &plain(p2), oper)
+            }
         }
     }
     pub fn abs_eq(a:f64, b:f64) -> bool {
         (a-b).abs() < 1e-10
     }
     pub fn make_two(p1:&ExpressionType, p2:&ExpressionType, oper:&str) -> Result<ExpressionType, String> {
+        //任一参数为null时按三值逻辑处理：false&&null为false，true||null为true，其余运算的结果都是null。
+        if p1.is_null() || p2.is_null() {
+            return match oper {
+                "&&" => match (p1, p2) {
+                    (&ExpressionType::Bool(false), _) | (_, &ExpressionType::Bool(false)) => Result::Ok(ExpressionType::Bool(false)),
+                    _ => Result::Ok(ExpressionType::Null)
+                },
+                "||" => match (p1, p2) {
+                    (&ExpressionType::Bool(true), _) | (_, &ExpressionType::Bool(true)) => Result::Ok(ExpressionType::Bool(true)),
+                    _ => Result::Ok(ExpressionType::Null)
+                },
+                "^" | "*" | "/" | "%" | "+" | "-" |
+                ">=" | "<=" | ">" | "<" | "=" | "!=" => Result::Ok(ExpressionType::Null),
+                _ => Result::Err(format!("Unknown operator: {}.", oper))
+            };
+        }
+        if p1.is_temporal() || p2.is_temporal() {
+            return ExpressionType::make_temporal(p1, p2, oper);
+        }
+        if p1.is_decimal() || p2.is_decimal() {
+            return ExpressionType::make_decimal(p1, p2, oper);
+        }
         match oper {
             "^" => {
                 match p1 {
@@ -343,15 +519,279 @@ impl ExpressionType {
             _ => Result::Err(format!("Unknown operator: {}.", oper))
         }
     }
+    pub fn make_three(p1:&ExpressionType, p2:&ExpressionType, p3:&ExpressionType, oper:&str) -> Result<ExpressionType, String> {
+        //x between a and b 等价于 x >= a && x <= b，null按照&&的三值逻辑处理。
+        match oper {
+            "between" | "notbetween" => {
+                let low = match ExpressionType::make_two(p1, p2, ">=") {
+                    Result::Ok(ok) => ok,
+                    e@Result::Err(_) => {return e;}
+                };
+                let high = match ExpressionType::make_two(p1, p3, "<=") {
+                    Result::Ok(ok) => ok,
+                    e@Result::Err(_) => {return e;}
+                };
+                let ret = ExpressionType::make_two(&low, &high, "&&");
+                if oper == "between" {ret}
+                else{match ret {
+                    Result::Ok(ok) => ExpressionType::make_one(&ok, "!"),
+                    e@Result::Err(_) => e
+                }}
+            },
+            _ => Result::Err(format!("Unknown operator: {}.", oper))
+        }
+    }
+    pub fn make_in(p1:&ExpressionType, list:&[ExpressionType], oper:&str) -> Result<ExpressionType, String> {
+        //x in (...)：有相等的值时为true；否则列表中有null时为null，没有时为false。not in取反。
+        if p1.is_null() {return Result::Ok(ExpressionType::Null);}
+        let mut ret = ExpressionType::Bool(false);
+        for i in list.iter() {
+            match ExpressionType::make_two(p1, i, "=") {
+                Result::Ok(ExpressionType::Bool(true)) => {
+                    ret = ExpressionType::Bool(true);
+                    break;
+                },
+                Result::Ok(ExpressionType::Null) => {ret = ExpressionType::Null;},
+                Result::Ok(_) => {},
+                e@Result::Err(_) => {return e;}
+            }
+        }
+        if oper == "in" {Result::Ok(ret)}
+        else{ExpressionType::make_one(&ret, "!")}
+    }
     pub fn make_one(p1:&ExpressionType, oper:&str) -> Result<ExpressionType, String> {
         match oper {
             "!" => match p1 {
                 &ExpressionType::Bool(a) => Result::Ok(ExpressionType::Bool(!a)),
+                &ExpressionType::Null => Result::Ok(ExpressionType::Null),
                  _ => Result::Err(format!("Wrong operator param type."))
             },
+            "isnull" => Result::Ok(ExpressionType::Bool(p1.is_null())),
+            "isnotnull" => Result::Ok(ExpressionType::Bool(!p1.is_null())),
              _ => Result::Err(format!("Wrong operator param type."))
         }
     }
+    fn cast(&self, t:&str) -> Result<ExpressionType, String> {
+        //cast(x as 类型)。类型写成"类型名:参数:参数"的形式，与保存字段类型时相同，例如decimal:10:2、str:20。
+        let fail = || Result::Err(format!("Cannot cast {} to {}.", self.to_string(), t));
+        let v:Vec<&str> = t.split(':').collect();
+        let mut params = vec![];
+        for i in v[1..].iter() {
+            match i.parse::<usize>() {
+                Result::Ok(n) => params.push(n),
+                Result::Err(_) => {return Result::Err(format!("Unknown type: {}.", t));}
+            }
+        }
+        match v[0] {
+            "integer" => match self {
+                &ExpressionType::Integer(i) => Result::Ok(ExpressionType::Integer(i)),
+                &ExpressionType::Float(f) if f.is_finite() && f.abs() < 9.2e18 => Result::Ok(ExpressionType::Integer(f.round() as i64)),
+                &ExpressionType::Decimal(v, s) => match decimal::rescale(v, s, 0) {
+                    Option::Some(i) => Result::Ok(ExpressionType::Integer(i)),
+                    Option::None => fail()
+                },
+                &ExpressionType::Bool(b) => Result::Ok(ExpressionType::Integer(if b {1}else{0})),
+                ExpressionType::Str(s) => match s.trim().parse::<i64>() {
+                    Result::Ok(i) => Result::Ok(ExpressionType::Integer(i)),
+                    Result::Err(_) => fail()
+                },
+                _ => fail()
+            },
+            "float" => match self {
+                &ExpressionType::Integer(i) => Result::Ok(ExpressionType::Float(i as f64)),
+                &ExpressionType::Float(f) => Result::Ok(ExpressionType::Float(f)),
+                &ExpressionType::Decimal(v, s) => Result::Ok(ExpressionType::Float(decimal::format(v, s).parse().unwrap())),
+                ExpressionType::Str(s) => match s.trim().parse::<f64>() {
+                    Result::Ok(f) => Result::Ok(ExpressionType::Float(f)),
+                    Result::Err(_) => fail()
+                },
+                _ => fail()
+            },
+            "bool" => match self {
+                &ExpressionType::Bool(b) => Result::Ok(ExpressionType::Bool(b)),
+                &ExpressionType::Integer(i) => Result::Ok(ExpressionType::Bool(i != 0)),
+                ExpressionType::Str(s) => match s.trim().to_lowercase().as_str() {
+                    "true" => Result::Ok(ExpressionType::Bool(true)),
+                    "false" => Result::Ok(ExpressionType::Bool(false)),
+                    _ => fail()
+                },
+                _ => fail()
+            },
+            "str" | "text" => match self {
+                &ExpressionType::Integer(_) | &ExpressionType::Float(_) | &ExpressionType::Str(_) | &ExpressionType::Bool(_) |
+                &ExpressionType::Date(_) | &ExpressionType::Time(_) | &ExpressionType::Timestamp(_) | &ExpressionType::Decimal(..) => {
+                    //给出了长度时截断到该长度。
+                    let s = self.to_string();
+                    Result::Ok(ExpressionType::Str(match params.first() {
+                        Option::Some(&n) if v[0] == "str" => s.chars().take(n).collect(),
+                        _ => s
+                    }))
+                },
+                _ => fail()
+            },
+            "decimal" => {
+                let (p, s) = match (params.first(), params.get(1)) {
+                    (Option::None, _) => (10, 0),
+                    (Option::Some(&p), Option::None) => (p, 0),
+                    (Option::Some(&p), Option::Some(&s)) => (p, s)
+                };
+                if !(1..=decimal::MAX_PRECISION).contains(&p) || s > p {
+                    return Result::Err(format!("Unknown type: {}.", t));
+                }
+                let from = match self {
+                    &ExpressionType::Decimal(v, s) => Option::Some((v, s)),
+                    &ExpressionType::Integer(i) => Option::Some((i, 0)),
+                    &ExpressionType::Float(f) => decimal::parse(&f.to_string()),
+                    ExpressionType::Str(st) => decimal::parse(st),
+                    _ => Option::None
+                };
+                match from.and_then(|(v, from)|decimal::rescale(v, from, s)) {
+                    Option::Some(v) if decimal::fits(v, p) => Result::Ok(ExpressionType::Decimal(v, s)),
+                    _ => fail()
+                }
+            },
+            "date" | "time" | "timestamp" => {
+                let day = datetime::DAY_SECONDS;
+                match (v[0], self) {
+                    (_, &ExpressionType::Str(_)) => {
+                        let like = match v[0] {
+                            "date" => ExpressionType::Date(0),
+                            "time" => ExpressionType::Time(0),
+                            _ => ExpressionType::Timestamp(0)
+                        };
+                        //日期或时间也可以从完整的时间戳字符串中截取。
+                        match (self.to_temporal(&like), self.to_temporal(&ExpressionType::Timestamp(0))) {
+                            (r@Result::Ok(_), _) => r,
+                            (_, Result::Ok(ts)) => ts.cast(t),
+                            _ => fail()
+                        }
+                    },
+                    ("date", &ExpressionType::Date(d)) => Result::Ok(ExpressionType::Date(d)),
+                    ("date", &ExpressionType::Timestamp(t)) => Result::Ok(ExpressionType::Date(t.div_euclid(day) as i32)),
+                    ("time", &ExpressionType::Time(t)) => Result::Ok(ExpressionType::Time(t)),
+                    ("time", &ExpressionType::Timestamp(t)) => Result::Ok(ExpressionType::Time(t.rem_euclid(day) as i32)),
+                    ("timestamp", &ExpressionType::Timestamp(t)) => Result::Ok(ExpressionType::Timestamp(t)),
+                    ("timestamp", &ExpressionType::Date(d)) => Result::Ok(ExpressionType::Timestamp(d as i64 * day)),
+                    _ => fail()
+                }
+            },
+            _ => Result::Err(format!("Unknown type: {}.", t))
+        }
+    }
+    pub fn make_func(name:&str, args:&[ExpressionType]) -> Result<ExpressionType, String> {
+        //内置的标量函数。除coalesce与concat外，有参数为null时结果为null。
+        let (least, most) = match name {
+            "upper" | "lower" | "trim" | "length" | "abs" => (1, 1),
+            "round" => (1, 2),
+            "substr" => (2, 3),
+            "cast" => (2, 2),
+            "coalesce" | "concat" => (1, usize::max_value()),
+            _ => {return Result::Err(format!("Unknown function: {}.", name));}
+        };
+        if args.len() < least || args.len() > most {
+            return Result::Err(if least == most {format!("Function {} needs {} argument(s).", name, least)}
+                else if most == usize::max_value() {format!("Function {} needs at least {} argument(s).", name, least)}
+                else{format!("Function {} needs {} to {} arguments.", name, least, most)});
+        }
+        let wrong = || Result::Err(format!("Wrong param type for function {}.", name));
+        if name != "coalesce" && name != "concat" && args.iter().any(|a|a.is_null()) {
+            return Result::Ok(ExpressionType::Null);
+        }
+        match name {
+            "upper" | "lower" | "trim" => match &args[0] {
+                ExpressionType::Str(s) => Result::Ok(ExpressionType::Str(match name {
+                    "upper" => s.to_uppercase(),
+                    "lower" => s.to_lowercase(),
+                    _ => s.trim().to_string()
+                })),
+                _ => wrong()
+            },
+            "length" => match &args[0] {
+                ExpressionType::Str(s) => Result::Ok(ExpressionType::Integer(s.chars().count() as i64)),
+                _ => wrong()
+            },
+            "substr" => {
+                //substr(s, 起始位置[, 长度])，按字符计数，位置从1开始。起始位置小于1时，之前的部分也计入长度。
+                let chars:Vec<char> = match &args[0] {
+                    ExpressionType::Str(s) => s.chars().collect(),
+                    _ => {return wrong();}
+                };
+                let start = match &args[1] {&ExpressionType::Integer(i) => i - 1, _ => {return wrong();}};
+                let end = match args.get(2) {
+                    Option::None => chars.len() as i64,
+                    Option::Some(&ExpressionType::Integer(l)) if l >= 0 => start.saturating_add(l),
+                    _ => {return wrong();}
+                };
+                let clamp = |i:i64| if i < 0 {0}else if i > chars.len() as i64 {chars.len()}else{i as usize};
+                let (begin, end) = (clamp(start), clamp(end));
+                Result::Ok(ExpressionType::Str(if begin < end {chars[begin..end].iter().collect()}else{String::new()}))
+            },
+            "abs" => match &args[0] {
+                &ExpressionType::Integer(i) => match i.checked_abs() {
+                    Option::Some(i) => Result::Ok(ExpressionType::Integer(i)),
+                    Option::None => Result::Err("Integer value is out of range.".to_string())
+                },
+                &ExpressionType::Float(f) => Result::Ok(ExpressionType::Float(f.abs())),
+                &ExpressionType::Decimal(v, s) => Result::Ok(ExpressionType::Decimal(v.abs(), s)),
+                _ => wrong()
+            },
+            "round" => {
+                //round(x[, n])保留n位小数，n为负数时舍入到整十、整百……，0.5向远离0的方向进位。
+                let n = match args.get(1) {
+                    Option::None => 0,
+                    Option::Some(&ExpressionType::Integer(n)) if n.abs() <= decimal::MAX_PRECISION as i64 => n,
+                    _ => {return wrong();}
+                };
+                let round_int = |v:i64, n:i64| -> Option<i64> {
+                    //把整数看作有-n位小数的定点小数，舍去小数部分后再还原。
+                    if n >= 0 {Option::Some(v)}
+                    else{decimal::rescale(v, (-n) as usize, 0).and_then(|v|decimal::rescale(v, 0, (-n) as usize))}
+                };
+                match &args[0] {
+                    &ExpressionType::Integer(i) => match round_int(i, n) {
+                        Option::Some(i) => Result::Ok(ExpressionType::Integer(i)),
+                        Option::None => Result::Err("Integer value is out of range.".to_string())
+                    },
+                    &ExpressionType::Float(f) => {
+                        let p = 10f64.powi(n as i32);
+                        Result::Ok(ExpressionType::Float((f * p).round() / p))
+                    },
+                    &ExpressionType::Decimal(v, s) => {
+                        if n >= s as i64 {return Result::Ok(ExpressionType::Decimal(v, s));}
+                        let to = if n > 0 {n as usize}else{0};
+                        match decimal::rescale(v, s, to).and_then(|v|round_int(v, n)) {
+                            Option::Some(v) => Result::Ok(ExpressionType::Decimal(v, to)),
+                            Option::None => Result::Err("Decimal value is out of range.".to_string())
+                        }
+                    },
+                    _ => wrong()
+                }
+            },
+            "coalesce" => Result::Ok(match args.iter().find(|a|!a.is_null()) {
+                Option::Some(a) => a.copy(),
+                Option::None => ExpressionType::Null
+            }),
+            "concat" => {
+                //null参数被忽略。
+                let mut ret = String::new();
+                for a in args.iter() {
+                    match a {
+                        &ExpressionType::Null => {},
+                        &ExpressionType::Integer(_) | &ExpressionType::Float(_) | &ExpressionType::Str(_) | &ExpressionType::Bool(_) |
+                        &ExpressionType::Date(_) | &ExpressionType::Time(_) | &ExpressionType::Timestamp(_) | &ExpressionType::Decimal(..) => {
+                            ret.push_str(&a.to_string());
+                        },
+                        _ => {return wrong();}
+                    }
+                }
+                Result::Ok(ExpressionType::Str(ret))
+            },
+            _ => match &args[1] {
+                ExpressionType::Str(t) => args[0].cast(t),
+                _ => wrong()
+            }
+        }
+    }
 }
 pub struct Expression {
     pub li:Vec<ExpressionType>,
@@ -360,12 +800,12 @@ pub struct Expression {
 impl Expression {
     fn lv(s:&str) -> i32 {
         let mut map:HashMap<i32, Vec<&str>> = HashMap::new();
-        map.insert(0, ["(", ")"].to_vec());
+        map.insert(0, ["(", ")", "between?", "notbetween?"].to_vec());
         map.insert(999, ["."].to_vec());
         map.insert(10, ["^"].to_vec());
         map.insert(9, ["*", "/", "%"].to_vec());
         map.insert(8,["+", "-"].to_vec());
-        map.insert(7, [">", "<", ">=" ,"<=", "=", "!="].to_vec());
+        map.insert(7, [">", "<", ">=" ,"<=", "=", "!=", "isnull", "isnotnull", "between", "notbetween"].to_vec());
         map.insert(6, ["&&", "||"].to_vec());
         map.insert(4, ["!"].to_vec());
         for (i, v) in map {
@@ -377,8 +817,116 @@ impl Expression {
         }
         return -1;
     }
+    fn pop_higher(current:i32, stack:&mut Vec<ExpressionType>, ret:&mut Vec<ExpressionType>) {
+        //将栈顶优先级不低于current的符号依次出栈到输出序列。
+        loop {
+            let mut out = false;
+            match stack.last() {
+                None => {break;}
+                Some(t) => {
+                    if let ExpressionType::Signal(sign) = t {
+                        if current <= Expression::lv(sign) {
+                            out = true;
+                        } else {break;}
+                    }
+                } 
+            }
+            if out {
+                ret.push(stack.pop().unwrap());
+            }
+        }
+    }
+    pub fn arity_of(s:&str) -> usize {
+        //运算符需要的参数数目。
+        match s {
+            "!" | "isnull" | "isnotnull" => 1,
+            "between" | "notbetween" => 3,
+            _ => 2
+        }
+    }
+    pub fn find_end(li:&[DfaWord], begin:usize, comma:bool) -> usize {
+        //从begin开始寻找表达式的终点：括号外的第一个不属于表达式的关键字，comma为true时也包括括号外的逗号。
+        //括号内的内容全部属于表达式。
+        let mut depth = 0;
+        let mut i = begin;
+        while i < li.len() {
+            match li[i] {
+                DfaWord::Signal(ref s) if s == "(" => {depth += 1;},
+                DfaWord::Signal(ref s) if s == ")"
+                    && depth > 0 => {depth -= 1;},
+                DfaWord::Signal(ref s) if s == "," && comma && depth == 0 => {return i;},
+                DfaWord::Kword(ref k) if depth == 0 && !Expression::is_kword(k) => {return i;},
+                _ => {}
+            }
+            i += 1;
+        }
+        li.len()
+    }
+    fn is_signal(w:Option<&DfaWord>, signal:&str) -> bool {
+        if let Option::Some(DfaWord::Signal(s)) = w {s == signal}else{false}
+    }
+    fn is_kword_at(li:&[DfaWord], index:usize, kwords:&[&str]) -> bool {
+        if let Option::Some(DfaWord::Kword(k)) = li.get(index) {kwords.contains(&k.as_str())}else{false}
+    }
+    fn find_between(stack:&[ExpressionType]) -> Option<usize> {
+        //在当前括号层内查找还没有遇到and的between。
+        for (i, e) in stack.iter().enumerate().rev() {
+            if let ExpressionType::Signal(s) = e {
+                if s == "(" {return Option::None;}
+                if s == "between?" || s == "notbetween?" {return Option::Some(i);}
+            }
+        }
+        Option::None
+    }
+    pub fn find_close(li:&[DfaWord], open:usize) -> usize {
+        //查找与open处的左括号配对的右括号。没有时返回末尾。
+        let mut depth = 0;
+        for i in open..li.len() {
+            match li[i] {
+                DfaWord::Signal(ref s) if s == "(" => {depth += 1;},
+                DfaWord::Signal(ref s) if s == ")" => {
+                    depth -= 1;
+                    if depth == 0 {return i;}
+                },
+                _ => {}
+            }
+        }
+        li.len()
+    }
+    pub fn sub_selects(&self) -> Vec<&SelectSyntax> {
+        //表达式中直接出现的子查询。
+        let mut ret = Vec::new();
+        for e in self.li.iter() {
+            if let ExpressionType::Select(sub) = e {ret.push(&**sub);}
+        }
+        ret
+    }
+    fn is_call(li:&[DfaWord], index:usize) -> bool {
+        //index指向变量之后的位置。
+        Expression::is_signal(li.get(index), "(")
+    }
+    fn is_negative_number(li:&[DfaWord], pos:usize) -> bool {
+        //pos处的负号之后是数字，并且负号前面没有操作数：在表达式开头，或者跟在左括号、逗号、运算符和关键字之后。
+        match li.get(pos + 1) {
+            Option::Some(DfaWord::Integer(_)) | Option::Some(DfaWord::Float(_)) => {},
+            _ => {return false;}
+        }
+        if pos == 0 {return true;}
+        match li[pos - 1] {
+            DfaWord::Signal(ref s) => s != ")",
+            DfaWord::Kword(ref k) => k != "null",
+            _ => false
+        }
+    }
+    fn is_kword(k:&str) -> bool {
+        //可以出现在表达式内部的关键字。
+        match k {
+            "is" | "null" | "between" | "in" => true,
+            _ => false
+        }
+    }
     pub fn empty() -> Self {
-        Expression::new(&vec![])
+        Expression{li: vec![], setence: String::new()}
     }
     pub fn new_single(s:&str) -> Self {
         Expression::new(&vec![DfaWord::Var(s.to_string())])
@@ -387,15 +935,52 @@ impl Expression {
         Expression::new(&vec![DfaWord::Signal("*".to_string())])
     }
     pub fn new(li:&[DfaWord]) -> Self {
+        //不会出错的表达式的构造。其中的子查询有语法错误时得到空的表达式。
+        match Expression::parse(li) {
+            Result::Ok(e) => e,
+            Result::Err(_) => Expression::empty()
+        }
+    }
+    pub fn parse(li:&[DfaWord]) -> Result<Self, usize> {
         //构造一个没有逗号和分号分割的表达式。传入表达式的中缀模式。
+        //其中的子查询有语法错误时，返回出错的单词在li中的位置。
         //使用中转后模式。
         //特殊处理点语法。
         let mut ret:Vec<ExpressionType> = Vec::new();
         let mut stack:Vec<ExpressionType> = Vec::new();
+        let mut args:Vec<usize> = Vec::new(); //正在处理的各层函数调用已有的参数数目。
+        let mut negate = false; //not between/not in
         //开始。
-        for i in li {
+        let mut index = 0;
+        while index < li.len() {
+            let i = &li[index];
+            index += 1;
             //首先区分符号与计算对象。
             match i {
+                DfaWord::Signal(s) if s == "!" && Expression::is_kword_at(li, index, &["between", "in"]) => {
+                    //not between/not in作为一个整体的运算符。
+                    negate = true;
+                },
+                DfaWord::Signal(s) if s == "&&" && Expression::find_between(&stack).is_some() => {
+                    //between的上下界之间的and：收栈到between为止，between的两个界已经齐了。
+                    let pos = Expression::find_between(&stack).unwrap();
+                    while stack.len() > pos + 1 {
+                        ret.push(stack.pop().unwrap());
+                    }
+                    let oper = if let Option::Some(ExpressionType::Signal(s)) = stack.pop() {
+                        s.trim_end_matches('?').to_string()
+                    }else{"between".to_string()};
+                    stack.push(ExpressionType::Signal(oper));
+                },
+                DfaWord::Signal(s) if s == "-" && Expression::is_negative_number(li, index - 1) => {
+                    //前面没有操作数的负号与其后的数字一起作为负数的常量，例如abs(-2)、round(x, -2)。
+                    match li[index] {
+                        DfaWord::Integer(i) => ret.push(ExpressionType::Integer(-i)),
+                        DfaWord::Float(f) => ret.push(ExpressionType::Float(-f)),
+                        _ => {}
+                    }
+                    index += 1;
+                },
                 &DfaWord::Signal(ref s) => {
                     //遇到一个符号时，需要与栈顶作比较。当新符号Lv<=栈顶Lv时，需要出栈。
                     if s == "(" {
@@ -414,28 +999,153 @@ impl Expression {
                                 break;
                             }
                         }
-                    }else{
-                        let current = Expression::lv(s);
-                        loop {
-                            let mut out = false;
-                            match stack.last() {
-                                None => {break;}
-                                Some(ref t) => {
-                                    if let &ExpressionType::Signal(ref sign) = *t {
-                                        if current <= Expression::lv(sign) {
-                                            out = true;
-                                        } else {break;}
-                                    }
-                                } 
-                            }
-                            if out {
-                                ret.push(stack.pop().unwrap());
+                        //括号属于函数调用时，函数跟在参数之后输出。
+                        let is_func = if let Option::Some(&ExpressionType::Func(..)) = stack.last() {true}else{false};
+                        if is_func {
+                            if let Option::Some(ExpressionType::Func(name, _)) = stack.pop() {
+                                ret.push(ExpressionType::Func(name, args.pop().unwrap_or(0)));
                             }
                         }
+                    }else if s == "," && !args.is_empty() {
+                        //函数参数之间的逗号：收栈到左括号为止。
+                        loop {
+                            let is_left = match stack.last() {
+                                Option::Some(ExpressionType::Signal(s)) => s == "(",
+                                Option::Some(_) => false,
+                                Option::None => true
+                            };
+                            if is_left {break;}
+                            ret.push(stack.pop().unwrap());
+                        }
+                        if let Option::Some(n) = args.last_mut() {*n += 1;}
+                    }else if s == "!" {
+                        //前缀的单目运算符作用在它后面的操作数上，入栈时不需要让前面的运算符出栈。
+                        stack.push(ExpressionType::new(i));
+                    }else{
+                        Expression::pop_higher(Expression::lv(s), &mut stack, &mut ret);
                         stack.push(ExpressionType::new(i));    
                     }  
                 },
+                DfaWord::Kword(k) if k == "is" => {
+                    //is [not] null是后缀的单目运算符，作用在它前面已经输出的操作数上，因此直接输出。
+                    let oper = match (li.get(index), li.get(index + 1)) {
+                        (Option::Some(DfaWord::Kword(n)), _) if n == "null" => {index += 1; "isnull"},
+                        (Option::Some(DfaWord::Signal(s)), Option::Some(DfaWord::Kword(n))) if s == "!" && n == "null" => {
+                            index += 2; "isnotnull"
+                        },
+                        _ => {continue;}
+                    };
+                    Expression::pop_higher(Expression::lv(oper), &mut stack, &mut ret);
+                    ret.push(ExpressionType::Signal(oper.to_string()));
+                },
+                DfaWord::Kword(k) if k == "null" => {
+                    ret.push(ExpressionType::Null);
+                },
+                DfaWord::Kword(k) if k == "between" => {
+                    //between在遇到and之前是一个括号一样的标记，之后成为三目运算符。
+                    let oper = if negate {"notbetween"}else{"between"};
+                    negate = false;
+                    Expression::pop_higher(Expression::lv(oper), &mut stack, &mut ret);
+                    stack.push(ExpressionType::Signal(format!("{}?", oper)));
+                },
+                DfaWord::Kword(k) if k == "in" => {
+                    //in (列表)或in (子查询)。列表中的各项和子查询直接输出，最后输出带有参数数目的in。
+                    let oper = if negate {"notin"}else{"in"};
+                    negate = false;
+                    if !Expression::is_signal(li.get(index), "(") {continue;}
+                    let close = Expression::find_close(li, index);
+                    Expression::pop_higher(Expression::lv("="), &mut stack, &mut ret);
+                    let inner = &li[index + 1..close];
+                    let is_select = if let Option::Some(DfaWord::Kword(k)) = inner.first() {k == "select"}else{false};
+                    if is_select {
+                        let mut tree = SelectTree::new();
+                        let sub = tree.construct(&inner[1..]);
+                        if tree.get_error().1 != EnumError::None {
+                            return Result::Err(index + 2 + tree.get_error().0 as usize);
+                        }
+                        ret.push(ExpressionType::Select(Box::new(sub)));
+                        ret.push(ExpressionType::Func(oper.to_string(), 2));
+                    }else{
+                        let mut count = 1;
+                        let mut begin = 0;
+                        while begin < inner.len() {
+                            let end = Expression::find_end(inner, begin, true);
+                            let item = match Expression::parse(&inner[begin..end]) {
+                                Result::Ok(e) => e,
+                                Result::Err(pos) => {return Result::Err(index + 1 + begin + pos);}
+                            };
+                            for e in item.li.into_iter() {ret.push(e);}
+                            count += 1;
+                            begin = end + 1;
+                        }
+                        ret.push(ExpressionType::Func(oper.to_string(), count));
+                    }
+                    index = close + 1;
+                },
+                DfaWord::Kword(k) if k == "select" && index >= 2 && Expression::is_signal(li.get(index - 2), "(") => {
+                    //括号中的子查询：标量子查询或exists的参数。子查询作为一个操作数输出，右括号照常处理。
+                    let close = Expression::find_close(li, index - 2);
+                    let mut tree = SelectTree::new();
+                    let sub = tree.construct(&li[index..close]);
+                    if tree.get_error().1 != EnumError::None {
+                        return Result::Err(index + tree.get_error().0 as usize);
+                    }
+                    ret.push(ExpressionType::Select(Box::new(sub)));
+                    index = close;
+                },
                 &DfaWord::End | &DfaWord::Kword(_) => {},
+                DfaWord::Var(name) if name.to_lowercase() == "cast" && Expression::is_call(li, index) => {
+                    //cast(x as 类型)：先输出x，再把类型写成与保存字段类型相同的字符串输出，最后输出cast。
+                    let close = Expression::find_close(li, index);
+                    let inner = &li[index + 1..close];
+                    let pos = inner.iter().rposition(|w|if let DfaWord::Kword(k) = w {k == "as"}else{false});
+                    if let Option::Some(pos) = pos {
+                        let item = match Expression::parse(&inner[..pos]) {
+                            Result::Ok(e) => e,
+                            Result::Err(p) => {return Result::Err(index + 1 + p);}
+                        };
+                        for e in item.li.into_iter() {ret.push(e);}
+                        let mut t = vec![];
+                        for w in inner[pos + 1..].iter() {
+                            if let Option::Some(name) = type_name(w) {
+                                t.push(name);
+                                continue;
+                            }
+                            match w {
+                                DfaWord::Kword(k) => t.push(k.to_string()),
+                                DfaWord::Var(v) => t.push(v.to_lowercase()),
+                                &DfaWord::Integer(i) => t.push(i.to_string()),
+                                _ => {}
+                            }
+                        }
+                        ret.push(ExpressionType::Str(t.join(":")));
+                        ret.push(ExpressionType::Func("cast".to_string(), 2));
+                    }else{
+                        //没有as时交给计算阶段报告参数数目错误。
+                        ret.push(ExpressionType::Func("cast".to_string(), 0));
+                    }
+                    index = close + 1;
+                },
+                DfaWord::Var(name) if Expression::is_call(li, index) => {
+                    //变量后紧跟左括号时是函数调用。count(*)表示计数所有记录，没有参数。
+                    let mut name = name.to_lowercase();
+                    if Expression::is_signal(li.get(index + 1), "*") && Expression::is_signal(li.get(index + 2), ")") {
+                        index += 3;
+                        ret.push(ExpressionType::Func(name, 0));
+                    }else{
+                        //聚合函数的参数前可以有distinct或all。带distinct的函数名写成"count distinct"，只对不同的值计算。
+                        if Expression::is_kword_at(li, index + 1, &["distinct", "all"]) {
+                            if !Expression::is_aggregate_name(&name) {return Result::Err(index + 1);}
+                            if Expression::is_kword_at(li, index + 1, &["distinct"]) {name = format!("{} distinct", name);}
+                            index += 1;
+                        }
+                        let empty = Expression::is_signal(li.get(index + 1), ")");
+                        index += 1;
+                        stack.push(ExpressionType::Func(name, 0));
+                        stack.push(ExpressionType::Signal("(".to_string()));
+                        args.push(if empty {0}else{1});
+                    }
+                },
                 _ => {//var,int,float,str
                     ret.push(ExpressionType::new(&i));
                 }
@@ -477,10 +1187,10 @@ impl Expression {
         for i in li.iter() {
             set+=i.to_code_string().as_str();
         }
-        Self{
+        Result::Ok(Self{
             li:ret,
             setence: set
-        }
+        })
     }
     pub fn new_array(li:&[DfaWord]) -> Vec<Self> {
         let mut v:Vec<Self> = Vec::new();
@@ -515,6 +1225,72 @@ impl Expression {
         }
         Self{li:li, setence: self.setence.to_string()}
     }
+    pub fn arity(e:&ExpressionType) -> usize {
+        //后缀式中每个元素需要从栈中取出的参数数目。
+        match e {
+            ExpressionType::Signal(s) => Expression::arity_of(s),
+            &ExpressionType::Func(_, argc) => argc,
+            _ => 0
+        }
+    }
+    pub fn is_aggregate_name(name:&str) -> bool {
+        match name.trim_end_matches(" distinct") {
+            "count" | "sum" | "avg" | "min" | "max" => true,
+            _ => false
+        }
+    }
+    pub fn is_scalar_name(name:&str) -> bool {
+        match name {
+            "upper" | "lower" | "length" | "substr" | "trim" | "abs" | "round" | "coalesce" | "cast" | "concat" => true,
+            _ => false
+        }
+    }
+    pub fn has_aggregate(&self) -> bool {
+        self.li.iter().any(|e|e.is_aggregate())
+    }
+    pub fn subtree_starts(&self) -> (Vec<usize>, usize) {
+        //求出后缀式中每一个元素所在子树的起点。同时返回子树根的数目，完整的表达式只有一个根。
+        let mut starts:Vec<usize> = Vec::with_capacity(self.li.len());
+        let mut stack:Vec<usize> = Vec::new();
+        for (i, e) in self.li.iter().enumerate() {
+            let mut start = i;
+            for _ in 0..Expression::arity(e) {
+                if let Option::Some(s) = stack.pop() {start = s;}
+            }
+            stack.push(start);
+            starts.push(start);
+        }
+        (starts, stack.len())
+    }
+    pub fn split_and(&self) -> Vec<Expression> {
+        //将顶层由&&连接的条件拆分成多个子表达式。
+        //对后缀式求出每一个元素所在子树的起点，根节点为&&时递归拆分左右两棵子树。
+        let (starts, roots) = self.subtree_starts();
+        let mut ret = Vec::new();
+        if roots != 1 {
+            //不是一个完整的表达式，不做拆分。
+            if !self.li.is_empty() {ret.push(self.copy());}
+            return ret;
+        }
+        let mut ranges = vec![(0, self.li.len())];
+        while let Option::Some((begin, end)) = ranges.pop() {
+            let root = &self.li[end - 1];
+            let is_and = if let ExpressionType::Signal(s) = root {s == "&&"}else{false};
+            if is_and && end - begin >= 3 {
+                //右子树以end-2为根，其起点即为左右子树的分界。
+                let mid = starts[end - 2];
+                ranges.push((mid, end - 1));
+                ranges.push((begin, mid));
+            }else{
+                let mut li = vec![];
+                for i in self.li[begin..end].iter() {li.push(i.copy());}
+                let mut setence = String::new();
+                for i in li.iter() {setence += (i.to_string() + " ").as_str();}
+                ret.push(Expression{li, setence});
+            }
+        }
+        ret
+    }
 }
 //= 组合结构体 =============================================
 pub enum ColSyntax {
@@ -533,9 +1309,17 @@ pub enum ColSyntax {
     CreateUser(CreateUserSyntax),
     AlterUser(AlterUserSyntax),
     DropUser(DropUserSyntax),
+    CreateRole(CreateRoleSyntax),
+    DropRole(DropRoleSyntax),
     Grant(GrantSyntax),
     CreateView(CreateViewSyntax),
-    DropView(DropViewSyntax)
+    DropView(DropViewSyntax),
+    CreateIndex(CreateIndexSyntax),
+    DropIndex(DropIndexSyntax),
+    Vacuum(VacuumSyntax),
+    Begin,
+    Commit,
+    Rollback
 }
 
 //= 接口 ==========================================================================
@@ -555,9 +1339,14 @@ impl Syntax for EmptySyntax {
 pub struct SelectSyntax {
     pub distinct:bool,
     pub froms:HashMap<String, Switch<String, SelectSyntax>>, //来源的别名:实际列
+    pub joins:Vec<JoinSyntax>, //按顺序连接到来源上的表
     pub goals: Vec<(String, Expression)>, //目标列表，包括别名。
     pub wheres:Expression, //条件表达式
-    pub orders:Vec<(String, bool)> //排序序列
+    pub groups:Vec<Expression>, //分组表达式
+    pub havings:Expression, //分组的条件表达式
+    pub orders:Vec<(String, bool)>, //排序序列
+    pub limit:Option<usize>, //最多返回的行数
+    pub offset:usize //跳过的行数
 }
 impl SelectSyntax {
     pub fn copy(&self) -> Self {
@@ -580,8 +1369,13 @@ impl SelectSyntax {
             distinct: self.distinct,
             wheres: self.wheres.copy(),
             froms: froms,
+            joins: self.joins.iter().map(|j|j.copy()).collect(),
             goals: goals,
-            orders: orders
+            groups: self.groups.iter().map(|g|g.copy()).collect(),
+            havings: self.havings.copy(),
+            orders,
+            limit: self.limit,
+            offset: self.offset
         }
     }
     pub fn get_setence(&self) -> String {
@@ -603,9 +1397,22 @@ impl SelectSyntax {
                 if i < len - 1 {ret += ", "}else{ret += " ";}
             }
         }
+        for j in self.joins.iter() {
+            ret += format!("\n{} JOIN {} AS {}", j.kind.to_uppercase(), j.table, j.name).as_str();
+            if !j.on.li.is_empty() {
+                ret += format!(" ON {}", j.on.setence).as_str();
+            }
+        }
         if self.wheres.li.len() > 0 {
             ret += format!("\nWHERE {}", self.wheres.setence).as_str();
         }
+        if !self.groups.is_empty() {
+            let groups:Vec<String> = self.groups.iter().map(|g|g.setence.to_string()).collect();
+            ret += format!("\nGROUP BY {}", groups.join(", ")).as_str();
+        }
+        if !self.havings.li.is_empty() {
+            ret += format!("\nHAVING {}", self.havings.setence).as_str();
+        }
         if !self.orders.is_empty() {
             ret += "\nORDER BY ";
             let len = self.froms.len();
@@ -613,6 +1420,12 @@ impl SelectSyntax {
                 ret += format!("{}{}{} ", if b {""}else{"-"}, name, if i < len - 1 {","}else{""}).as_str();
             }
         }
+        if let Option::Some(limit) = self.limit {
+            ret += format!("\nLIMIT {}", limit).as_str();
+        }
+        if self.offset > 0 {
+            ret += format!("\nOFFSET {}", self.offset).as_str();
+        }
         ret
     }
 }
@@ -634,11 +1447,33 @@ impl Syntax for SelectSyntax {
         format!("dist:{}\nfroms:\n{}goals:\n{}", dist, from, goal)
     }
 }
+pub struct JoinSyntax {
+    pub kind: String, //inner/left/right
+    pub name: String, //别名
+    pub table: String, //实际的表名
+    pub on: Expression //连接条件
+}
+impl JoinSyntax {
+    pub fn new(kind:&str) -> Self {Self{
+        kind: kind.to_string(),
+        name: String::new(),
+        table: String::new(),
+        on: Expression::empty()
+    }}
+    pub fn copy(&self) -> Self {Self{
+        kind: self.kind.to_string(),
+        name: self.name.to_string(),
+        table: self.table.to_string(),
+        on: self.on.copy()
+    }}
+}
 //= Insert语法树 ===========================================
 pub struct InsertSyntax {
     pub table_name:String,
     pub has_head:bool,
-    pub values: Vec<HashMap<String, DfaWord>>
+    pub values: Vec<HashMap<String, DfaWord>>,
+    pub columns: Vec<String>, //给出的列名，没有时为空
+    pub select: Option<SelectSyntax> //insert ... select的查询，此时values为空
 }
 impl Syntax for InsertSyntax {
     fn get_type(&self) -> String {"insert".to_string()}
@@ -655,7 +1490,7 @@ impl Syntax for DeleteSyntax {
 pub struct UpdateSyntax {
     pub table_name: String,
     pub wheres: Expression,
-    pub sets: HashMap<String, DfaWord>
+    pub sets: HashMap<String, Expression> //字段名:新值的表达式，按每条记录求值
 }
 impl Syntax for UpdateSyntax {
     fn get_type(&self) -> String {"update".to_string()}
@@ -664,7 +1499,10 @@ impl Syntax for UpdateSyntax {
 pub struct CreateTableSyntax {
     pub name: String,
     pub fields: Vec<TableFieldSyntax>,
-    pub foreigns: Vec<TableForeignSyntax>
+    pub foreigns: Vec<TableForeignSyntax>,
+    pub checks: Vec<(String, Expression)>, //check约束的名称与条件
+    pub primary: Vec<String>, //表级的primary key (a, b)
+    pub uniques: Vec<Vec<String>> //表级的unique (a, b)
 }
 pub struct TableFieldSyntax {
     pub name:String,
@@ -700,7 +1538,10 @@ pub struct AlterTableSyntax {
     pub name: String,
     pub adds: Vec<TableFieldSyntax>,
     pub alters: Vec<TableFieldSyntax>,
-    pub drops: Vec<String>
+    pub drops: Vec<String>,
+    pub checks: Vec<(String, Expression)>, //新增的check约束
+    pub drop_checks: Vec<String>, //删除的check约束名
+    pub uniques: Vec<Vec<String>> //新增的unique (a, b)
 }
 impl Syntax for AlterTableSyntax {
     fn get_type(&self) -> String {"alter_table".to_string()}
@@ -802,12 +1643,39 @@ impl DropUserSyntax {
         username: user.to_string()
     }}
 }
+//= create role 语法树 ===========
+pub struct CreateRoleSyntax {
+    pub name: String
+}
+impl Syntax for CreateRoleSyntax {
+    fn get_type(&self) -> String {"create_role".to_string()}
+}
+impl CreateRoleSyntax {
+    pub fn new(name:&str) -> Self{Self{
+        name: name.to_string()
+    }}
+}
+//= drop role 语法树 =============
+pub struct DropRoleSyntax {
+    pub name: String
+}
+impl Syntax for DropRoleSyntax {
+    fn get_type(&self) -> String {"drop_role".to_string()}
+}
+impl DropRoleSyntax {
+    pub fn new(name:&str) -> Self{Self{
+        name: name.to_string()
+    }}
+}
 //= grant 语法树 =================
 pub struct GrantSyntax {
     pub grants: Vec<String>,
     pub all: bool,
     pub objects:Vec<(String, String)>,
+    pub roles: Vec<String>,  //grant role to user时授予的角色，此时没有权限和对象
     pub users: Vec<String>,
+    pub grant_option: bool,  //with grant option
+    pub cascade: bool,  //revoke ... cascade
     pub is_grant: bool
 }
 impl Syntax for GrantSyntax {
@@ -838,4 +1706,44 @@ impl DropViewSyntax {
 }
 impl Syntax for DropViewSyntax {
     fn get_type(&self) -> String {"dropview".to_string()}
-}
\ No newline at end of file
+}
+//= create index 语法树 ============
+pub struct CreateIndexSyntax {
+    pub name: String,
+    pub table: String,
+    pub field: String
+}
+impl CreateIndexSyntax {
+    pub fn new(name:&str, table:&str, field:&str) -> Self {Self{
+        name: name.to_string(),
+        table: table.to_string(),
+        field: field.to_string()
+    }}
+}
+impl Syntax for CreateIndexSyntax {
+    fn get_type(&self) -> String {"createindex".to_string()}
+}
+//= drop index 语法树 ===============
+pub struct DropIndexSyntax {
+    pub name: String
+}
+impl DropIndexSyntax {
+    pub fn new(name:&str) -> Self{Self{
+        name: name.to_string()
+    }}
+}
+impl Syntax for DropIndexSyntax {
+    fn get_type(&self) -> String {"dropindex".to_string()}
+}
+//= vacuum 语法树 ==================
+pub struct VacuumSyntax {
+    pub table: Option<String>  //为None时整理数据库中的全部表
+}
+impl VacuumSyntax {
+    pub fn new(table:Option<&str>) -> Self{Self{
+        table: table.map(|t|t.to_string())
+    }}
+}
+impl Syntax for VacuumSyntax {
+    fn get_type(&self) -> String {"vacuum".to_string()}
+}
@@ -5,7 +5,7 @@ use super::structures::{
     Syntax, EmptySyntax, ColSyntax, HelpSyntax,
     UseSyntax, DropTableSyntax, CreateDatabaseSyntax, DropDatabaseSyntax,
     CreateUserSyntax, AlterUserSyntax, DropUserSyntax, GrantSyntax,
-    CreateViewSyntax, DropViewSyntax
+    CreateRoleSyntax, DropRoleSyntax, CreateViewSyntax, DropViewSyntax, CreateIndexSyntax, DropIndexSyntax, VacuumSyntax
 };
 use super::trees::{DfaNode, AResult, EnumError, EnumResult, Tree};
 use super::selecttree::{SelectTree};
@@ -203,6 +203,35 @@ impl PublicTree {
                                         return ColSyntax::DropView(DropViewSyntax::new(name));
                                     }
                                 },
+                                "createindex" => {
+                                    //create index name on table(field)
+                                    let subvec = &li[i..slice_i];
+                                    if subvec.len() != 6 {
+                                        self.error = (subvec.len() as i32 + 1, EnumError::SyntaxError);
+                                        return ColSyntax::None;
+                                    }
+                                    if let (DfaWord::Var(name), DfaWord::Kword(k), DfaWord::Var(table),
+                                            DfaWord::Signal(l), DfaWord::Var(field), DfaWord::Signal(r))
+                                        = (&subvec[0], &subvec[1], &subvec[2], &subvec[3], &subvec[4], &subvec[5]) {
+                                        if k == "on" && l == "(" && r == ")" {
+                                            return ColSyntax::CreateIndex(CreateIndexSyntax::new(name, table, field));
+                                        }
+                                    }
+                                    self.error = (1, EnumError::SyntaxError);
+                                    return ColSyntax::None;
+                                },
+                                "dropindex" => {
+                                    let subvec = &li[i..slice_i];
+                                    if subvec.len() != 1 {
+                                        self.error = (subvec.len() as i32 + 1, EnumError::SyntaxError);
+                                        return ColSyntax::None;
+                                    }
+                                    if let DfaWord::Var(ref name) = subvec[0] {
+                                        return ColSyntax::DropIndex(DropIndexSyntax::new(name));
+                                    }
+                                    self.error = (1, EnumError::SyntaxError);
+                                    return ColSyntax::None;
+                                },
                                 "createdatabase" => {
                                     let subvec = &li[i..slice_i];
                                     if subvec.len() <= 0 {
@@ -292,6 +321,47 @@ impl PublicTree {
                                         }
                                     }
                                 },
+                                "createrole" | "droprole" => {
+                                    //create role name / drop role name
+                                    let subvec = &li[i..slice_i];
+                                    if subvec.len() == 1 {
+                                        if let DfaWord::Var(ref name) = subvec[0] {
+                                            return if goto == "createrole" {
+                                                ColSyntax::CreateRole(CreateRoleSyntax::new(name))
+                                            }else{
+                                                ColSyntax::DropRole(DropRoleSyntax::new(name))
+                                            };
+                                        }
+                                    }
+                                    self.error = (1, EnumError::SyntaxError);
+                                    return ColSyntax::None;
+                                },
+                                "vacuum" => {
+                                    //vacuum [table]
+                                    let subvec = &li[i..slice_i];
+                                    if subvec.is_empty() {
+                                        return ColSyntax::Vacuum(VacuumSyntax::new(Option::None));
+                                    }else if subvec.len() == 1 {
+                                        if let DfaWord::Var(ref name) = subvec[0] {
+                                            return ColSyntax::Vacuum(VacuumSyntax::new(Option::Some(name)));
+                                        }
+                                    }
+                                    self.error = (1, EnumError::SyntaxError);
+                                    return ColSyntax::None;
+                                },
+                                "begin" | "commit" | "rollback" => {
+                                    //事务控制语句不带任何参数。
+                                    let subvec = &li[i..slice_i];
+                                    if !subvec.is_empty() {
+                                        self.error = (i as i32 + 1, EnumError::SyntaxError);
+                                        return ColSyntax::None;
+                                    }
+                                    return match goto {
+                                        "begin" => ColSyntax::Begin,
+                                        "commit" => ColSyntax::Commit,
+                                        _ => ColSyntax::Rollback
+                                    };
+                                },
                                 "grant" | "revoke" => {
                                     let subvec = &li[i..slice_i];
                                     let mut tree = GrantTree::new(goto == "grant");
@@ -344,7 +414,8 @@ impl DfaNode for NodeStart {
                 let mut guide = "";
                 let mut error = EnumError::None;
                 match &word[..] {
-                    "use" | "select" | "update" | "delete" | "help" | "grant" | "revoke" => {tp = false;}
+                    "use" | "select" | "update" | "delete" | "help" | "grant" | "revoke" |
+                    "begin" | "commit" | "rollback" | "vacuum" => {tp = false;}
                     "create" => {guide = "Create";},
                     "alter" => {guide = "Alter";},
                     "drop" => {guide = "Drop";},
@@ -384,7 +455,7 @@ impl DfaNode for NodeCreate {
     fn analysis(&self, w:&DfaWord) -> AResult {
         match w {
            &DfaWord:: Kword(ref word) => match &word[..] {
-                "database" | "view" | "table" | "user" | "adminuser" => {return AResult {
+                "database" | "view" | "table" | "user" | "adminuser" | "index" => {AResult {
                     result: EnumResult::Accept,
                     action: vec!["goto".to_string(), ("create".to_string() + &word[..])],
                     guide: "".to_string(),
@@ -397,6 +468,12 @@ impl DfaNode for NodeCreate {
                     error: EnumError::SyntaxError
                 }}
             },
+            DfaWord::Var(word) if word == "role" => {AResult {  //role不是关键字，只在这里识别
+                result: EnumResult::Accept,
+                action: vec!["goto".to_string(), "createrole".to_string()],
+                guide: "".to_string(),
+                error: EnumError::None
+            }},
             _ => {return AResult {
                 result: EnumResult::Accept,
                 action: vec![],
@@ -448,7 +525,7 @@ impl DfaNode for NodeDrop {
     fn analysis(&self, w:&DfaWord) -> AResult {
         match w {
             &DfaWord::Kword(ref word) => match &word[..] {
-                "database" | "view" | "table" | "user" => {return AResult {
+                "database" | "view" | "table" | "user" | "index" => {AResult {
                     result: EnumResult::Accept,
                     action: vec!["goto".to_string(), ("drop".to_string() + &word[..])],
                     guide: "".to_string(),
@@ -461,6 +538,12 @@ impl DfaNode for NodeDrop {
                     error: EnumError::SyntaxError
                 }}
             },
+            DfaWord::Var(word) if word == "role" => {AResult {  //role不是关键字，只在这里识别
+                result: EnumResult::Accept,
+                action: vec!["goto".to_string(), "droprole".to_string()],
+                guide: "".to_string(),
+                error: EnumError::None
+            }},
             _ => {return AResult {
                 result: EnumResult::Accept,
                 action: vec![],
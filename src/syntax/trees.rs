@@ -43,4 +43,23 @@ impl PartialEq for EnumResult {
 pub trait Tree {
     fn construct(&mut self, li:&[DfaWord]) -> Box<Syntax>;
     fn get_error(&self) -> &(i32, EnumError);
-}
\ No newline at end of file
+}
+
+pub fn type_name(w:&DfaWord) -> Option<String> {
+    /*  字段类型的名称。较早的类型是关键字；date、time、timestamp、text、blob、decimal不是关键字，
+        只在需要类型的位置上识别，仍然可以用作表名或字段名。datetime与numeric是别名。
+    */
+    match w {
+        DfaWord::Kword(k) => match k.as_str() {
+            "integer" | "float" | "str" | "bool" => Option::Some(k.to_string()),
+            _ => Option::None
+        },
+        DfaWord::Var(v) => match v.as_str() {
+            "date" | "time" | "timestamp" | "text" | "blob" | "decimal" => Option::Some(v.to_string()),
+            "datetime" => Option::Some("timestamp".to_string()),
+            "numeric" => Option::Some("decimal".to_string()),
+            _ => Option::None
+        },
+        _ => Option::None
+    }
+}
@@ -91,14 +91,16 @@ impl UpdateTree {
                        },   
                        "value" => {
                            if last_column != "".to_string() {
-                                let v = res.action[2].to_string();
-                                sets.insert(last_column.to_string(), match res.action[1].as_str() {
-                                    "str" => DfaWord::Str(v.to_string()),
-                                    "integer" => DfaWord::Integer(v.parse().unwrap()),
-                                    "float" => DfaWord::Float(v.parse().unwrap()),
-                                    "bool" => DfaWord::Bool(v.parse().unwrap()),
-                                    _ => panic!("Wrong DfaWord Type.")
-                                });
+                                let begin_i:usize = res.action[1].parse().unwrap();
+                                let end_i:usize = res.action[2].parse().unwrap();
+                                let expression = match Expression::parse(&li[begin_i..end_i]) {
+                                    Result::Ok(e) => e,
+                                    Result::Err(pos) => {
+                                        self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                        break;
+                                    }
+                                };
+                                sets.insert(last_column.to_string(), expression);
                            }
                            last_column = "".to_string();
                        },
@@ -106,7 +108,13 @@ impl UpdateTree {
                             let begin_i:usize = res.action[1].parse().unwrap();
                             let end_i:usize = res.action[2].parse().unwrap();
                             // 调用expression的构造。
-                            wheres = Expression::new(&li[begin_i..end_i]);
+                            wheres = match Expression::parse(&li[begin_i..end_i]) {
+                                Result::Ok(e) => e,
+                                Result::Err(pos) => {
+                                    self.error = ((begin_i + pos) as i32, EnumError::SyntaxError);
+                                    break;
+                                }
+                            };
                         },
                        _ =>{}
                     }
@@ -236,39 +244,28 @@ impl DfaNode for NodeColumn {
 struct NodeEquals;
 impl DfaNode for NodeEquals {
     fn analysis(&self, w:&DfaWord) -> AResult {
-        let mut result = EnumResult::Accept;
-        let mut action = vec![];
-        let mut guide = "";
-        let mut error = EnumError::None;
-        match w {
-            &DfaWord::Str(ref s) => {
-               guide = "Value";
-               action = vec!["value".to_string(), "str".to_string(), s.to_string()];
-           },
-           &DfaWord::Integer(i) => {
-               guide = "Value";
-               action = vec!["value".to_string(), "integer".to_string(), i.to_string()];
-           },
-           &DfaWord::Float(f) => {
-               guide = "Value";
-               action = vec!["value".to_string(), "float".to_string(), f.to_string()];
-           },
-           &DfaWord::Bool(b) => {
-               guide = "Value";
-               action = vec!["value".to_string(), "bool".to_string(), b.to_string()];
-           },
-            _ => {
-                error = EnumError::SyntaxError;
+        panic!("Not Allowed");
+    }
+    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
+        //新值是一个表达式，在逗号或下一个子句的关键字处结束。
+        let i = Expression::find_end(w, begin as usize, true);
+        if i == begin as usize {
+            return AResult {
+                result: EnumResult::Accept,
+                guide: "".to_string(),
+                error: EnumError::SyntaxError,
+                action: vec![]
             }
         }
-        return AResult {
-            result: result, action: action, guide: guide.to_string(), error: error
+        *end = i as i32;
+        AResult {
+            result: EnumResult::Return,
+            guide: "Value".to_string(),
+            action: vec!["value".to_string(), begin.to_string(), i.to_string()],
+            error: EnumError::None
         }
     }
-    fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
-        panic!("Not Allowed");
-    }
-    fn allow_array(&self) -> bool {false}
+    fn allow_array(&self) -> bool {true}
 }
 
 struct NodeValue;
@@ -279,6 +276,9 @@ impl DfaNode for NodeValue {
         let mut guide = "";
         let mut error = EnumError::None;
         match w {
+            DfaWord::Signal(s) if s == "," => {
+                guide = "Set";
+            },
             &DfaWord::Kword(ref kword)=> {
                 guide = "Set";
                 result = EnumResult::Return;
@@ -303,18 +303,15 @@ impl DfaNode for NodeWhere {
         panic!("Not Allowed");
     }
     fn analysis_array(&self, w:&[DfaWord], begin:i32, end:&mut i32) -> AResult{
-        let mut i = begin as usize;
-        while i < w.len() {
-            if let DfaWord::Kword(_) = w[i] {
-                *end = i as i32;
-                return AResult {
-                    result: EnumResult::Return,
-                    guide: "Set".to_string(),
-                    error: EnumError::None,
-                    action: vec!["where".to_string(), begin.to_string(), i.to_string()]
-                }
-            }else{
-                i+=1;
+        //where的条件在下一个子句的关键字处结束。
+        let i = Expression::find_end(w, begin as usize, false);
+        if i < w.len() {
+            *end = i as i32;
+            return AResult {
+                result: EnumResult::Return,
+                guide: "Set".to_string(),
+                error: EnumError::None,
+                action: vec!["where".to_string(), begin.to_string(), i.to_string()]
             }
         }
         *end = w.len() as i32;
@@ -0,0 +1,85 @@
+#![allow(dead_code)]
+use std::env;
+use std::fs::{self, File};
+use std::io::{Write};
+use std::path::{PathBuf};
+use std::process;
+use dba::engine::engine::{Engine, Session};
+use dba::engine::database::{DResult};
+use dba::net::server::{parse_statement};
+// 测试使用的数据目录与语句执行
+
+pub struct TestDir {
+    pub dir: PathBuf
+}
+impl TestDir {
+    pub fn new(name:&str) -> Self {
+        //在临时目录中建立一个空的数据目录以及指向它的配置文件。
+        let dir = env::temp_dir().join(format!("dba-test-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("database")).unwrap();
+        let mut f = File::create(dir.join("dba.ini")).unwrap();
+        write!(f, "database={}/\nsystembase=system\nlock_timeout=300\n", dir.join("database").to_str().unwrap()).unwrap();
+        TestDir{dir: dir}
+    }
+    pub fn engine(&self) -> Engine {
+        //打开(或重新打开)数据目录上的引擎。
+        Engine::load(self.dir.join("dba.ini").to_str().unwrap()).unwrap()
+    }
+    pub fn path(&self, name:&str) -> PathBuf {
+        self.dir.join("database").join(name)
+    }
+}
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+pub fn root(engine:&Engine) -> Session<'_> {
+    //以root登录。第一次登录时先修改默认密码，之后的密码为rpw。
+    match engine.session("root", "rpw") {
+        Result::Ok(s) => s,
+        Result::Err(_) => {
+            let mut s = engine.session("root", "root").unwrap();
+            assert_eq!(run(&mut s, "alter user root with password \"rpw\""), "User root has been altered.");
+            s
+        }
+    }
+}
+pub fn session<'a>(engine:&'a Engine, sqls:&[&str]) -> Session<'a> {
+    //以root登录，并依次执行准备的语句，例如建立和使用数据库。
+    let mut s = root(engine);
+    run_all(&mut s, sqls);
+    s
+}
+pub fn run(session:&mut Session, sql:&str) -> String {
+    //执行一条语句，返回结果的文本。词法或语法错误返回错误信息。
+    //语句与交互输入的一行一样以换行结尾。
+    match parse_statement(&format!("{}\n", sql)) {
+        Result::Ok(syntax) => session.execute(&syntax).to_string(),
+        Result::Err(e) => e
+    }
+}
+pub fn run_all(session:&mut Session, sqls:&[&str]) {
+    //依次执行若干条语句，它们都不能返回表格。
+    for sql in sqls.iter() {
+        let ret = run(session, sql);
+        assert!(!ret.starts_with("ERROR"), "{} => {}", sql, ret);
+    }
+}
+pub fn rows(session:&mut Session, sql:&str) -> Vec<Vec<String>> {
+    //执行一条查询，返回每一行各个值的字符串形式。null写作NULL。
+    let syntax = match parse_statement(&format!("{}\n", sql)) {
+        Result::Ok(s) => s,
+        Result::Err(e) => panic!("{} => {}", sql, e)
+    };
+    match session.execute(&syntax) {
+        DResult::Table(dt) => dt.content.iter().map(|d|d.li.iter().map(|i|if i.is_null() {"NULL".to_string()}else{i.to_string()}).collect()).collect(),
+        other => panic!("{} => {}", sql, other.to_string())
+    }
+}
+pub fn ids(v:Vec<Vec<String>>) -> Vec<String> {
+    //查询结果中每一行的第一列。
+    v.into_iter().map(|r|r[0].to_string()).collect()
+}
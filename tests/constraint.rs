@@ -0,0 +1,65 @@
+extern crate dba;
+mod common;
+use common::{TestDir, session, run, run_all, rows};
+// check约束、组合主键与unique约束
+
+fn prepare(engine:&dba::engine::engine::Engine) -> dba::engine::engine::Session<'_> {
+    session(engine, &["create database d", "use d",
+        "create table k(a integer, b integer, c integer check (c > 0), primary key (a, b), unique (b, c), constraint small check (a < 100))",
+        "insert into k values (1, 1, 1)"])
+}
+
+#[test]
+fn check_constraints_are_named_and_enforced() {
+    let dir = TestDir::new("constraint-check");
+    let engine = dir.engine();
+    let mut s = prepare(&engine);
+    assert_eq!(run(&mut s, "insert into k values (1, 2, 0)"), "Check constraint k_c_check is not satisfied.");
+    assert_eq!(run(&mut s, "insert into k values (200, 2, 2)"), "Check constraint small is not satisfied.");
+    assert_eq!(run(&mut s, "update k set a = 300 where a = 1;"), "Check constraint small is not satisfied.");
+    //条件为null时视为满足。
+    assert_eq!(run(&mut s, "insert into k values (2, 2, null)"), "1 record(s) has inserted.");
+    //已有的记录不满足新约束时不能添加。
+    assert_eq!(run(&mut s, "alter table k add check (b < 0)"), "Check constraint k_b_check is not satisfied.");
+    assert_eq!(run(&mut s, "alter table k add constraint pos check (b > 0)"), "Alter table success.");
+    assert_eq!(run(&mut s, "insert into k values (3, 0, 3)"), "Check constraint pos is not satisfied.");
+    assert_eq!(run(&mut s, "alter table k drop constraint small"), "Alter table success.");
+    assert_eq!(run(&mut s, "insert into k values (200, 3, 3)"), "1 record(s) has inserted.");
+    assert_eq!(rows(&mut s, "select a from k"), vec![vec!["1"], vec!["2"], vec!["200"]]);
+}
+
+#[test]
+fn composite_keys_compare_whole_combinations() {
+    let dir = TestDir::new("constraint-composite");
+    let engine = dir.engine();
+    let mut s = prepare(&engine);
+    assert_eq!(run(&mut s, "insert into k values (1, 2, 1)"), "1 record(s) has inserted.");
+    assert_eq!(run(&mut s, "insert into k values (1, 1, 2)"), "Primary constriant is not satisfied.");
+    assert_eq!(run(&mut s, "insert into k values (2, 1, 1)"), "Unique constriant (b, c) is not satisfied.");
+    //含有null的组合不参与unique查重。
+    run_all(&mut s, &["insert into k values (2, 1, null)", "insert into k values (3, 1, null)"]);
+    run_all(&mut s, &["create table m(x integer, y integer, z integer)", "insert into m values (1, 1, 1)", "insert into m values (2, 1, 1)"]);
+    assert_eq!(run(&mut s, "alter table m add unique (y, z)"), "Unique constriant (y, z) is not satisfied.");
+    assert_eq!(run(&mut s, "alter table m add unique (x, y)"), "Alter table success.");
+    assert_eq!(run(&mut s, "insert into m values (1, 1, 5)"), "Unique constriant (x, y) is not satisfied.");
+    //表只能有一个主键，主键字段也不能在alter中失去主键。
+    assert_eq!(run(&mut s, "alter table k add e integer primary key"), "Table can only have one primary key.");
+    assert_eq!(run(&mut s, "alter table k alter a integer"), "Alter field a is a primary key and must keep primary.");
+}
+
+#[test]
+fn update_checks_keys_against_the_final_rows() {
+    let dir = TestDir::new("constraint-update");
+    let engine = dir.engine();
+    let mut s = prepare(&engine);
+    run_all(&mut s, &["insert into k values (1, 2, 1)", "create table u(id integer unique, v integer)",
+        "insert into u values (1, 1)", "insert into u values (2, 2)", "insert into u values (3, 3)"]);
+    assert_eq!(run(&mut s, "update k set b = 1 where a = 1 and b = 2;"), "Primary constriant is not satisfied.");
+    assert_eq!(run(&mut s, "update k set b = 3, a = 5 where a = 1 and b = 2;"), "1 record(s) updated.");
+    //逐条修改时中间状态会重复，但修改后的全部记录不重复，语句成功。
+    assert_eq!(run(&mut s, "update u set id = id + 1;"), "3 record(s) updated.");
+    assert_eq!(run(&mut s, "update u set id = 9;"), "Unique constriant (id) is not satisfied.");
+    assert_eq!(run(&mut s, "update u set id = 2 where id = 4;"), "Unique constriant (id) is not satisfied.");
+    assert_eq!(rows(&mut s, "select id from u"), vec![vec!["2"], vec!["3"], vec!["4"]]);
+    assert_eq!(rows(&mut s, "select a, b from k"), vec![vec!["1", "1"], vec!["5", "3"]]);
+}
@@ -0,0 +1,80 @@
+extern crate dba;
+extern crate serde_json;
+mod common;
+use std::fs;
+use serde_json::{Value};
+use common::{TestDir, session, run, run_all, rows};
+// 外键的删除动作
+
+fn prepare(engine:&dba::engine::engine::Engine) -> dba::engine::engine::Session<'_> {
+    session(engine, &["create database d", "use d",
+        "create table p(id integer primary key, v integer)",
+        "create table c1(id integer, pid integer, foreign key (pid) reference p(id) on delete cascade)",
+        "create table c2(id integer, pid integer, foreign key (pid) reference p(id) on delete set null)",
+        "create table c3(id integer, pid integer, foreign key (pid) reference p(id))",
+        "create table g(id integer, cid integer, foreign key (cid) reference c1(id) on delete cascade)",
+        "insert into p values (1, 1)", "insert into p values (2, 2)", "insert into p values (3, 3)",
+        "insert into c1 values (10, 1)", "insert into g values (100, 10)",
+        "insert into c2 values (20, 1)", "insert into c2 values (21, 2)", "insert into c3 values (30, 3)"])
+}
+
+#[test]
+fn references_must_exist() {
+    let dir = TestDir::new("foreign-insert");
+    let engine = dir.engine();
+    let mut s = prepare(&engine);
+    assert_eq!(run(&mut s, "insert into c1 values (11, 9)"), "Foreign constriant is failed.");
+    assert_eq!(rows(&mut s, "select id from c1"), vec![vec!["10"]]);
+}
+
+#[test]
+fn delete_follows_the_declared_action() {
+    let dir = TestDir::new("foreign-delete");
+    let engine = dir.engine();
+    let mut s = prepare(&engine);
+    //没有声明删除动作时为restrict。
+    assert_eq!(run(&mut s, "delete from p where id = 3;"), "Foreign constriant is failed: record(s) in c3 reference it.");
+    //cascade递归地删除引用它的记录，set null把引用置为null。
+    assert_eq!(run(&mut s, "delete from p where id = 1;"), "1 record(s) deleted, 3 dependent record(s) affected.");
+    assert_eq!(rows(&mut s, "select id from c1").len(), 0);
+    assert_eq!(rows(&mut s, "select id from g").len(), 0);
+    assert_eq!(rows(&mut s, "select * from c2"), vec![vec!["20", "NULL"], vec!["21", "2"]]);
+    assert_eq!(rows(&mut s, "select id from p"), vec![vec!["3"], vec!["2"]]);
+}
+
+#[test]
+fn restricted_delete_changes_nothing() {
+    let dir = TestDir::new("foreign-restrict");
+    let engine = dir.engine();
+    let mut s = prepare(&engine);
+    //一条语句删除的多条记录中有一条被restrict的外键引用时，整条语句都不生效。
+    assert!(run(&mut s, "delete from p where id > 0;").starts_with("Foreign constriant is failed"));
+    assert_eq!(rows(&mut s, "select id from p").len(), 3);
+    assert_eq!(rows(&mut s, "select id from c1"), vec![vec!["10"]]);
+    assert_eq!(rows(&mut s, "select * from c2"), vec![vec!["20", "1"], vec!["21", "2"]]);
+}
+
+#[test]
+fn foreign_keys_in_old_files_become_restrict() {
+    //旧版本的数据文件没有版本号，保存的删除动作是当时默认的cascade。
+    let dir = TestDir::new("foreign-upgrade");
+    {
+        let engine = dir.engine();
+        session(&engine, &["create database d", "use d", "create table p(id integer primary key)",
+            "create table c(id integer, pid integer, foreign key (pid) reference p(id) on delete cascade)"]);
+    }
+    let mut conf:Value = serde_json::from_str(&fs::read_to_string(dir.path("d.dba")).unwrap()).unwrap();
+    conf.as_object_mut().unwrap().remove("version");
+    fs::write(dir.path("d.dba"), conf.to_string()).unwrap();
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d", "insert into p values (1)", "insert into c values (10, 1)"]);
+    assert_eq!(run(&mut s, "delete from p where id = 1;"), "Foreign constriant is failed: record(s) in c reference it.");
+    //升级只发生一次，之后声明的动作照常生效。
+    run_all(&mut s, &["create table c2(id integer, pid integer, foreign key (pid) reference p(id) on delete cascade)",
+        "insert into p values (2)", "insert into c2 values (20, 2)"]);
+    drop(s);
+    drop(engine);
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(run(&mut s, "delete from p where id = 2;"), "1 record(s) deleted, 1 dependent record(s) affected.");
+}
@@ -0,0 +1,58 @@
+extern crate dba;
+mod common;
+use common::{TestDir, session, run, run_all, rows};
+// 分组与排序
+
+fn prepare(dir:&TestDir) -> dba::engine::engine::Engine {
+    let engine = dir.engine();
+    {
+        let mut s = session(&engine, &["create database d", "use d", "create table s(id integer, cls varchar(8), score integer)"]);
+        for &(id, cls, score) in [(1, "b", 60), (2, "a", 80), (3, "c", 70), (4, "b", 90), (5, "c", 50), (6, "c", 95)].iter() {
+            run_all(&mut s, &[format!("insert into s values ({}, \"{}\", {})", id, cls, score).as_str()]);
+        }
+    }
+    engine
+}
+
+#[test]
+fn grouped_rows_are_sorted_after_grouping() {
+    let dir = TestDir::new("group-order");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(rows(&mut s, "select cls, count(id) from s group by cls order by cls desc"),
+        vec![vec!["c", "3"], vec!["b", "2"], vec!["a", "1"]]);
+    //having过滤之后再排序。
+    assert_eq!(rows(&mut s, "select cls, max(score) from s group by cls having count(id) > 1 order by cls"),
+        vec![vec!["b", "90"], vec!["c", "95"]]);
+    //没有分组时仍然先排序再取目标列。
+    assert_eq!(rows(&mut s, "select id from s where score > 75 order by score desc"),
+        vec![vec!["6"], vec!["4"], vec!["2"]]);
+}
+
+#[test]
+fn grouped_rows_can_be_ordered_by_an_aggregate() {
+    let dir = TestDir::new("group-aggregate");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(rows(&mut s, "select cls, sum(score) as total from s group by cls order by total desc"),
+        vec![vec!["c", "215"], vec!["b", "150"], vec!["a", "80"]]);
+    assert_eq!(rows(&mut s, "select cls, count(id) as n, min(score) as low from s group by cls order by n, low desc"),
+        vec![vec!["a", "1", "80"], vec!["b", "2", "60"], vec!["c", "3", "50"]]);
+    assert_eq!(rows(&mut s, "select cls, count(id) as n from s group by cls order by n desc limit 1;"),
+        vec![vec!["c", "3"]]);
+    //不是目标列的名字时只能按分组字段排序。
+    assert!(run(&mut s, "select cls from s group by cls order by score").contains("group by"));
+}
+
+#[test]
+fn distinct_aggregates_count_each_value_once() {
+    let dir = TestDir::new("group-distinct");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d", "insert into s values (7, \"a\", 80)", "insert into s values (8, null, 80)"]);
+    assert_eq!(rows(&mut s, "select count(distinct cls), count(cls), count(all cls) from s"), vec![vec!["3", "7", "7"]]);
+    assert_eq!(rows(&mut s, "select cls, count(distinct score), sum(distinct score), avg(distinct score) from s where cls = \"a\" group by cls"),
+        vec![vec!["a", "1", "80", "80"]]);
+    assert_eq!(rows(&mut s, "select count(distinct score) from s group by cls having count(distinct score) > 2;"), vec![vec!["3"]]);
+    //distinct只能用在聚合函数中。
+    assert!(run(&mut s, "select upper(distinct cls) from s").contains("rror"));
+}
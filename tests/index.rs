@@ -0,0 +1,78 @@
+extern crate dba;
+mod common;
+use common::{TestDir, session, run, run_all, rows, ids};
+// 索引的查找与维护
+
+#[test]
+fn lookups_follow_writes_after_a_read_loaded_the_index() {
+    let dir = TestDir::new("index-cache");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d", "create table t(id integer, k integer)", "create index t_k on t(k)"]);
+    for i in 0..6 {
+        run_all(&mut s, &[format!("insert into t values ({}, {})", i, i % 3).as_str()]);
+    }
+    {
+        //另一个会话在只读的语句中第一次加载索引。
+        let mut reader = session(&engine, &["use d"]);
+        assert_eq!(ids(rows(&mut reader, "select id from t where k = 1;")), vec!["1", "4"]);
+        assert_eq!(ids(rows(&mut reader, "select id from t where k >= 2;")), vec!["2", "5"]);
+    }
+    run_all(&mut s, &["insert into t values (6, 1)", "update t set k = 2 where id = 1;", "delete from t where id = 4;"]);
+    assert_eq!(ids(rows(&mut s, "select id from t where k = 1;")), vec!["6"]);
+    assert_eq!(ids(rows(&mut s, "select id from t where k = 2;")), vec!["1", "2", "5"]);
+    //回滚之后索引同样回到事务开始时的状态。
+    run_all(&mut s, &["begin", "insert into t values (7, 1)"]);
+    assert_eq!(ids(rows(&mut s, "select id from t where k = 1;")), vec!["6", "7"]);
+    run_all(&mut s, &["rollback"]);
+    assert_eq!(ids(rows(&mut s, "select id from t where k = 1;")), vec!["6"]);
+    drop(s);
+    drop(engine);
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(ids(rows(&mut s, "select id from t where k < 2;")), vec!["0", "3", "6"]);
+}
+
+#[test]
+fn unique_checks_use_the_index() {
+    let dir = TestDir::new("index-unique");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d", "create table t(id integer unique, v integer)", "create index t_id on t(id)",
+        "insert into t values (1, 1)", "insert into t values (2, 2)"]);
+    assert!(run(&mut s, "insert into t values (2, 3)").contains("nique"));
+    assert!(run(&mut s, "update t set id = 1 where id = 2;").contains("nique"));
+    run_all(&mut s, &["update t set id = 3 where id = 2;"]);
+    assert_eq!(ids(rows(&mut s, "select v from t where id = 3;")), vec!["2"]);
+    assert_eq!(rows(&mut s, "select v from t where id = 2;").len(), 0);
+}
+
+#[test]
+fn index_stays_consistent_through_updates_and_deletes() {
+    //与不经过索引的查询结果比较。
+    let dir = TestDir::new("index-maintain");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d", "create table t(id integer, k integer)", "create index t_k on t(k)"]);
+    for i in 0..40 {
+        run_all(&mut s, &[format!("insert into t values ({}, {})", i, i % 7).as_str()]);
+    }
+    run_all(&mut s, &["delete from t where id < 5;", "update t set k = k + 10 where id > 30;", "delete from t where k = 3;",
+        "update t set k = 3 where id < 12;", "delete from t where id > 36;"]);
+    let expected = rows(&mut s, "select id, k from t");
+    let check = |s:&mut dba::engine::engine::Session| for k in 0..20 {
+        let mut indexed = ids(rows(s, format!("select id from t where k = {};", k).as_str()));
+        let mut scanned:Vec<String> = expected.iter().filter(|r|r[1] == k.to_string()).map(|r|r[0].to_string()).collect();
+        indexed.sort();
+        scanned.sort();
+        assert_eq!(indexed, scanned, "k = {}", k);
+    };
+    check(&mut s);
+    //重新打开后从索引页加载的索引也一致。
+    drop(s);
+    drop(engine);
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d"]);
+    check(&mut s);
+    run_all(&mut s, &["delete from t"]);
+    assert_eq!(rows(&mut s, "select id from t where k >= 0;").len(), 0);
+    run_all(&mut s, &["insert into t values (100, 1)"]);
+    assert_eq!(ids(rows(&mut s, "select id from t where k = 1;")), vec!["100"]);
+}
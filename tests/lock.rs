@@ -0,0 +1,103 @@
+extern crate dba;
+mod common;
+use std::sync::{Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+use common::{TestDir, root, run, run_all, rows};
+use dba::engine::engine::{Engine};
+use dba::engine::lock::{Lock, LockMode, LockFailure};
+// 会话之间的读写锁
+
+const SHORT:u64 = 100;
+const LONG:u64 = 5000;
+
+#[test]
+fn shared_locks_do_not_block_each_other() {
+    let lock = Lock::new();
+    assert_eq!(lock.acquire(1, LockMode::Shared, Duration::from_millis(SHORT)), Result::Ok(()));
+    assert_eq!(lock.acquire(2, LockMode::Shared, Duration::from_millis(SHORT)), Result::Ok(()));
+    assert_eq!(lock.acquire(3, LockMode::Exclusive, Duration::from_millis(SHORT)), Result::Err(LockFailure::Timeout));
+    //超时的排他锁不会继续挡住新的共享锁。
+    assert_eq!(lock.acquire(4, LockMode::Shared, Duration::from_millis(SHORT)), Result::Ok(()));
+}
+
+#[test]
+fn exclusive_lock_waits_for_readers() {
+    let lock = Arc::new(Lock::new());
+    lock.acquire(1, LockMode::Shared, Duration::from_millis(SHORT)).unwrap();
+    let other = lock.clone();
+    let writer = thread::spawn(move || other.acquire(2, LockMode::Exclusive, Duration::from_millis(LONG)));
+    thread::sleep(Duration::from_millis(SHORT));
+    //有会话在等待排他锁时，新的共享锁也要等待。
+    assert_eq!(lock.acquire(3, LockMode::Shared, Duration::from_millis(SHORT)), Result::Err(LockFailure::Timeout));
+    lock.release(1);
+    assert_eq!(writer.join().unwrap(), Result::Ok(()));
+    assert!(lock.is_exclusive(2));
+}
+
+#[test]
+fn second_upgrader_fails_fast_with_deadlock() {
+    let lock = Arc::new(Lock::new());
+    lock.acquire(1, LockMode::Shared, Duration::from_millis(SHORT)).unwrap();
+    lock.acquire(2, LockMode::Shared, Duration::from_millis(SHORT)).unwrap();
+    let other = lock.clone();
+    let first = thread::spawn(move || other.acquire(1, LockMode::Exclusive, Duration::from_millis(LONG)));
+    thread::sleep(Duration::from_millis(SHORT));
+    let start = Instant::now();
+    assert_eq!(lock.acquire(2, LockMode::Exclusive, Duration::from_millis(LONG)), Result::Err(LockFailure::Deadlock));
+    assert!(start.elapsed() < Duration::from_millis(LONG / 2));
+    //失败的一方回滚并释放共享锁后，先等待的一方完成升级。
+    lock.release(2);
+    assert_eq!(first.join().unwrap(), Result::Ok(()));
+    assert!(lock.is_exclusive(1));
+}
+
+#[test]
+fn sessions_read_together_and_fail_fast_on_deadlock() {
+    let dir = TestDir::new("lock-sessions");
+    let engine = dir.engine();
+    let mut a = root(&engine);
+    let mut b = root(&engine);
+    run_all(&mut a, &["create database d", "use d", "create table t(id integer)", "insert into t values (1)"]);
+    run(&mut b, "use d");
+    //两个事务都读过t，持有共享锁。
+    run_all(&mut a, &["begin"]);
+    run_all(&mut b, &["begin"]);
+    assert_eq!(rows(&mut a, "select id from t").len(), 1);
+    assert_eq!(rows(&mut b, "select id from t").len(), 1);
+    thread::scope(|scope| {
+        let first = scope.spawn(|| {
+            let ret = run(&mut a, "insert into t values (2)");
+            run_all(&mut a, &["commit"]);
+            ret
+        });
+        thread::sleep(Duration::from_millis(SHORT));
+        let start = Instant::now();
+        assert!(run(&mut b, "insert into t values (3)").starts_with("Deadlock on database d"));
+        assert!(start.elapsed() < Duration::from_millis(SHORT * 2));
+        run_all(&mut b, &["rollback"]);
+        assert_eq!(first.join().unwrap(), "1 record(s) has inserted.");
+    });
+    assert_eq!(rows(&mut b, "select id from t"), vec![vec!["1"], vec!["2"]]);
+}
+
+#[test]
+fn data_directory_is_opened_by_one_engine_at_a_time() {
+    let dir = TestDir::new("lock-files");
+    let ini = dir.dir.join("dba.ini");
+    let engine = dir.engine();
+    {
+        let mut s = root(&engine);
+        run_all(&mut s, &["create database d"]);
+    }
+    match Engine::load(ini.to_str().unwrap()) {
+        Result::Err(e) => assert!(e.contains("is in use by another process"), "{}", e),
+        Result::Ok(_) => panic!("opened twice")
+    }
+    //关闭后.lock文件保留，下次打开时重新加锁。
+    drop(engine);
+    assert!(dir.path("system.lock").exists() && dir.path("d.lock").exists());
+    let engine = dir.engine();
+    let mut s = root(&engine);
+    assert_eq!(run(&mut s, "use d"), "use d.");
+}
@@ -0,0 +1,48 @@
+extern crate dba;
+mod common;
+use common::{TestDir, session, run, run_all, rows, ids};
+// null值
+
+fn prepare(dir:&TestDir) -> dba::engine::engine::Engine {
+    let engine = dir.engine();
+    session(&engine, &["create database d", "use d", "create table t(id integer, name varchar(8), f float)",
+        "insert into t values (1, \"a\", 1.5)", "insert into t values (2, null, null)", "insert into t (id) values (3)"]);
+    engine
+}
+
+#[test]
+fn nulls_are_stored_and_read_back() {
+    let dir = TestDir::new("null-store");
+    drop(prepare(&dir));
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(rows(&mut s, "select * from t"),
+        vec![vec!["1", "a", "1.5"], vec!["2", "NULL", "NULL"], vec!["3", "NULL", "NULL"]]);
+    run_all(&mut s, &["update t set name = null where id = 1;", "update t set f = 2.5 where id = 2;"]);
+    assert_eq!(rows(&mut s, "select name, f from t"), vec![vec!["NULL", "1.5"], vec!["NULL", "2.5"], vec!["NULL", "NULL"]]);
+}
+
+#[test]
+fn conditions_use_three_valued_logic() {
+    let dir = TestDir::new("null-logic");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(ids(rows(&mut s, "select id from t where name is null")), vec!["2", "3"]);
+    assert_eq!(ids(rows(&mut s, "select id from t where name is not null")), vec!["1"]);
+    //与null比较的结果是null，取反后仍然不满足。
+    assert_eq!(rows(&mut s, "select id from t where not (name = \"a\")").len(), 0);
+    assert_eq!(rows(&mut s, "select id + null from t where id = 1;"), vec![vec!["NULL"]]);
+    assert_eq!(rows(&mut s, "select count(name), count(id), sum(f) from t"), vec![vec!["1", "3", "1.5"]]);
+}
+
+#[test]
+fn not_null_is_checked() {
+    let dir = TestDir::new("null-check");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d", "create table t(id integer not_null, v integer)", "insert into t values (1, 1)"]);
+    assert_eq!(run(&mut s, "insert into t values (null, 2)"), "Field id cannot be null.");
+    assert_eq!(run(&mut s, "insert into t (v) values (2)"), "Error: field id has no default value and cannot find its value.");
+    assert_eq!(run(&mut s, "update t set id = null where v = 1;"), "Field id cannot be null.");
+    assert_eq!(run(&mut s, "alter table t add x integer not_null"), "Add field x must have a default value.");
+    assert_eq!(rows(&mut s, "select id from t"), vec![vec!["1"]]);
+}
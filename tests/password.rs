@@ -0,0 +1,61 @@
+extern crate dba;
+mod common;
+use std::fs;
+use common::{TestDir, root, run, run_all};
+// 用户密码的保存与修改
+
+#[test]
+fn default_root_password_must_be_changed() {
+    let dir = TestDir::new("password-root");
+    let engine = dir.engine();
+    let mut s = engine.session("root", "root").unwrap();
+    let must = "You must change your password first: alter user root with password \"...\".";
+    assert_eq!(run(&mut s, "create database x"), must);
+    assert_eq!(run(&mut s, "alter user root with password \"root\""), "The new password must be different from the old one.");
+    assert_eq!(run(&mut s, "alter user root with password \"rpw\""), "User root has been altered.");
+    assert_eq!(run(&mut s, "create database x"), "Database x has created.");
+    assert_eq!(engine.session("root", "root").err(), Option::Some("Password wrong.".to_string()));
+    assert!(engine.session("root", "rpw").is_ok());
+}
+
+#[test]
+fn passwords_set_by_staff_must_be_changed() {
+    let dir = TestDir::new("password-staff");
+    let engine = dir.engine();
+    let mut r = root(&engine);
+    run_all(&mut r, &["create database x", "create user alice with password \"a1\"", "alter user alice with password \"a2\""]);
+    assert_eq!(engine.session("alice", "a1").err(), Option::Some("Password wrong.".to_string()));
+    let mut a = engine.session("alice", "a2").unwrap();
+    let must = "You must change your password first: alter user alice with password \"...\".";
+    assert_eq!(run(&mut a, "use x"), must);
+    //只能修改自己的密码。
+    assert_eq!(run(&mut a, "alter user root with password \"a3\""), must);
+    assert_eq!(run(&mut a, "alter user alice with password \"a3\""), "User alice has been altered.");
+    assert_eq!(run(&mut a, "alter user alice with password \"a4\""), "User alice has been altered.");
+    drop(a);
+    //自己设置的密码不需要再修改。
+    let mut a = engine.session("alice", "a4").unwrap();
+    assert_eq!(run(&mut a, "use x"), "You do not have grant on these setences.");
+}
+
+#[test]
+fn passwords_are_stored_as_salted_hashes() {
+    let dir = TestDir::new("password-hash");
+    {
+        let engine = dir.engine();
+        let mut r = root(&engine);
+        run_all(&mut r, &["create user alice with password \"plain-secret\"", "create user bob with password \"plain-secret\""]);
+    }
+    let data = fs::read(dir.path("system.db")).unwrap();
+    let text = String::from_utf8_lossy(&data);
+    assert!(!text.contains("plain-secret"));
+    //相同的密码因为盐不同得到不同的散列值。
+    let mut hashes:Vec<&str> = text.split("pbkdf2-sha256$").skip(1).map(|h|&h[..60]).collect();
+    let count = hashes.len();
+    hashes.sort();
+    hashes.dedup();
+    assert!(count >= 3 && hashes.len() == count);
+    let engine = dir.engine();
+    assert!(engine.session("alice", "plain-secret").is_ok());
+    assert!(engine.session("bob", "plain-secre").is_err());
+}
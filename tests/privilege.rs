@@ -0,0 +1,150 @@
+extern crate dba;
+mod common;
+use common::{TestDir, root, run, run_all, rows};
+// 角色、转授与级联收回
+
+const DENIED:&str = "You do not have grant on these setences.";
+
+fn prepare(engine:&dba::engine::engine::Engine) {
+    let mut s = root(engine);
+    run_all(&mut s, &[
+        "create database d",
+        "use d",
+        "create table t(id integer)",
+        "insert into t values (1)",
+        "create user alice with password \"a\"",
+        "create user bob with password \"b\"",
+        "create user carol with password \"c\""
+    ]);
+}
+
+#[test]
+fn role_privileges_apply_to_members() {
+    let dir = TestDir::new("role-members");
+    let engine = dir.engine();
+    prepare(&engine);
+    let mut r = root(&engine);
+    run_all(&mut r, &["use d", "create role reader", "grant select on table t to reader", "grant reader to alice"]);
+    {
+        let mut alice = engine.session("alice", "a").unwrap();
+        run(&mut alice, "use d");
+        assert_eq!(rows(&mut alice, "select * from t"), vec![vec!["1".to_string()]]);
+        assert_eq!(run(&mut alice, "insert into t values (2)"), DENIED);
+        let mut bob = engine.session("bob", "b").unwrap();
+        assert_eq!(run(&mut bob, "use d"), DENIED);
+    }
+    assert_eq!(run(&mut r, "revoke reader from alice"), "Revoke complete.");
+    let mut alice = engine.session("alice", "a").unwrap();
+    assert_eq!(run(&mut alice, "use d"), DENIED);
+    //角色只能授予用户。
+    assert_eq!(run(&mut r, "grant reader to reader"), "User reader is not exists.");
+}
+
+#[test]
+fn grant_option_allows_delegation() {
+    let dir = TestDir::new("delegation");
+    let engine = dir.engine();
+    prepare(&engine);
+    let mut r = root(&engine);
+    run_all(&mut r, &["use d", "grant select on table t to alice with grant option"]);
+    let mut alice = engine.session("alice", "a").unwrap();
+    run(&mut alice, "use d");
+    assert_eq!(run(&mut alice, "grant select on table t to bob"), "Grant complete.");
+    //没有转授权限的权限不能再授予别人。
+    assert_eq!(run(&mut alice, "grant insert on table t to bob"), "You do not have grant option of insert on t.");
+    let mut bob = engine.session("bob", "b").unwrap();
+    run(&mut bob, "use d");
+    assert_eq!(rows(&mut bob, "select * from t").len(), 1);
+    assert_eq!(run(&mut bob, "grant select on table t to carol"), "You do not have grant option of select on t.");
+}
+
+#[test]
+fn revoke_requires_cascade_for_delegated_grants() {
+    let dir = TestDir::new("cascade");
+    let engine = dir.engine();
+    prepare(&engine);
+    let mut r = root(&engine);
+    run_all(&mut r, &["use d", "grant select on table t to alice with grant option"]);
+    {
+        let mut alice = engine.session("alice", "a").unwrap();
+        run_all(&mut alice, &["use d", "grant select on table t to bob with grant option"]);
+        let mut bob = engine.session("bob", "b").unwrap();
+        run_all(&mut bob, &["use d", "grant select on table t to carol"]);
+    }
+    assert_eq!(run(&mut r, "revoke select on table t from alice"),
+        "Some privileges were granted to others by these grants. Use revoke ... cascade to revoke them too.");
+    let mut carol = engine.session("carol", "c").unwrap();
+    run(&mut carol, "use d");
+    assert_eq!(rows(&mut carol, "select * from t").len(), 1);
+    assert_eq!(run(&mut r, "revoke select on table t from alice cascade"), "Revoke complete.");
+    for &(user, pw) in [("alice", "a"), ("bob", "b"), ("carol", "c")].iter() {
+        let mut s = engine.session(user, pw).unwrap();
+        assert_eq!(run(&mut s, "use d"), DENIED, "{}", user);
+    }
+}
+
+#[test]
+fn grants_in_a_cycle_do_not_keep_each_other() {
+    let dir = TestDir::new("cycle");
+    let engine = dir.engine();
+    prepare(&engine);
+    let mut r = root(&engine);
+    run_all(&mut r, &["use d", "grant select on table t to alice with grant option"]);
+    {
+        let mut alice = engine.session("alice", "a").unwrap();
+        run_all(&mut alice, &["use d", "grant select on table t to bob with grant option"]);
+        let mut bob = engine.session("bob", "b").unwrap();
+        run_all(&mut bob, &["use d", "grant select on table t to alice with grant option"]);
+    }
+    assert_eq!(run(&mut r, "revoke select on table t from alice cascade"), "Revoke complete.");
+    let mut bob = engine.session("bob", "b").unwrap();
+    assert_eq!(run(&mut bob, "use d"), DENIED);
+    let mut alice = engine.session("alice", "a").unwrap();
+    assert_eq!(run(&mut alice, "use d"), DENIED);
+}
+
+#[test]
+fn revoking_a_role_cascades_to_grants_made_through_it() {
+    let dir = TestDir::new("role-cascade");
+    let engine = dir.engine();
+    prepare(&engine);
+    let mut r = root(&engine);
+    run_all(&mut r, &["use d", "create role rr", "grant select on table t to rr with grant option", "grant rr to alice"]);
+    {
+        let mut alice = engine.session("alice", "a").unwrap();
+        run_all(&mut alice, &["use d", "grant select on table t to bob"]);
+    }
+    assert_eq!(run(&mut r, "revoke rr from alice"),
+        "Some privileges were granted to others by these grants. Use revoke ... cascade to revoke them too.");
+    assert_eq!(run(&mut r, "revoke rr from alice cascade"), "Revoke complete.");
+    let mut bob = engine.session("bob", "b").unwrap();
+    assert_eq!(run(&mut bob, "use d"), DENIED);
+}
+
+#[test]
+fn dropping_a_user_or_role_removes_dependent_grants() {
+    let dir = TestDir::new("drop-grantee");
+    let engine = dir.engine();
+    prepare(&engine);
+    let mut r = root(&engine);
+    run_all(&mut r, &["use d", "create role rr", "grant select on table t to rr with grant option", "grant rr to alice",
+        "grant select on table t to bob with grant option"]);
+    {
+        let mut alice = engine.session("alice", "a").unwrap();
+        run_all(&mut alice, &["use d", "grant select on table t to carol"]);
+    }
+    assert_eq!(run(&mut r, "drop role rr"), "Role rr has been droped.");
+    {
+        let mut carol = engine.session("carol", "c").unwrap();
+        assert_eq!(run(&mut carol, "use d"), DENIED);
+        let mut bob = engine.session("bob", "b").unwrap();
+        run_all(&mut bob, &["use d", "grant select on table t to carol"]);
+    }
+    assert_eq!(run(&mut r, "drop user bob"), "User bob has been droped.");
+    let mut carol = engine.session("carol", "c").unwrap();
+    assert_eq!(run(&mut carol, "use d"), DENIED);
+    //重新建立的同名用户不会继承之前的权限。
+    run(&mut r, "create user bob with password \"b\"");
+    let mut bob = engine.session("bob", "b").unwrap();
+    assert_eq!(run(&mut bob, "use d"), DENIED);
+}
@@ -0,0 +1,119 @@
+extern crate dba;
+mod common;
+use common::{TestDir, session, run, run_all, rows, ids};
+// select的条件、连接与截取，update与insert中的表达式
+
+fn prepare(dir:&TestDir) -> dba::engine::engine::Engine {
+    let engine = dir.engine();
+    session(&engine, &["create database d", "use d",
+        "create table a(id integer, name varchar(8), price float, qty integer)", "create table b(aid integer, tag varchar(8))",
+        "insert into a values (1, \"x\", 10.0, 1)", "insert into a values (2, \"y\", 20.0, 2)", "insert into a values (3, \"z\", 30.0, 3)",
+        "insert into b values (1, \"p\")", "insert into b values (1, \"q\")", "insert into b values (4, \"r\")"]);
+    engine
+}
+
+#[test]
+fn between_and_in_list() {
+    let dir = TestDir::new("query-between");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(ids(rows(&mut s, "select id from a where price between 15 and 30;")), vec!["2", "3"]);
+    assert_eq!(ids(rows(&mut s, "select id from a where price not between 15 and 30;")), vec!["1"]);
+    assert_eq!(ids(rows(&mut s, "select id from a where name in (\"x\", \"z\")")), vec!["1", "3"]);
+    assert_eq!(ids(rows(&mut s, "select id from a where name not in (\"x\", \"z\")")), vec!["2"]);
+    assert_eq!(ids(rows(&mut s, "select id from a where id in (select aid from b)")), vec!["1"]);
+    assert_eq!(ids(rows(&mut s, "select id from a where id not in (select aid from b)")), vec!["2", "3"]);
+}
+
+#[test]
+fn inner_left_and_right_joins() {
+    let dir = TestDir::new("query-join");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(rows(&mut s, "select a.id, b.tag from a join b on a.id = b.aid"), vec![vec!["1", "p"], vec!["1", "q"]]);
+    assert_eq!(rows(&mut s, "select a.id, b.tag from a inner join b on a.id = b.aid and b.tag = \"q\""), vec![vec!["1", "q"]]);
+    assert_eq!(rows(&mut s, "select a.id, b.tag from a left join b on a.id = b.aid"),
+        vec![vec!["1", "p"], vec!["1", "q"], vec!["2", "NULL"], vec!["3", "NULL"]]);
+    assert_eq!(rows(&mut s, "select a.id, b.tag from a left outer join b on a.id = b.aid where b.tag is null"),
+        vec![vec!["2", "NULL"], vec!["3", "NULL"]]);
+    assert_eq!(rows(&mut s, "select a.id, b.aid, b.tag from a right join b on a.id = b.aid"),
+        vec![vec!["1", "1", "p"], vec!["1", "1", "q"], vec!["NULL", "4", "r"]]);
+}
+
+#[test]
+fn limit_and_offset() {
+    let dir = TestDir::new("query-limit");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(ids(rows(&mut s, "select id from a limit 2;")), vec!["1", "2"]);
+    assert_eq!(ids(rows(&mut s, "select id from a limit 2 offset 2;")), vec!["3"]);
+    assert_eq!(ids(rows(&mut s, "select id from a order by id desc limit 1 offset 1;")), vec!["2"]);
+    assert_eq!(rows(&mut s, "select id from a limit 0;").len(), 0);
+    //有join时，where在join之后过滤，截取也要在过滤之后。
+    assert_eq!(rows(&mut s, "select a.id, b.tag from a left join b on a.id = b.aid where a.id > 1 limit 1;"), vec![vec!["2", "NULL"]]);
+    assert_eq!(rows(&mut s, "select a.id, b.tag from a join b on a.id = b.aid where b.tag = \"q\" limit 1;"), vec![vec!["1", "q"]]);
+    assert_eq!(rows(&mut s, "select b.tag from b join a on a.id = b.aid limit 1 offset 1;"), vec![vec!["q"]]);
+    //分批扫描时，满足条件的记录可以在后面的批次中。
+    run_all(&mut s, &["create table n(id integer)"]);
+    for i in 0..600 {
+        run_all(&mut s, &[format!("insert into n values ({});", i).as_str()]);
+    }
+    assert_eq!(ids(rows(&mut s, "select id from n where id > 250 limit 3 offset 2;")), vec!["253", "254", "255"]);
+    assert_eq!(ids(rows(&mut s, "select id from n where id >= 590 limit 5 offset 8;")), vec!["598", "599"]);
+    assert_eq!(ids(rows(&mut s, "select id from n limit 2 offset 511;")), vec!["511", "512"]);
+}
+
+#[test]
+fn update_with_expressions() {
+    let dir = TestDir::new("query-update");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    //每个表达式都在修改前的记录上求值。
+    assert_eq!(run(&mut s, "update a set price = price * 1.5, qty = qty + 1 where id < 3;"), "2 record(s) updated.");
+    assert_eq!(rows(&mut s, "select price, qty from a"), vec![vec!["15", "2"], vec!["30", "3"], vec!["30", "3"]]);
+    run_all(&mut s, &["update a set name = concat(name, id), qty = id * qty, id = id + 10 where id = 3;"]);
+    assert_eq!(rows(&mut s, "select id, name, qty from a where id = 13;"), vec![vec!["13", "z3", "9"]]);
+    //整数可以赋给浮点数的字段，反之不行。
+    run_all(&mut s, &["update a set price = qty where id = 1;"]);
+    assert_eq!(rows(&mut s, "select price from a where id = 1;"), vec![vec!["2"]]);
+    assert_eq!(run(&mut s, "update a set qty = price where id = 1;"), "Wrong value type for field qty.");
+}
+
+#[test]
+fn insert_from_select() {
+    let dir = TestDir::new("query-insert");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    run_all(&mut s, &["create table c(id integer, name varchar(8))"]);
+    assert_eq!(run(&mut s, "insert into c select id, name from a where id > 1;"), "2 record(s) has inserted.");
+    assert_eq!(run(&mut s, "insert into c (name) select tag from b"), "3 record(s) has inserted.");
+    assert_eq!(rows(&mut s, "select * from c"),
+        vec![vec!["2", "y"], vec!["3", "z"], vec!["NULL", "p"], vec!["NULL", "q"], vec!["NULL", "r"]]);
+    assert_eq!(run(&mut s, "insert into c select id from a"), "Column count of select does not match the insert.");
+    //从自身查询插入时，查询的结果在插入之前就已经确定。
+    assert_eq!(run(&mut s, "insert into c select id, name from c where id is not null"), "2 record(s) has inserted.");
+    assert_eq!(rows(&mut s, "select id from c where id is not null").len(), 4);
+}
+
+#[test]
+fn scalar_functions() {
+    let dir = TestDir::new("query-function");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    run_all(&mut s, &["insert into a values (4, \" Xy \", 2.567, 0)", "insert into a (id) values (5)"]);
+    assert_eq!(rows(&mut s, "select upper(name), lower(name), trim(name), length(name) from a where id > 3;"),
+        vec![vec![" XY ", " xy ", "Xy", "4"], vec!["NULL", "NULL", "NULL", "NULL"]]);
+    assert_eq!(rows(&mut s, "select substr(\"abcdef\", 2, 3), substr(\"abcdef\", 4), abs(0 - price), round(price, 2) from a where id = 4;"),
+        vec![vec!["bcd", "def", "2.567", "2.57"]]);
+    assert_eq!(rows(&mut s, "select coalesce(name, \"none\"), concat(\"a\", id, name) from a where id > 3;"),
+        vec![vec![" Xy ", "a4 Xy "], vec!["none", "a5"]]);
+    assert_eq!(rows(&mut s, "select cast(\"12\" as integer), cast(id as varchar(4)), cast(\"2026-10-18\" as date) from a where id = 4;"),
+        vec![vec!["12", "4", "2026-10-18"]]);
+    assert_eq!(ids(rows(&mut s, "select id from a where length(trim(name)) = 2;")), vec!["4"]);
+    //参数可以是负数的常量。
+    assert_eq!(rows(&mut s, "select round(1234, -2), round(1250, -2), round(price * 100, -1), abs(-2), abs(-2.5) from a where id = 4;"),
+        vec![vec!["1200", "1300", "260", "2", "2.5"]]);
+    assert_eq!(rows(&mut s, "select id - 1, -1 + id, id * -2 from a where id = 4;"), vec![vec!["3", "3", "-8"]]);
+    assert_eq!(run(&mut s, "select upper(id) from a"), "Wrong param type for function upper.");
+    assert_eq!(run(&mut s, "select cast(\"x\" as integer) from a"), "Cannot cast x to integer.");
+}
@@ -0,0 +1,112 @@
+extern crate dba;
+mod common;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc};
+use std::thread;
+use common::{TestDir, root, run_all};
+use dba::engine::database::{DResult};
+use dba::engine::ini::{Config};
+use dba::net::client::{Client};
+use dba::net::protocol::{MAX_FRAME};
+use dba::net::server::{serve};
+// tcp服务与通信协议
+
+fn start(dir:&TestDir) -> String {
+    //在本机的随机端口上启动服务，返回地址。服务线程随测试进程结束。
+    let engine = dir.engine();
+    {
+        let mut s = root(&engine);
+        run_all(&mut s, &["create database e", "create database d", "use d", "create table t(id integer, name varchar(8), d date)",
+            "insert into t values (1, \"a\", \"2026-10-18\")", "insert into t values (2, null, null)"]);
+    }
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let engine = Arc::new(engine);
+    thread::spawn(move || serve(engine, &listener));
+    addr
+}
+fn exchange(addr:&str, frames:&[&[u8]]) -> Vec<String> {
+    //直接发送原始的帧，返回服务器的全部回应，直到服务器断开连接。
+    let mut stream = TcpStream::connect(addr).unwrap();
+    for f in frames.iter() {
+        stream.write_all(f).unwrap();
+    }
+    BufReader::new(stream).lines().map(|l|l.unwrap()).collect()
+}
+
+#[test]
+fn client_executes_statements() {
+    let dir = TestDir::new("server-client");
+    let addr = start(&dir);
+    let mut client = Client::connect(&addr, "root", "rpw", Option::Some("d")).unwrap();
+    assert_eq!(client.get_username(), "root");
+    assert_eq!(client.get_using_database(), Option::Some("d".to_string()));
+    match client.execute("select * from t\n") {
+        Result::Ok(DResult::Table(dt)) => {
+            assert_eq!(dt.head, vec!["id", "name", "d"]);
+            assert_eq!(dt.content[0].li.iter().map(|i|i.to_string()).collect::<Vec<String>>(), vec!["1", "a", "2026-10-18"]);
+            assert!(dt.content[1].li[1].is_null() && dt.content[1].li[2].is_null());
+            assert_eq!(dt.content[0].li[2].get_type().to_string(), "date");
+        },
+        other => panic!("{:?}", other.map(|r|r.to_string()))
+    }
+    //执行失败的信息是结果，语法错误是错误。
+    assert_eq!(client.execute("insert into u values (1)\n").unwrap().to_string(), "Table is not exists.");
+    match client.execute("insert t\n") {
+        Result::Err(e) => assert!(e.starts_with("ERROR"), "{}", e),
+        Result::Ok(r) => panic!("{}", r.to_string())
+    }
+    assert_eq!(client.execute("use e\n").unwrap().to_string(), "use e.");
+    assert_eq!(client.get_using_database(), Option::Some("e".to_string()));
+    //两个连接各自有会话。
+    let mut other = Client::connect(&addr, "root", "rpw", Option::None).unwrap();
+    assert_eq!(other.get_using_database(), Option::None);
+    assert_eq!(other.execute("use d\n").unwrap().to_string(), "use d.");
+    other.close();
+    client.close();
+}
+
+#[test]
+fn login_fails_with_wrong_password_or_unusable_database() {
+    let dir = TestDir::new("server-login");
+    let addr = start(&dir);
+    assert!(Client::connect(&addr, "root", "wrong", Option::None).is_err());
+    assert!(Client::connect(&addr, "nobody", "rpw", Option::None).is_err());
+    assert!(Client::connect(&addr, "root", "rpw", Option::Some("missing")).is_err());
+    assert!(Client::connect(&addr, "root", "rpw", Option::Some("d")).is_ok());
+}
+
+#[test]
+fn malformed_frames_get_errors() {
+    let dir = TestDir::new("server-frames");
+    let addr = start(&dir);
+    assert_eq!(exchange(&addr, &[b"{\"op\": \"execute\", \"sql\": \"use d\\n\"}\n"]),
+        vec!["{\"message\":\"Please login first.\",\"type\":\"error\"}"]);
+    assert_eq!(exchange(&addr, &[b"hello\n"]), vec!["{\"message\":\"Wrong frame.\",\"type\":\"error\"}"]);
+    let replies = exchange(&addr, &[b"{\"op\": \"login\", \"user\": \"root\", \"password\": \"rpw\"}\n",
+        b"{\"op\": \"fly\"}\n", b"{\"op\": \"login\", \"user\": \"root\", \"password\": \"rpw\"}\n", b"{\"op\": \"quit\"}\n"]);
+    assert_eq!(replies, vec![
+        "{\"type\":\"login\",\"user\":\"root\",\"using\":null}",
+        "{\"message\":\"Unknown op: fly.\",\"type\":\"error\"}",
+        "{\"message\":\"Already logged in.\",\"type\":\"error\"}",
+        "{\"type\":\"bye\"}"
+    ]);
+}
+
+#[test]
+fn oversized_frame_is_rejected() {
+    let dir = TestDir::new("server-oversized");
+    let addr = start(&dir);
+    let big = vec![b'a'; MAX_FRAME + 1];
+    let replies = exchange(&addr, &[&big]);
+    assert_eq!(replies.len(), 1);
+    assert!(replies[0].contains("\"type\":\"error\"") && replies[0].contains("larger than"), "{}", replies[0]);
+}
+
+#[test]
+fn server_listens_on_localhost_by_default() {
+    let dir = TestDir::new("server-config");
+    let conf = Config::load(dir.dir.join("dba.ini").to_str().unwrap());
+    assert_eq!((conf.host.as_str(), conf.port), ("127.0.0.1", 7070));
+}
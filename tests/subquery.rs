@@ -0,0 +1,67 @@
+extern crate dba;
+mod common;
+use common::{TestDir, session, run, run_all, rows, ids};
+// 子查询
+
+fn prepare(dir:&TestDir) -> dba::engine::engine::Engine {
+    let engine = dir.engine();
+    {
+        let mut s = session(&engine, &["create database d", "use d",
+            "create table s(id integer, cls varchar(8), score integer)", "create table k(name varchar(8))"]);
+        for &(id, cls, score) in [(1, "a", 60), (2, "a", 80), (3, "b", 70), (4, "b", 90), (5, "c", 50)].iter() {
+            run_all(&mut s, &[format!("insert into s values ({}, \"{}\", {})", id, cls, score).as_str()]);
+        }
+        run_all(&mut s, &["insert into k values (\"a\")", "insert into k values (\"c\")"]);
+    }
+    engine
+}
+
+#[test]
+fn uncorrelated_subqueries_give_the_same_result_for_every_row() {
+    let dir = TestDir::new("subquery-uncorrelated");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(ids(rows(&mut s, "select id from s where cls in (select name from k);")), vec!["1", "2", "5"]);
+    assert_eq!(ids(rows(&mut s, "select id from s where score > (select avg(score) from s);")), vec!["2", "4"]);
+    //同一个子查询分别作为exists与标量使用。
+    assert_eq!(ids(rows(&mut s, "select id from s where exists (select name from k where name = \"c\") and cls = (select name from k where name = \"c\");")), vec!["5"]);
+    assert_eq!(run(&mut s, "select id from s where score = (select score from s);"), "Subquery returned more than one row.");
+}
+
+#[test]
+fn correlated_subqueries_run_for_each_row() {
+    let dir = TestDir::new("subquery-correlated");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    //每个班中高于班级平均分的学生；内层不相关的子查询嵌套在相关子查询中。
+    assert_eq!(ids(rows(&mut s, "select id from s as o where o.score > (select avg(score) from s where cls = o.cls);")), vec!["2", "4"]);
+    assert_eq!(ids(rows(&mut s, "select id from s as o where exists (select id from s where cls = o.cls and id != o.id and cls in (select name from k));")), vec!["1", "2"]);
+}
+
+#[test]
+fn subquery_results_do_not_leak_between_statements() {
+    let dir = TestDir::new("subquery-statements");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(ids(rows(&mut s, "select id from s where cls in (select name from k);")), vec!["1", "2", "5"]);
+    run_all(&mut s, &["insert into k values (\"b\")"]);
+    assert_eq!(ids(rows(&mut s, "select id from s where cls in (select name from k);")), vec!["1", "2", "3", "4", "5"]);
+    //update中的子查询在修改之前的记录上计算。
+    run_all(&mut s, &["update s set score = score + 1 where score < (select max(score) from s);"]);
+    assert_eq!(ids(rows(&mut s, "select score from s;")), vec!["61", "81", "71", "90", "51"]);
+    run_all(&mut s, &["delete from s where score = (select min(score) from s);"]);
+    assert_eq!(ids(rows(&mut s, "select id from s;")).len(), 4);
+}
+
+#[test]
+fn scalar_subqueries_in_the_select_list() {
+    let dir = TestDir::new("subquery-goals");
+    let engine = prepare(&dir);
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(rows(&mut s, "select id, (select count(id) from s where cls = o.cls) from s as o where id < 4;"),
+        vec![vec!["1", "2"], vec!["2", "2"], vec!["3", "2"]]);
+    //没有记录时标量子查询的值是null。
+    assert_eq!(rows(&mut s, "select id, (select name from k where name = o.cls) from s as o where id > 2;"),
+        vec![vec!["3", "NULL"], vec!["4", "NULL"], vec!["5", "c"]]);
+    assert_eq!(run(&mut s, "select id, (select name from k) from s"), "Subquery returned more than one row.");
+}
@@ -0,0 +1,65 @@
+extern crate dba;
+mod common;
+use common::{TestDir, session, run, run_all, rows, ids};
+// begin/commit/rollback事务
+
+#[test]
+fn commit_keeps_and_rollback_discards_changes() {
+    let dir = TestDir::new("transaction-commit");
+    {
+        let engine = dir.engine();
+        let mut s = session(&engine, &["create database d", "use d", "create table t(id integer)", "insert into t values (1)"]);
+        run_all(&mut s, &["begin", "insert into t values (2)", "update t set id = 10 where id = 1;"]);
+        assert_eq!(ids(rows(&mut s, "select id from t")), vec!["10", "2"]);
+        run_all(&mut s, &["rollback"]);
+        assert_eq!(ids(rows(&mut s, "select id from t")), vec!["1"]);
+        run_all(&mut s, &["begin", "insert into t values (3)", "delete from t where id = 1;", "commit"]);
+    }
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(ids(rows(&mut s, "select id from t")), vec!["3"]);
+}
+
+#[test]
+fn uncommitted_changes_are_lost_when_the_session_ends() {
+    let dir = TestDir::new("transaction-drop");
+    {
+        let engine = dir.engine();
+        session(&engine, &["create database d", "use d", "create table t(id integer)", "begin", "insert into t values (1)"]);
+    }
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(rows(&mut s, "select id from t").len(), 0);
+}
+
+#[test]
+fn failed_statement_outside_a_transaction_changes_nothing() {
+    let dir = TestDir::new("transaction-autocommit");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d", "create table t(id integer not_null, v integer)",
+        "insert into t values (1, 1)", "insert into t values (2, 2)"]);
+    assert_eq!(run(&mut s, "update t set id = null where v = 2 or v = 1;"), "Field id cannot be null.");
+    assert_eq!(ids(rows(&mut s, "select id from t")), vec!["1", "2"]);
+    //第二行违反unique，第一行的插入也不能留下。
+    run_all(&mut s, &["create table u(id integer unique)", "insert into u values (2)"]);
+    assert!(run(&mut s, "insert into u select id from t").contains("nique"));
+    assert_eq!(ids(rows(&mut s, "select id from u")), vec!["2"]);
+    assert_eq!(run(&mut s, "commit"), "No transaction in progress.");
+    drop(s);
+    drop(engine);
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(ids(rows(&mut s, "select id from t")), vec!["1", "2"]);
+    assert_eq!(ids(rows(&mut s, "select id from u")), vec!["2"]);
+}
+
+#[test]
+fn writes_in_a_transaction_block_other_sessions_until_commit() {
+    let dir = TestDir::new("transaction-lock");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d", "create table t(id integer)", "begin", "insert into t values (1)"]);
+    let mut other = session(&engine, &["use d"]);
+    assert!(run(&mut other, "select id from t").starts_with("Lock wait timeout"));
+    run_all(&mut s, &["commit"]);
+    assert_eq!(ids(rows(&mut other, "select id from t")), vec!["1"]);
+}
@@ -0,0 +1,100 @@
+extern crate dba;
+mod common;
+use std::fs;
+use common::{TestDir, session, run, run_all, rows};
+// 日期时间、text/blob与定点小数类型
+
+#[test]
+fn dates_and_times() {
+    let dir = TestDir::new("types-datetime");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d"]);
+    run_all(&mut s, &["create table e(id integer, d date, t time, ts timestamp)",
+        "insert into e values (1, \"2026-10-18\", \"12:30:00\", \"2026-10-18 12:30:00\")",
+        "insert into e values (2, \"2024-02-28\", \"23:59:59\", \"2024-02-29 00:00:00\")"]);
+    assert_eq!(run(&mut s, "insert into e values (3, \"2024-02-30\", \"12:00:00\", \"2024-02-29 00:00:00\")"), "Wrong value type for field d.");
+    assert_eq!(rows(&mut s, "select id, d + 1, t + 60, ts - 1 from e"),
+        vec![vec!["1", "2026-10-19", "12:31:00", "2026-10-18 12:29:59"], vec!["2", "2024-02-29", "00:00:59", "2024-02-28 23:59:59"]]);
+    assert_eq!(rows(&mut s, "select d - \"2026-01-01\", ts - \"2026-10-18 00:00:00\", d + t from e where id = 1;"),
+        vec![vec!["290", "45000", "2026-10-18 12:30:00"]]);
+    assert_eq!(run(&mut s, "select d + 3000000 from e where id = 1;"), "Date/time value is out of range.");
+    assert_eq!(run(&mut s, "select ts + 9000000000000000000 from e"), "Date/time value is out of range.");
+    assert_eq!(run(&mut s, "select d - 9000000000000000000 from e"), "Date/time value is out of range.");
+    run_all(&mut s, &["create index e_d on e(d)"]);
+    assert_eq!(rows(&mut s, "select id from e where d < \"2025-01-01\""), vec![vec!["2"]]);
+    assert_eq!(rows(&mut s, "select id from e where d = \"2026-10-18\""), vec![vec!["1"]]);
+}
+
+#[test]
+fn type_names_can_still_name_fields() {
+    let dir = TestDir::new("types-names");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d"]);
+    run_all(&mut s, &["create table n(date integer, text varchar(4), time integer, blob integer, decimal integer, timestamp integer)",
+        "insert into n values (1, \"a\", 2, 3, 4, 6)"]);
+    assert_eq!(rows(&mut s, "select date, text, time + 1, decimal from n where timestamp = 6;"), vec![vec!["1", "a", "3", "4"]]);
+}
+
+#[test]
+fn text_and_blob_use_overflow_pages() {
+    let dir = TestDir::new("types-text");
+    let big = "abcdefgh".repeat(20000);  //超过一页
+    {
+        let engine = dir.engine();
+        let mut s = session(&engine, &["create database d", "use d"]);
+        run_all(&mut s, &["create table x(id integer, body text, raw blob)", "insert into x values (1, \"hello\", \"0aff\")",
+            format!("insert into x values (2, \"{}\", null)", big).as_str()]);
+        assert_eq!(run(&mut s, "create index x_b on x(body)"), "Cannot create index on text or blob field body.");
+        assert_eq!(run(&mut s, "insert into x values (3, \"a\", \"xyz\")"), "Wrong value type for field raw.");
+    }
+    let engine = dir.engine();
+    let mut s = session(&engine, &["use d"]);
+    assert_eq!(rows(&mut s, "select id, raw from x where body = \"hello\""), vec![vec!["1", "0aff"]]);
+    assert_eq!(rows(&mut s, "select length(body) from x where id = 2;"), vec![vec!["160000"]]);
+    assert_eq!(rows(&mut s, "select body from x where id = 2;")[0][0], big);
+    //反复修改大的文本，不再使用的溢出页被复用，文件不会一直变大。
+    let size = fs::metadata(dir.path("d.db")).unwrap().len();
+    for i in 0..5 {
+        run_all(&mut s, &["update x set body = \"short\" where id = 2;", format!("update x set body = \"{}{}\" where id = 2;", big, i).as_str()]);
+    }
+    assert!(fs::metadata(dir.path("d.db")).unwrap().len() <= size + 4 * 65536);
+    assert_eq!(rows(&mut s, "select length(body) from x where id = 2;"), vec![vec!["160001"]]);
+    run_all(&mut s, &["delete from x where id = 2;"]);
+    assert_eq!(rows(&mut s, "select id from x"), vec![vec!["1"]]);
+    //短的内容保存在记录中，稍长的内容共用溢出页，许多小的值只占用很少的页。
+    let medium = "0123456789".repeat(20);
+    for i in 0..300 {
+        run_all(&mut s, &[format!("insert into x values ({}, \"v{}\", \"0a0b\")", i + 10, i).as_str(),
+            format!("insert into x values ({}, \"{}{}\", null)", i + 1000, medium, i).as_str()]);
+    }
+    run_all(&mut s, &["commit"]);
+    let pages = fs::metadata(dir.path("d.db")).unwrap().len() / 65536;
+    assert!(pages <= 8, "{} pages", pages);
+    assert_eq!(rows(&mut s, "select body, raw from x where id = 15;"), vec![vec!["v5", "0a0b"]]);
+    assert_eq!(rows(&mut s, "select body from x where id = 1299;")[0][0], format!("{}299", medium));
+    //删除共享溢出页中的值之后，整页不再使用时被复用。
+    run_all(&mut s, &["delete from x where id >= 1000;"]);
+    for i in 0..300 {
+        run_all(&mut s, &[format!("insert into x values ({}, \"{}{}\", null)", i + 2000, medium, i).as_str()]);
+    }
+    run_all(&mut s, &["commit"]);
+    assert_eq!(fs::metadata(dir.path("d.db")).unwrap().len() / 65536, pages);
+    assert_eq!(rows(&mut s, "select length(body) from x where id = 2150;"), vec![vec!["203"]]);
+}
+
+#[test]
+fn decimals_are_exact() {
+    let dir = TestDir::new("types-decimal");
+    let engine = dir.engine();
+    let mut s = session(&engine, &["create database d", "use d"]);
+    run_all(&mut s, &["create table m(id integer, p decimal(10, 2), q numeric(5, 3))",
+        "insert into m values (1, 10.005, 1.2345)", "insert into m values (2, 0.1, 0.2)"]);
+    //按字段的小数位数四舍五入存储，超出精度时报错。
+    assert_eq!(run(&mut s, "insert into m values (3, 123456789.12, 1)"), "Wrong value type for field p.");
+    assert_eq!(rows(&mut s, "select p, q, p + q, p * 3, p / 3 from m"),
+        vec![vec!["10.01", "1.235", "11.245", "30.03", "3.33666667"], vec!["0.10", "0.200", "0.300", "0.30", "0.03333333"]]);
+    assert_eq!(rows(&mut s, "select id from m where p + q = 0.3;"), vec![vec!["2"]]);
+    assert_eq!(rows(&mut s, "select sum(p), avg(p) from m"), vec![vec!["10.11", "5.05500000"]]);
+    run_all(&mut s, &["update m set p = p - 0.01 where id = 1;"]);
+    assert_eq!(rows(&mut s, "select p from m where id = 1;"), vec![vec!["10.00"]]);
+}
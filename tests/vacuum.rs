@@ -0,0 +1,56 @@
+extern crate dba;
+mod common;
+use std::fs;
+use common::{TestDir, root, run, run_all, rows};
+// vacuum整理表的存储与空闲页的复用
+
+fn fill(s:&mut dba::engine::engine::Session, table:&str, count:usize) {
+    //每条记录约1K，300条占用若干页。
+    let pad = "x".repeat(900);
+    for i in 0..count {
+        run_all(s, &[format!("insert into {} values ({}, \"{}\")", table, i, pad).as_str()]);
+    }
+}
+
+#[test]
+fn vacuum_compacts_rows_and_frees_pages() {
+    let dir = TestDir::new("vacuum-compact");
+    let engine = dir.engine();
+    let mut s = root(&engine);
+    run_all(&mut s, &["create database d", "use d", "create table t(id integer, pad varchar(1000))", "create index t_id on t(id)"]);
+    fill(&mut s, "t", 300);
+    run_all(&mut s, &["delete from t where id >= 20;"]);
+    let message = run(&mut s, "vacuum t");
+    assert!(message.starts_with("Vacuum finished: ") && message != "Vacuum finished: 0 page(s) freed.", "{}", message);
+    assert_eq!(run(&mut s, "vacuum t"), "Vacuum finished: 0 page(s) freed.");
+    assert_eq!(run(&mut s, "vacuum u"), "Table u is not exists.");
+    let expected:Vec<Vec<String>> = (0..20).map(|i|vec![i.to_string()]).collect();
+    assert_eq!(rows(&mut s, "select id from t"), expected);
+    assert_eq!(rows(&mut s, "select id from t where id = 7;"), vec![vec!["7"]]);
+    drop(s);
+    drop(engine);
+    let engine = dir.engine();
+    let mut s = root(&engine);
+    run(&mut s, "use d");
+    assert_eq!(rows(&mut s, "select id from t"), expected);
+}
+
+#[test]
+fn freed_pages_are_reused() {
+    let dir = TestDir::new("vacuum-reuse");
+    let engine = dir.engine();
+    let mut s = root(&engine);
+    run_all(&mut s, &["create database d", "use d", "create table t(id integer, pad varchar(1000))"]);
+    fill(&mut s, "t", 300);
+    let size = fs::metadata(dir.path("d.db")).unwrap().len();
+    //删除后整理，空出来的页再次插入时被复用，.db文件不会变大。
+    for _ in 0..2 {
+        run_all(&mut s, &["delete from t", "vacuum"]);
+        fill(&mut s, "t", 300);
+        assert!(fs::metadata(dir.path("d.db")).unwrap().len() <= size);
+    }
+    run_all(&mut s, &["drop table t", "create table u(id integer, pad varchar(1000))"]);
+    fill(&mut s, "u", 300);
+    assert!(fs::metadata(dir.path("d.db")).unwrap().len() <= size);
+    assert_eq!(rows(&mut s, "select id from u where id = 299;"), vec![vec!["299"]]);
+}
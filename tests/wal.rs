@@ -0,0 +1,75 @@
+extern crate dba;
+mod common;
+use std::fs::{self, OpenOptions};
+use std::io::{Write};
+use common::{TestDir, root, run_all, rows};
+use dba::engine::wal::{WriteAheadLog};
+// 预写日志的提交与恢复
+
+fn ids(dir:&TestDir) -> Vec<Vec<String>> {
+    let engine = dir.engine();
+    let mut s = root(&engine);
+    run_all(&mut s, &["use d"]);
+    rows(&mut s, "select id from t")
+}
+
+#[test]
+fn committed_data_survives_reopen() {
+    let dir = TestDir::new("wal-reopen");
+    {
+        let engine = dir.engine();
+        let mut s = root(&engine);
+        run_all(&mut s, &["create database d", "use d", "create table t(id integer)", "insert into t values (1)", "insert into t values (2)"]);
+    }
+    assert_eq!(ids(&dir), vec![vec!["1".to_string()], vec!["2".to_string()]]);
+    assert!(!dir.path("d.wal").exists());
+}
+
+#[test]
+fn complete_log_is_replayed_on_open() {
+    //模拟只修改配置的提交在覆写.dba时中断：日志完整，.dba还是旧的。
+    let dir = TestDir::new("wal-replay");
+    let old;
+    {
+        let engine = dir.engine();
+        let mut s = root(&engine);
+        run_all(&mut s, &["create database d", "use d", "create table t(id integer)", "insert into t values (1)"]);
+        old = fs::read_to_string(dir.path("d.dba")).unwrap();
+        run_all(&mut s, &["create table u(id integer)"]);
+    }
+    let new = fs::read_to_string(dir.path("d.dba")).unwrap();
+    fs::write(dir.path("d.dba"), &old).unwrap();
+    WriteAheadLog::new(dir.path("d.wal").to_str().unwrap().to_string()).write(&[], &new);
+    {
+        let engine = dir.engine();
+        let mut s = root(&engine);
+        run_all(&mut s, &["use d"]);
+        assert_eq!(rows(&mut s, "select * from u").len(), 0);
+    }
+    assert!(!dir.path("d.wal").exists());
+    assert_eq!(ids(&dir), vec![vec!["1".to_string()]]);
+}
+
+#[test]
+fn incomplete_log_is_discarded() {
+    //模拟在写日志时中断：日志没有结束标记，.db与.dba保持提交前的状态。
+    let dir = TestDir::new("wal-discard");
+    {
+        let engine = dir.engine();
+        let mut s = root(&engine);
+        run_all(&mut s, &["create database d", "use d", "create table t(id integer)", "insert into t values (1)"]);
+    }
+    let config = fs::read_to_string(dir.path("d.dba")).unwrap();
+    let wal = dir.path("d.wal");
+    WriteAheadLog::new(wal.to_str().unwrap().to_string()).write(&[(0, vec![0xff_u8; dba::engine::save::PAGE_SIZE])], "{}");
+    let len = fs::metadata(&wal).unwrap().len();
+    OpenOptions::new().write(true).open(&wal).unwrap().set_len(len - 3).unwrap();
+    assert_eq!(ids(&dir), vec![vec!["1".to_string()]]);
+    assert!(!wal.exists());
+    assert_eq!(fs::read_to_string(dir.path("d.dba")).unwrap(), config);
+    //损坏的日志同样被丢弃。
+    let mut f = OpenOptions::new().create(true).write(true).open(&wal).unwrap();
+    f.write_all(b"DBAWAL01garbage").unwrap();
+    drop(f);
+    assert_eq!(ids(&dir), vec![vec!["1".to_string()]]);
+}